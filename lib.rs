@@ -1,18 +1,325 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
+use ink::prelude::{string::String, vec::Vec};
+use ink::primitives::AccountId;
+
+/// PSP22 standard error
+#[derive(Debug, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub enum PSP22Error {
+    Custom(String),
+    InsufficientBalance,
+    InsufficientAllowance,
+    ZeroRecipientAddress,
+    ZeroSenderAddress,
+    SafeTransferCheckFailed(String),
+}
+
+/// Error returned by a PSP22Receiver hook
+#[derive(Debug, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub enum PSP22ReceiverError {
+    TransferRejected(String),
+}
+
+/// PSP22 fungible token interface.
+///
+/// Other contracts can call this token through `ink::contract_ref!(PSP22)`.
+#[ink::trait_definition]
+pub trait PSP22 {
+    #[ink(message)]
+    fn total_supply(&self) -> u128;
+
+    #[ink(message)]
+    fn balance_of(&self, owner: AccountId) -> u128;
+
+    #[ink(message)]
+    fn allowance(&self, owner: AccountId, spender: AccountId) -> u128;
+
+    #[ink(message)]
+    fn transfer(&mut self, to: AccountId, value: u128, data: Vec<u8>) -> Result<(), PSP22Error>;
+
+    #[ink(message)]
+    fn transfer_from(
+        &mut self,
+        from: AccountId,
+        to: AccountId,
+        value: u128,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22Error>;
+
+    #[ink(message)]
+    fn approve(&mut self, spender: AccountId, value: u128) -> Result<(), PSP22Error>;
+
+    #[ink(message)]
+    fn increase_allowance(
+        &mut self,
+        spender: AccountId,
+        delta_value: u128,
+    ) -> Result<(), PSP22Error>;
+
+    #[ink(message)]
+    fn decrease_allowance(
+        &mut self,
+        spender: AccountId,
+        delta_value: u128,
+    ) -> Result<(), PSP22Error>;
+}
+
+/// PSP22Metadata extension
+#[ink::trait_definition]
+pub trait PSP22Metadata {
+    #[ink(message)]
+    fn token_name(&self) -> Option<String>;
+
+    #[ink(message)]
+    fn token_symbol(&self) -> Option<String>;
+
+    #[ink(message)]
+    fn token_decimals(&self) -> u8;
+}
+
+/// Hook implemented by contracts that want to accept PSP22 transfers
+#[ink::trait_definition]
+pub trait PSP22Receiver {
+    #[ink(message)]
+    fn before_received(
+        &mut self,
+        operator: AccountId,
+        from: AccountId,
+        value: u128,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22ReceiverError>;
+}
+
+/// Callback invoked on the spender by `approve_and_call`
+#[ink::trait_definition]
+pub trait PSP22ApprovalReceiver {
+    #[ink(message)]
+    fn on_approval_received(
+        &mut self,
+        owner: AccountId,
+        value: u128,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22ReceiverError>;
+}
+
 #[ink::contract]
 mod token {
-    use ink::prelude::vec::Vec;
+    use crate::{PSP22Error, PSP22Metadata, PSP22ReceiverError, PSP22};
+    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::env::hash::{Blake2x256, Sha2x256};
+    use ink::env::CallFlags;
+    use ink::prelude::{format, string::String, vec::Vec};
     use ink::storage::Mapping;
 
     #[ink(storage)]
     pub struct Token {
         balances: Mapping<AccountId, u128>,
-        allowances: Mapping<(AccountId, AccountId), u128>,
-        blacklist: Mapping<AccountId, bool>,
+        allowances: Mapping<(AccountId, AccountId), Allowance>,
+        periodic_allowances: Mapping<(AccountId, AccountId), PeriodicAllowance>,
+        spender_destinations: Mapping<AllowanceKey, Vec<AccountId>>,
+        session_keys: Mapping<(AccountId, AccountId), SessionKey>,
+        blacklist: Mapping<AccountId, BlacklistEntry>,
         owner: AccountId,
         total_supply: u128,
         paused: bool,
+        name: Option<String>,
+        symbol: Option<String>,
+        decimals: u8,
+        nonces: Mapping<AccountId, u64>,
+        cap: Option<u128>,
+        renounced: bool,
+        minter_quotas: Mapping<AccountId, u128>,
+        admins: Vec<AccountId>,
+        admin_threshold: u32,
+        admin_epoch: u32,
+        proposal_ttl: Timestamp,
+        next_proposal_id: u64,
+        proposals: Mapping<u64, Proposal>,
+        confirmations: Mapping<(u64, AccountId), bool>,
+        timelock_delay: Timestamp,
+        large_mint_threshold: u128,
+        next_operation_id: u64,
+        scheduled: Mapping<u64, ScheduledOperation>,
+        guardian: Option<AccountId>,
+        minting_paused: bool,
+        burning_paused: bool,
+        transfers_paused: bool,
+        approvals_paused: bool,
+        breaker_window: Timestamp,
+        breaker_mint_limit: u128,
+        breaker_transfer_limit: u128,
+        window_start: Timestamp,
+        window_minted: u128,
+        window_transferred: u128,
+        max_transfer_amount: Option<u128>,
+        compliance: Option<AccountId>,
+        tiers: Mapping<AccountId, KycTier>,
+        tier_limits: Mapping<KycTier, TierLimits>,
+        whitelist_mode: bool,
+        whitelist: Mapping<AccountId, bool>,
+        blacklisted_accounts: Mapping<u32, AccountId>,
+        blacklist_index: Mapping<AccountId, u32>,
+        blacklist_count: u32,
+        frozen: Mapping<AccountId, u128>,
+        self_locks: Mapping<AccountId, Timestamp>,
+        audit_log: Mapping<u64, AuditEntry>,
+        audit_log_len: u64,
+        appeals: Mapping<AccountId, Appeal>,
+        travel_rule_threshold: Option<u128>,
+        payments: Mapping<Hash, PaymentRecord>,
+        fee_bps: u16,
+        fee_collector: Option<AccountId>,
+        fee_schedule: Vec<FeeBracket>,
+        savings: Mapping<AccountId, SavingsAccount>,
+        savings_apr_bps: u16,
+        savings_index: u128,
+        savings_index_updated_at: Timestamp,
+        term_deposits: Mapping<u64, TermDeposit>,
+        term_deposits_of: Mapping<AccountId, Vec<u64>>,
+        next_term_deposit_id: u64,
+        term_deposit_apr_bps: u16,
+        early_closure_penalty_bps: u16,
+        demurrage: Option<Demurrage>,
+        demurrage_index: u128,
+        demurrage_index_updated_at: Timestamp,
+        demurrage_index_of: Mapping<AccountId, u128>,
+        demurrage_since: u128,
+        vault_assets: u128,
+        vault_total_shares: u128,
+        vault_shares: Mapping<AccountId, u128>,
+        vault_strategy: Option<AccountId>,
+        vesting_schedules: Mapping<u64, VestingSchedule>,
+        vesting_schedules_of: Mapping<AccountId, Vec<u64>>,
+        next_vesting_id: u64,
+        locked_transfers: Mapping<u64, LockedTransfer>,
+        locked_transfers_sent: Mapping<AccountId, Vec<u64>>,
+        next_locked_transfer_id: u64,
+        streams: Mapping<u64, Stream>,
+        next_stream_id: u64,
+        standing_orders: Mapping<u64, StandingOrder>,
+        standing_orders_of: Mapping<AccountId, Vec<u64>>,
+        next_standing_order_id: u64,
+        subscriptions: Mapping<u64, Subscription>,
+        next_subscription_id: u64,
+        mandates: Mapping<u64, Mandate>,
+        next_mandate_id: u64,
+        payrolls: Mapping<u64, Payroll>,
+        payroll_entries: Mapping<(u64, u32), PayrollEntry>,
+        next_payroll_id: u64,
+        escrows: Mapping<u64, Escrow>,
+        next_escrow_id: u64,
+        payment_requests: Mapping<u64, PaymentRequest>,
+        next_payment_request_id: u64,
+        open_requests: Mapping<(AccountId, u32), u64>,
+        open_request_count: Mapping<AccountId, u32>,
+        open_request_index: Mapping<u64, u32>,
+        bills: Mapping<u64, Bill>,
+        next_bill_id: u64,
+        spent_cheques: Mapping<(AccountId, u64), bool>,
+        gift_cards: Mapping<Hash, GiftCard>,
+        credit_limits: Mapping<AccountId, u128>,
+        debts: Mapping<AccountId, Debt>,
+        overdraft_apr_bps: u16,
+        overdraft_index: u128,
+        overdraft_index_updated_at: Timestamp,
+        collateral: Mapping<AccountId, Balance>,
+        loans: Mapping<AccountId, Debt>,
+        loan_to_value_bps: u16,
+        collateral_price: u128,
+        price_oracle: Option<AccountId>,
+        liquidation_threshold_bps: u16,
+        liquidation_bonus_bps: u16,
+        rate_model: Option<InterestRateModel>,
+        loan_index: u128,
+        loan_index_updated_at: Timestamp,
+        total_loans: u128,
+        total_savings: u128,
+        credit_history: Mapping<AccountId, CreditHistory>,
+        min_credit_score: u16,
+        joint_accounts: Mapping<AccountId, JointAccount>,
+        next_joint_account_id: u64,
+        joint_transfers: Mapping<u64, JointTransfer>,
+        pending_joint_transfers: Mapping<AccountId, Vec<u64>>,
+        next_joint_transfer_id: u64,
+        envelopes: Mapping<(AccountId, String), Envelope>,
+        envelope_names: Mapping<AccountId, Vec<String>>,
+        savings_goals: Mapping<u64, SavingsGoal>,
+        savings_goals_of: Mapping<AccountId, Vec<u64>>,
+        next_savings_goal_id: u64,
+        goal_penalty_bps: u16,
+        incentive_pool: u128,
+        inheritances: Mapping<AccountId, Inheritance>,
+        recovery_configs: Mapping<AccountId, RecoveryConfig>,
+        recoveries: Mapping<AccountId, Recovery>,
+        cashback_merchants: Mapping<AccountId, ()>,
+        cashback_bps: u16,
+        rewards_pool: u128,
+        cashback: Mapping<AccountId, u128>,
+        referral_program: Option<ReferralProgram>,
+        referrals: Mapping<AccountId, Referral>,
+        referral_stats: Mapping<AccountId, ReferralStats>,
+        loyalty_tiers: Vec<LoyaltyTier>,
+        loyalty_volume: Mapping<AccountId, LoyaltyVolume>,
+        merchants: Mapping<AccountId, Merchant>,
+        settlement_fee_bps: u16,
+        settlement_reports: Mapping<(AccountId, u64), SettlementReport>,
+        protected_payments: Mapping<u64, ProtectedPayment>,
+        next_protected_payment_id: u64,
+        dispute_window: Timestamp,
+        causes: Mapping<AccountId, Cause>,
+        matching_pool: u128,
+        matching_ratio_bps: u16,
+        campaigns: Mapping<u64, Campaign>,
+        next_campaign_id: u64,
+        pledges: Mapping<(u64, AccountId), u128>,
+        campaign_pledgers: Mapping<(u64, u32), AccountId>,
+        treasury_balance: u128,
+        treasurer: Option<AccountId>,
+        budgets: Mapping<String, Budget>,
+        budget_names: Vec<String>,
+        spend_proposals: Mapping<u64, SpendProposal>,
+        next_spend_id: u64,
+        grant_budgets: Mapping<u64, String>,
+        insurance_levy_bps: u16,
+        insurance_fund: u128,
+        insurance_payouts: Mapping<u64, InsurancePayout>,
+        insurance_payout_count: u64,
+        last_activity: Mapping<AccountId, Timestamp>,
+        dormancy_rules: Option<DormancyRules>,
+        dormant_since: Mapping<AccountId, Timestamp>,
+        approved_spenders: Mapping<AccountId, Vec<AccountId>>,
+        allowance_owners: Mapping<AccountId, Vec<AccountId>>,
+        min_balance: u128,
+        sweep_dust: bool,
+        payroll_income: Mapping<AccountId, PayrollIncome>,
+        salary_advances: Mapping<AccountId, SalaryAdvance>,
+        salary_advance_bps: u16,
+        salary_advance_fee_bps: u16,
+        current_snapshot: u32,
+        balance_checkpoints: Mapping<(AccountId, u32), Checkpoint>,
+        checkpoint_counts: Mapping<AccountId, u32>,
+        distributions: Mapping<u64, Distribution>,
+        next_distribution_id: u64,
+        dividend_claims: Mapping<(u64, AccountId), ()>,
+        airdrops: Mapping<u64, Airdrop>,
+        next_airdrop_id: u64,
+        airdrop_claims: Mapping<(u64, AccountId), ()>,
+        faucet_amount: u128,
+        faucet_cooldown: Timestamp,
+        faucet_claims: Mapping<AccountId, Timestamp>,
+        bonding_curve: Option<BondingCurve>,
+        curve_supply: u128,
+        curve_reserve: Balance,
+        sale: Option<TokenSale>,
+        sale_round: u32,
+        sale_purchases: Mapping<(u32, AccountId), u128>,
+        swap_pair: Option<SwapPair>,
+        swap_reserve: u128,
+        dvp_trades: Mapping<u64, DvpTrade>,
+        next_dvp_id: u64,
+        htlcs: Mapping<Hash, Htlc>,
     }
 
     /// Transfer event
@@ -23,6 +330,7 @@ mod token {
         #[ink(topic)]
         to: Option<AccountId>,
         value: u128,
+        data: Vec<u8>,
     }
 
     /// Approval event
@@ -35,6 +343,48 @@ mod token {
         value: u128,
     }
 
+    /// `owner` let `spender` move up to `limit` every `period` ms. A limit of 0
+    /// revokes it.
+    #[ink(event)]
+    pub struct PeriodicApproval {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        spender: AccountId,
+        limit: u128,
+        period: Timestamp,
+    }
+
+    /// `owner` limited where `spender` may send its tokens; an empty list lifts
+    /// the restriction
+    #[ink(event)]
+    pub struct SpenderDestinationsUpdated {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        spender: AccountId,
+        destinations: Vec<AccountId>,
+    }
+
+    /// `holder` registered `delegate` as a session key
+    #[ink(event)]
+    pub struct SessionKeyRegistered {
+        #[ink(topic)]
+        holder: AccountId,
+        #[ink(topic)]
+        delegate: AccountId,
+        cap: u128,
+        expires_at: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct SessionKeyRevoked {
+        #[ink(topic)]
+        holder: AccountId,
+        #[ink(topic)]
+        delegate: AccountId,
+    }
+
     /// Pause state changed
     #[ink(event)]
     pub struct Paused {
@@ -49,6 +399,8 @@ mod token {
         account: AccountId,
         #[ink(topic)]
         blacklisted: bool,
+        expires_at: Option<Timestamp>,
+        reason: Option<BlacklistReason>,
     }
 
     /// Ownership transferred
@@ -60,658 +412,17508 @@ mod token {
         new_owner: AccountId,
     }
 
-    #[derive(Debug, PartialEq, Eq)]
-    #[ink::scale_derive(Encode, Decode, TypeInfo)]
-    pub enum Error {
-        InsufficientBalance,
-        InsufficientAllowance,
-        Unauthorized,
-        Overflow,
-        InvalidAmount,
-        ContractPaused,
-        AccountBlacklisted,
-        SelfApproval,
+    /// Summary of a batch mint
+    #[ink(event)]
+    pub struct BatchMinted {
+        recipients: u32,
+        total_amount: u128,
     }
 
-    pub type Result<T> = core::result::Result<T, Error>;
-
-    impl Default for Token {
-        fn default() -> Self {
-            Self::new()
-        }
+    /// A signed off-chain authorization was consumed
+    #[ink(event)]
+    pub struct AuthorizationUsed {
+        #[ink(topic)]
+        authorizer: AccountId,
+        nonce: u64,
     }
 
-    impl Token {
-        #[ink(constructor)]
-        pub fn new() -> Self {
-            Self {
-                balances: Mapping::default(),
-                allowances: Mapping::default(),
-                blacklist: Mapping::default(),
-                owner: Self::env().caller(),
-                total_supply: 0,
-                paused: false,
-            }
-        }
+    /// A relayer executed a signed call on behalf of `signer`
+    #[ink(event)]
+    pub struct MetaTxExecuted {
+        #[ink(topic)]
+        signer: AccountId,
+        #[ink(topic)]
+        relayer: AccountId,
+        nonce: u64,
+        fee: u128,
+    }
 
-        #[inline]
-        fn only_owner(&self) -> Result<()> {
-            if self.env().caller() != self.owner {
-                return Err(Error::Unauthorized);
-            }
-            Ok(())
-        }
+    /// Native currency wrapped into tokens
+    #[ink(event)]
+    pub struct Deposit {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+    }
 
-        #[inline]
-        fn when_not_paused(&self) -> Result<()> {
-            if self.paused {
-                return Err(Error::ContractPaused);
-            }
-            Ok(())
-        }
+    /// Tokens unwrapped back into native currency
+    #[ink(event)]
+    pub struct Withdrawal {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+    }
 
-        #[inline]
-        fn not_blacklisted(&self, account: AccountId) -> Result<()> {
-            if self.blacklist.get(account).unwrap_or(false) {
-                return Err(Error::AccountBlacklisted);
-            }
-            Ok(())
-        }
+    /// Bonding curve set or switched off
+    #[ink(event)]
+    pub struct BondingCurveUpdated {
+        curve: Option<BondingCurve>,
+    }
 
-        #[ink(message)]
-        pub fn mint(&mut self, to: AccountId, amount: u128) -> Result<()> {
-            self.only_owner()?;
-            self.not_blacklisted(to)?;
+    /// `account` bought `amount` from the bonding curve for `cost` native
+    #[ink(event)]
+    pub struct CurveBought {
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+        cost: Balance,
+    }
 
-            if amount == 0 {
-                return Err(Error::InvalidAmount);
-            }
+    /// `account` sold `amount` back to the bonding curve for `proceeds` native
+    #[ink(event)]
+    pub struct CurveSold {
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+        proceeds: Balance,
+    }
 
-            let balance = self.balance_of(to);
-            let new_balance = balance.checked_add(amount).ok_or(Error::Overflow)?;
-            let new_supply = self
-                .total_supply
-                .checked_add(amount)
-                .ok_or(Error::Overflow)?;
+    /// The owner opened sale `round` of `supply` tokens at `rate` per native unit
+    #[ink(event)]
+    pub struct SaleCreated {
+        #[ink(topic)]
+        round: u32,
+        rate: u128,
+        start: Timestamp,
+        end: Timestamp,
+        supply: u128,
+    }
 
-            self.balances.insert(to, &new_balance);
-            self.total_supply = new_supply;
+    /// `buyer` bought `amount` in the sale for `paid` native
+    #[ink(event)]
+    pub struct TokensPurchased {
+        #[ink(topic)]
+        buyer: AccountId,
+        amount: u128,
+        paid: Balance,
+    }
 
-            self.env().emit_event(Transfer {
-                from: None,
-                to: Some(to),
-                value: amount,
-            });
+    /// Unsold tokens of sale `round` went back to the owner
+    #[ink(event)]
+    pub struct SaleUnsoldReclaimed {
+        #[ink(topic)]
+        round: u32,
+        amount: u128,
+    }
 
-            Ok(())
-        }
+    /// The native currency raised in sale `round` went to the owner
+    #[ink(event)]
+    pub struct SaleProceedsWithdrawn {
+        #[ink(topic)]
+        round: u32,
+        amount: Balance,
+    }
 
-        #[ink(message)]
-        pub fn burn(&mut self, amount: u128) -> Result<()> {
-            self.when_not_paused()?;
+    /// Swap pair set or removed
+    #[ink(event)]
+    pub struct SwapPairUpdated {
+        pair: Option<SwapPair>,
+    }
 
-            let from = self.env().caller();
-            self.not_blacklisted(from)?;
+    /// `account` swapped `amount` of this token for `pair_amount` of the pair
+    /// token, or the other way round when `to_pair` is false
+    #[ink(event)]
+    pub struct Swapped {
+        #[ink(topic)]
+        account: AccountId,
+        to_pair: bool,
+        amount: u128,
+        pair_amount: u128,
+    }
 
-            let balance = self.balance_of(from);
+    /// `seller` offered `amount` of this token to `buyer` for `asset_amount` of
+    /// `asset`
+    #[ink(event)]
+    pub struct DvpCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        seller: AccountId,
+        #[ink(topic)]
+        buyer: AccountId,
+        amount: u128,
+        asset: AccountId,
+        asset_amount: u128,
+        deadline: Timestamp,
+    }
 
-            if balance < amount {
-                return Err(Error::InsufficientBalance);
-            }
+    /// `account` locked its leg of trade `id`
+    #[ink(event)]
+    pub struct DvpFunded {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        account: AccountId,
+    }
 
-            if amount == 0 {
-                return Err(Error::InvalidAmount);
-            }
+    /// Both legs of trade `id` delivered
+    #[ink(event)]
+    pub struct DvpSettled {
+        #[ink(topic)]
+        id: u64,
+    }
 
-            let new_balance = balance.checked_sub(amount).ok_or(Error::Overflow)?;
-            let new_supply = self
-                .total_supply
-                .checked_sub(amount)
-                .ok_or(Error::Overflow)?;
+    /// Locked legs of expired trade `id` returned
+    #[ink(event)]
+    pub struct DvpRefunded {
+        #[ink(topic)]
+        id: u64,
+    }
 
-            self.balances.insert(from, &new_balance);
-            self.total_supply = new_supply;
+    /// `sender` locked `amount` for `to` behind `hashlock` until `timelock`
+    #[ink(event)]
+    pub struct HtlcLocked {
+        #[ink(topic)]
+        hashlock: Hash,
+        #[ink(topic)]
+        sender: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        amount: u128,
+        timelock: Timestamp,
+    }
 
-            self.env().emit_event(Transfer {
-                from: Some(from),
-                to: None,
-                value: amount,
-            });
+    /// Lock `hashlock` paid to `to`. `preimage` is published so the other side
+    /// of the swap can claim with it too.
+    #[ink(event)]
+    pub struct HtlcClaimed {
+        #[ink(topic)]
+        hashlock: Hash,
+        #[ink(topic)]
+        to: AccountId,
+        amount: u128,
+        preimage: Vec<u8>,
+    }
 
-            Ok(())
-        }
+    /// Expired lock `hashlock` returned to its sender
+    #[ink(event)]
+    pub struct HtlcRefunded {
+        #[ink(topic)]
+        hashlock: Hash,
+        #[ink(topic)]
+        sender: AccountId,
+        amount: u128,
+    }
 
-        #[ink(message)]
-        pub fn balance_of(&self, account: AccountId) -> u128 {
-            self.balances.get(account).unwrap_or(0)
-        }
+    /// Supply cap set or lowered
+    #[ink(event)]
+    pub struct CapUpdated {
+        cap: u128,
+    }
 
-        #[ink(message)]
-        pub fn transfer(&mut self, to: AccountId, amount: u128) -> Result<()> {
-            self.when_not_paused()?;
+    /// Ownership permanently given up
+    #[ink(event)]
+    pub struct OwnershipRenounced {
+        #[ink(topic)]
+        previous_owner: AccountId,
+    }
 
-            let from = self.env().caller();
-            self.not_blacklisted(from)?;
-            self.not_blacklisted(to)?;
+    /// Remaining mint allowance of a minter changed
+    #[ink(event)]
+    pub struct MinterQuotaUpdated {
+        #[ink(topic)]
+        minter: AccountId,
+        quota: u128,
+    }
 
-            self._transfer(from, to, amount)
-        }
+    /// Faucet amount or cooldown changed; a zero `amount` turns the faucet off
+    #[ink(event)]
+    pub struct FaucetUpdated {
+        amount: u128,
+        cooldown: Timestamp,
+    }
 
-        fn _transfer(&mut self, from: AccountId, to: AccountId, amount: u128) -> Result<()> {
-            if amount == 0 {
-                return Err(Error::InvalidAmount);
-            }
+    /// `account` drew `amount` from the faucet
+    #[ink(event)]
+    pub struct FaucetClaimed {
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+    }
 
-            let from_balance = self.balance_of(from);
+    /// Admin set or threshold reconfigured
+    #[ink(event)]
+    pub struct AdminsUpdated {
+        admins: u32,
+        threshold: u32,
+    }
 
-            if from_balance < amount {
-                return Err(Error::InsufficientBalance);
-            }
+    /// Admin action proposed to the multisig
+    #[ink(event)]
+    pub struct ProposalCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        proposer: AccountId,
+        action: AdminAction,
+        expires_at: Timestamp,
+    }
 
-            let to_balance = self.balance_of(to);
-            let new_to_balance = to_balance.checked_add(amount).ok_or(Error::Overflow)?;
-            let new_from_balance = from_balance.checked_sub(amount).ok_or(Error::Overflow)?;
+    /// An admin confirmed a proposal
+    #[ink(event)]
+    pub struct ProposalConfirmed {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        admin: AccountId,
+        confirmations: u32,
+    }
 
-            self.balances.insert(from, &new_from_balance);
-            self.balances.insert(to, &new_to_balance);
+    /// Proposal reached its threshold and was executed
+    #[ink(event)]
+    pub struct ProposalExecuted {
+        #[ink(topic)]
+        id: u64,
+    }
+
+    /// Timelock delay or large-mint threshold changed
+    #[ink(event)]
+    pub struct TimelockUpdated {
+        delay: Timestamp,
+        large_mint_threshold: u128,
+    }
+
+    /// Admin action queued behind the timelock
+    #[ink(event)]
+    pub struct OperationScheduled {
+        #[ink(topic)]
+        id: u64,
+        action: AdminAction,
+        eta: Timestamp,
+    }
+
+    /// Queued admin action executed after its delay
+    #[ink(event)]
+    pub struct OperationExecuted {
+        #[ink(topic)]
+        id: u64,
+    }
+
+    /// Queued admin action cancelled before execution
+    #[ink(event)]
+    pub struct OperationCancelled {
+        #[ink(topic)]
+        id: u64,
+    }
+
+    /// Emergency pause guardian set or removed
+    #[ink(event)]
+    pub struct GuardianUpdated {
+        guardian: Option<AccountId>,
+    }
+
+    /// One class of operations paused or resumed
+    #[ink(event)]
+    pub struct ScopePaused {
+        #[ink(topic)]
+        scope: PauseScope,
+        paused: bool,
+    }
+
+    /// Circuit breaker window or thresholds changed
+    #[ink(event)]
+    pub struct CircuitBreakerUpdated {
+        window: Timestamp,
+        mint_limit: u128,
+        transfer_limit: u128,
+    }
+
+    /// Volume thresholds exceeded; transfers, burns and minting were paused
+    #[ink(event)]
+    pub struct CircuitBreaker {
+        window_start: Timestamp,
+        minted: u128,
+        transferred: u128,
+    }
+
+    /// Per-transfer limit set or removed
+    #[ink(event)]
+    pub struct MaxTransferAmountUpdated {
+        max_transfer_amount: Option<u128>,
+    }
+
+    /// Compliance officer set or removed
+    #[ink(event)]
+    pub struct ComplianceOfficerUpdated {
+        compliance: Option<AccountId>,
+    }
+
+    /// Account moved to a different KYC tier
+    #[ink(event)]
+    pub struct TierUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        tier: KycTier,
+    }
+
+    /// Limits for a KYC tier changed
+    #[ink(event)]
+    pub struct TierLimitsUpdated {
+        #[ink(topic)]
+        tier: KycTier,
+        limits: TierLimits,
+    }
+
+    /// Whitelist-only transfer mode switched on or off
+    #[ink(event)]
+    pub struct WhitelistModeUpdated {
+        enabled: bool,
+    }
+
+    /// Account whitelist status changed
+    #[ink(event)]
+    pub struct WhitelistUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        whitelisted: bool,
+    }
+
+    /// Frozen part of an account's balance changed
+    #[ink(event)]
+    pub struct BalanceFrozen {
+        #[ink(topic)]
+        account: AccountId,
+        frozen: u128,
+    }
+
+    /// Compliance moved tokens out of an account without its consent
+    #[ink(event)]
+    pub struct Seizure {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        amount: u128,
+    }
+
+    /// Balance of a blacklisted account destroyed
+    #[ink(event)]
+    pub struct BlacklistedFundsWiped {
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+    }
+
+    /// Holder locked their own account, or scheduled it to unlock
+    #[ink(event)]
+    pub struct SelfLockUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        /// `None` while locked until further notice
+        unlock_at: Option<Timestamp>,
+    }
+
+    /// Holder nominated a beneficiary, or removed it (`None`)
+    #[ink(event)]
+    pub struct BeneficiaryUpdated {
+        #[ink(topic)]
+        holder: AccountId,
+        beneficiary: Option<AccountId>,
+        inactivity_period: Timestamp,
+    }
+
+    /// Beneficiary took over the balance of a holder inactive for too long
+    #[ink(event)]
+    pub struct InheritanceClaimed {
+        #[ink(topic)]
+        holder: AccountId,
+        #[ink(topic)]
+        beneficiary: AccountId,
+        amount: u128,
+    }
+
+    /// Holder set the guardians who can recover its balance, or removed them
+    #[ink(event)]
+    pub struct RecoveryGuardiansUpdated {
+        #[ink(topic)]
+        holder: AccountId,
+        guardians: Vec<AccountId>,
+        quorum: u32,
+    }
+
+    /// Guardian backed moving `holder`'s balance to `new_account`
+    #[ink(event)]
+    pub struct RecoveryApproved {
+        #[ink(topic)]
+        holder: AccountId,
+        #[ink(topic)]
+        guardian: AccountId,
+        new_account: AccountId,
+        approvals: u32,
+    }
+
+    /// Guardians reached quorum; the recovery can run from `executable_at`
+    /// unless the holder cancels it
+    #[ink(event)]
+    pub struct RecoveryScheduled {
+        #[ink(topic)]
+        holder: AccountId,
+        new_account: AccountId,
+        executable_at: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct RecoveryCancelled {
+        #[ink(topic)]
+        holder: AccountId,
+    }
+
+    /// `holder`'s balance moved to `new_account` by its guardians
+    #[ink(event)]
+    pub struct AccountRecovered {
+        #[ink(topic)]
+        holder: AccountId,
+        #[ink(topic)]
+        new_account: AccountId,
+        amount: u128,
+    }
+
+    /// Blacklisted account asked compliance to review its blacklisting
+    #[ink(event)]
+    pub struct AppealSubmitted {
+        #[ink(topic)]
+        account: AccountId,
+        memo_hash: Hash,
+    }
+
+    /// Compliance decided on an appeal
+    #[ink(event)]
+    pub struct AppealResolved {
+        #[ink(topic)]
+        account: AccountId,
+        approved: bool,
+    }
+
+    /// Amount above which transfers need a travel-rule reference changed
+    #[ink(event)]
+    pub struct TravelRuleThresholdUpdated {
+        threshold: Option<u128>,
+    }
+
+    /// Payment made against a payment ID, e.g. an invoice number
+    #[ink(event)]
+    pub struct PaymentRecorded {
+        #[ink(topic)]
+        payment_id: Hash,
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        amount: u128,
+        memo: Option<Vec<u8>>,
+    }
+
+    /// Transfer fee rate or collector changed
+    #[ink(event)]
+    pub struct FeeConfigUpdated {
+        fee_bps: u16,
+        fee_collector: Option<AccountId>,
+    }
+
+    /// Fee deducted from a transfer; `to` received `net`
+    #[ink(event)]
+    pub struct FeeCharged {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        gross: u128,
+        net: u128,
+        fee: u128,
+    }
+
+    /// Bracketed fee schedule replaced
+    #[ink(event)]
+    pub struct FeeScheduleUpdated {
+        brackets: Vec<FeeBracket>,
+    }
+
+    /// Loyalty tiers replaced
+    #[ink(event)]
+    pub struct LoyaltyTiersUpdated {
+        tiers: Vec<LoyaltyTier>,
+    }
+
+    /// Tokens moved from `account`'s balance into its savings
+    #[ink(event)]
+    pub struct SavingsDeposit {
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+    }
+
+    /// Tokens moved from `account`'s savings back to its balance
+    #[ink(event)]
+    pub struct SavingsWithdrawal {
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+    }
+
+    /// Savings interest minted and added to `account`'s savings
+    #[ink(event)]
+    pub struct InterestPaid {
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+    }
+
+    /// Annual savings rate changed
+    #[ink(event)]
+    pub struct SavingsRateUpdated {
+        apr_bps: u16,
+    }
+
+    /// Owner set how far `account` may overdraw; 0 closes the credit line
+    #[ink(event)]
+    pub struct CreditLimitUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        limit: u128,
+    }
+
+    /// Tokens lent to `account` to cover a transfer beyond its balance
+    #[ink(event)]
+    pub struct OverdraftDrawn {
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+    }
+
+    /// Tokens burned from `account` to pay down its overdraft debt
+    #[ink(event)]
+    pub struct OverdraftRepaid {
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+    }
+
+    /// Annual overdraft rate changed
+    #[ink(event)]
+    pub struct OverdraftRateUpdated {
+        apr_bps: u16,
+    }
+
+    /// Native currency locked by `account` as loan collateral
+    #[ink(event)]
+    pub struct CollateralDeposited {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+    }
+
+    /// Native collateral returned to `account`
+    #[ink(event)]
+    pub struct CollateralWithdrawn {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+    }
+
+    /// Tokens minted to `account` against its collateral
+    #[ink(event)]
+    pub struct LoanBorrowed {
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+    }
+
+    /// Tokens burned from `account` to pay down its loan
+    #[ink(event)]
+    pub struct LoanRepaid {
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+    }
+
+    /// Share of collateral value that can be borrowed changed
+    #[ink(event)]
+    pub struct LoanToValueUpdated {
+        ltv_bps: u16,
+    }
+
+    /// Tokens one unit of native collateral is worth, scaled by `PRICE_PRECISION`
+    #[ink(event)]
+    pub struct CollateralPriceUpdated {
+        #[ink(topic)]
+        by: AccountId,
+        price: u128,
+    }
+
+    /// Account allowed to update the collateral price changed
+    #[ink(event)]
+    pub struct PriceOracleUpdated {
+        oracle: Option<AccountId>,
+    }
+
+    /// Health factor loans are liquidated below, or liquidation discount, changed
+    #[ink(event)]
+    pub struct LiquidationTermsUpdated {
+        threshold_bps: u16,
+        bonus_bps: u16,
+    }
+
+    /// Interest rate model switched on, changed or switched off (`None`)
+    #[ink(event)]
+    pub struct InterestRateModelUpdated {
+        model: Option<InterestRateModel>,
+    }
+
+    /// Credit score needed to draw an overdraft or take out a loan changed
+    #[ink(event)]
+    pub struct MinCreditScoreUpdated {
+        min_score: u16,
+    }
+
+    /// `liquidator` repaid `borrower`'s loan and took `seized` of its collateral
+    #[ink(event)]
+    pub struct Liquidation {
+        #[ink(topic)]
+        borrower: AccountId,
+        #[ink(topic)]
+        liquidator: AccountId,
+        repaid: u128,
+        seized: Balance,
+        price: u128,
+    }
+
+    /// Term deposit opened; `interest` is paid on closing at or after `matures_at`
+    #[ink(event)]
+    pub struct TermDepositOpened {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        owner: AccountId,
+        amount: u128,
+        interest: u128,
+        matures_at: Timestamp,
+    }
+
+    /// Term deposit closed, paying out `payout` after any early-closure `penalty`
+    #[ink(event)]
+    pub struct TermDepositClosed {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        owner: AccountId,
+        payout: u128,
+        penalty: u128,
+    }
+
+    /// Savings goal opened, met once it holds `target` and `unlock_at` has passed
+    #[ink(event)]
+    pub struct SavingsGoalCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        owner: AccountId,
+        target: Option<u128>,
+        unlock_at: Option<Timestamp>,
+    }
+
+    #[ink(event)]
+    pub struct SavingsGoalDeposit {
+        #[ink(topic)]
+        id: u64,
+        amount: u128,
+    }
+
+    /// `amount` taken out of a savings goal; `penalty` of it went to the
+    /// incentive pool because the goal wasn't met
+    #[ink(event)]
+    pub struct SavingsGoalWithdrawal {
+        #[ink(topic)]
+        id: u64,
+        amount: u128,
+        penalty: u128,
+    }
+
+    /// Penalty on withdrawals from unmet savings goals changed
+    #[ink(event)]
+    pub struct GoalPenaltyUpdated {
+        penalty_bps: u16,
+    }
+
+    /// `merchant` registered or changed where and how often it is settled
+    #[ink(event)]
+    pub struct MerchantRegistered {
+        #[ink(topic)]
+        merchant: AccountId,
+        payout: AccountId,
+        settlement_period: Timestamp,
+    }
+
+    /// `payer` paid `amount` into `merchant`'s settlement balance
+    #[ink(event)]
+    pub struct MerchantPayment {
+        #[ink(topic)]
+        payer: AccountId,
+        #[ink(topic)]
+        merchant: AccountId,
+        amount: u128,
+    }
+
+    /// `merchant`'s settlement balance was paid out to its payout account
+    #[ink(event)]
+    pub struct MerchantSettled {
+        #[ink(topic)]
+        merchant: AccountId,
+        payout: AccountId,
+        gross: u128,
+        fee: u128,
+    }
+
+    #[ink(event)]
+    pub struct SettlementFeeUpdated {
+        fee_bps: u16,
+    }
+
+    /// `payer` paid `payee` with a dispute window ending at `release_at`
+    #[ink(event)]
+    pub struct ProtectedPaymentCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        payer: AccountId,
+        #[ink(topic)]
+        payee: AccountId,
+        amount: u128,
+        release_at: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct PaymentDisputed {
+        #[ink(topic)]
+        id: u64,
+    }
+
+    /// Protected payment `id` went to the payee, or back to the payer if
+    /// `refunded`
+    #[ink(event)]
+    pub struct ProtectedPaymentSettled {
+        #[ink(topic)]
+        id: u64,
+        refunded: bool,
+    }
+
+    #[ink(event)]
+    pub struct DisputeWindowUpdated {
+        window: Timestamp,
+    }
+
+    /// Minimum account balance or dust handling changed
+    #[ink(event)]
+    pub struct MinBalanceUpdated {
+        min_balance: u128,
+        sweep_dust: bool,
+    }
+
+    /// `account` moved its whole balance to `beneficiary` and cleared its storage
+    #[ink(event)]
+    pub struct AccountClosed {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        beneficiary: AccountId,
+        amount: u128,
+    }
+
+    #[ink(event)]
+    pub struct DormancyRulesUpdated {
+        rules: Option<DormancyRules>,
+    }
+
+    /// `account` was flagged dormant; its balance can be swept to the custodian
+    /// from `escheat_after` unless it becomes active again
+    #[ink(event)]
+    pub struct DormancyNotice {
+        #[ink(topic)]
+        account: AccountId,
+        escheat_after: Timestamp,
+    }
+
+    /// A dormant account's balance was swept to the custodian
+    #[ink(event)]
+    pub struct Escheated {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        custodian: AccountId,
+        amount: u128,
+    }
+
+    #[ink(event)]
+    pub struct InsuranceLevyUpdated {
+        levy_bps: u16,
+    }
+
+    /// `amount` from the insurance fund reimbursed `to` after an incident
+    #[ink(event)]
+    pub struct InsurancePaid {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        to: AccountId,
+        amount: u128,
+    }
+
+    #[ink(event)]
+    pub struct TreasuryFunded {
+        #[ink(topic)]
+        from: AccountId,
+        amount: u128,
+    }
+
+    #[ink(event)]
+    pub struct TreasurerUpdated {
+        treasurer: Option<AccountId>,
+    }
+
+    /// Budget line `name` may spend up to `allocated` in total
+    #[ink(event)]
+    pub struct BudgetSet {
+        name: String,
+        allocated: u128,
+    }
+
+    #[ink(event)]
+    pub struct SpendProposed {
+        #[ink(topic)]
+        id: u64,
+        budget: String,
+        #[ink(topic)]
+        to: AccountId,
+        amount: u128,
+    }
+
+    /// Spend proposal `id` was paid out if `approved`, otherwise rejected
+    #[ink(event)]
+    pub struct SpendDecided {
+        #[ink(topic)]
+        id: u64,
+        approved: bool,
+    }
+
+    /// Treasury grant paid out through vesting schedule `id`
+    #[ink(event)]
+    pub struct GrantIssued {
+        #[ink(topic)]
+        id: u64,
+        budget: String,
+    }
+
+    /// Grant `id` was revoked and `unvested` went back to the treasury
+    #[ink(event)]
+    pub struct GrantRevoked {
+        #[ink(topic)]
+        id: u64,
+        unvested: u128,
+    }
+
+    #[ink(event)]
+    pub struct CampaignCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        creator: AccountId,
+        target: u128,
+        deadline: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct Pledged {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        backer: AccountId,
+        amount: u128,
+    }
+
+    /// The creator of campaign `id` collected `amount` after it met its target
+    #[ink(event)]
+    pub struct CampaignClaimed {
+        #[ink(topic)]
+        id: u64,
+        amount: u128,
+    }
+
+    /// `backer` took back its pledge to a campaign that missed its target
+    #[ink(event)]
+    pub struct PledgeRefunded {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        backer: AccountId,
+        amount: u128,
+    }
+
+    /// Owner registered or retired a cause accepting matched donations
+    #[ink(event)]
+    pub struct CauseUpdated {
+        #[ink(topic)]
+        cause: AccountId,
+        active: bool,
+    }
+
+    /// Owner changed the matching ratio or topped up the matching pool
+    #[ink(event)]
+    pub struct MatchingUpdated {
+        ratio_bps: u16,
+        pool: u128,
+    }
+
+    /// `donor` gave `amount` to `cause`, topped up with `matched` from the pool
+    #[ink(event)]
+    pub struct Donation {
+        #[ink(topic)]
+        donor: AccountId,
+        #[ink(topic)]
+        cause: AccountId,
+        amount: u128,
+        matched: u128,
+    }
+
+    /// Cashback rate or a merchant's eligibility changed
+    #[ink(event)]
+    pub struct CashbackConfigUpdated {
+        #[ink(topic)]
+        merchant: Option<AccountId>,
+        cashback_bps: u16,
+        eligible: bool,
+    }
+
+    /// `amount` was added to the rewards pool
+    #[ink(event)]
+    pub struct RewardsPoolFunded {
+        #[ink(topic)]
+        from: AccountId,
+        amount: u128,
+    }
+
+    /// `payer` earned cashback on a payment to `merchant`
+    #[ink(event)]
+    pub struct CashbackAccrued {
+        #[ink(topic)]
+        payer: AccountId,
+        #[ink(topic)]
+        merchant: AccountId,
+        amount: u128,
+    }
+
+    #[ink(event)]
+    pub struct CashbackClaimed {
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+    }
+
+    /// Referral bonus or activity thresholds changed; `None` ends the program
+    #[ink(event)]
+    pub struct ReferralProgramUpdated {
+        program: Option<ReferralProgram>,
+    }
+
+    #[ink(event)]
+    pub struct ReferrerSet {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        referrer: AccountId,
+    }
+
+    /// `account` reached the activity thresholds and `referrer` got its bonus
+    #[ink(event)]
+    pub struct ReferralBonusPaid {
+        #[ink(topic)]
+        referrer: AccountId,
+        #[ink(topic)]
+        account: AccountId,
+        bonus: u128,
+    }
+
+    /// Owner paid `amount` out of the incentive pool
+    #[ink(event)]
+    pub struct IncentivePaid {
+        #[ink(topic)]
+        to: AccountId,
+        amount: u128,
+    }
+
+    /// Term deposit rate or early-closure penalty changed
+    #[ink(event)]
+    pub struct TermDepositTermsUpdated {
+        apr_bps: u16,
+        penalty_bps: u16,
+    }
+
+    /// Demurrage switched on, changed or switched off (`None`)
+    #[ink(event)]
+    pub struct DemurrageUpdated {
+        demurrage: Option<Demurrage>,
+    }
+
+    /// `assets` deposited into the vault for `shares`
+    #[ink(event)]
+    pub struct VaultDeposit {
+        #[ink(topic)]
+        account: AccountId,
+        assets: u128,
+        shares: u128,
+    }
+
+    /// `shares` redeemed from the vault for `assets`
+    #[ink(event)]
+    pub struct VaultWithdrawal {
+        #[ink(topic)]
+        account: AccountId,
+        assets: u128,
+        shares: u128,
+    }
+
+    /// Yield added to the vault, raising the value of every share
+    #[ink(event)]
+    pub struct VaultYieldCredited {
+        #[ink(topic)]
+        from: AccountId,
+        amount: u128,
+    }
+
+    /// Account allowed to credit vault yield changed
+    #[ink(event)]
+    pub struct VaultStrategyUpdated {
+        strategy: Option<AccountId>,
+    }
+
+    /// Tokens set aside for `beneficiary`, vesting from `start` after the cliff
+    #[ink(event)]
+    pub struct VestingScheduleCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        beneficiary: AccountId,
+        total: u128,
+        start: Timestamp,
+        cliff: Timestamp,
+        duration: Timestamp,
+    }
+
+    /// Vested tokens released to `beneficiary`
+    #[ink(event)]
+    pub struct VestedClaimed {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        amount: u128,
+    }
+
+    /// Tokens escrowed for `to`, claimable from `unlock_at`
+    #[ink(event)]
+    pub struct LockedTransferCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        amount: u128,
+        unlock_at: Timestamp,
+    }
+
+    /// Escrowed tokens claimed by their recipient
+    #[ink(event)]
+    pub struct LockedTransferClaimed {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        to: AccountId,
+        amount: u128,
+    }
+
+    /// Payment stream of `deposit` from `sender` to `recipient` over `start..end`
+    #[ink(event)]
+    pub struct StreamCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        sender: AccountId,
+        #[ink(topic)]
+        recipient: AccountId,
+        deposit: u128,
+        start: Timestamp,
+        end: Timestamp,
+    }
+
+    /// Streamed tokens withdrawn by the recipient
+    #[ink(event)]
+    pub struct StreamWithdrawal {
+        #[ink(topic)]
+        id: u64,
+        amount: u128,
+    }
+
+    /// Stream stopped; the recipient got what had streamed, the sender the rest
+    #[ink(event)]
+    pub struct StreamCancelled {
+        #[ink(topic)]
+        id: u64,
+        recipient_amount: u128,
+        sender_amount: u128,
+    }
+
+    /// Recurring transfer set up by `payer`
+    #[ink(event)]
+    pub struct StandingOrderCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        payer: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        amount: u128,
+        first_due: Timestamp,
+        interval: Timestamp,
+        end: Timestamp,
+    }
+
+    /// A due standing order payment was made
+    #[ink(event)]
+    pub struct StandingOrderExecuted {
+        #[ink(topic)]
+        id: u64,
+        amount: u128,
+        due: Timestamp,
+    }
+
+    /// A due standing order payment failed and was skipped
+    #[ink(event)]
+    pub struct StandingOrderFailed {
+        #[ink(topic)]
+        id: u64,
+        due: Timestamp,
+        error: Error,
+    }
+
+    /// Standing order cancelled by its payer
+    #[ink(event)]
+    pub struct StandingOrderCancelled {
+        #[ink(topic)]
+        id: u64,
+    }
+
+    /// `subscriber` authorized `merchant` to pull `amount` once per `period`
+    #[ink(event)]
+    pub struct SubscriptionCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        subscriber: AccountId,
+        #[ink(topic)]
+        merchant: AccountId,
+        amount: u128,
+        period: Timestamp,
+    }
+
+    /// Merchant pulled a subscription payment
+    #[ink(event)]
+    pub struct SubscriptionCharged {
+        #[ink(topic)]
+        id: u64,
+        amount: u128,
+        next_charge_at: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct SubscriptionCancelled {
+        #[ink(topic)]
+        id: u64,
+    }
+
+    /// `payer` authorized `payee` to collect up to `limit` per direct debit
+    #[ink(event)]
+    pub struct DirectDebitMandateCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        payer: AccountId,
+        #[ink(topic)]
+        payee: AccountId,
+        limit: u128,
+    }
+
+    #[ink(event)]
+    pub struct DirectDebitMandateRevoked {
+        #[ink(topic)]
+        id: u64,
+    }
+
+    /// Direct debit collected under mandate `id`
+    #[ink(event)]
+    pub struct DirectDebitCollected {
+        #[ink(topic)]
+        id: u64,
+        amount: u128,
+    }
+
+    /// Direct debit returned unpaid because the payer's balance was too low
+    #[ink(event)]
+    pub struct DirectDebitDishonoured {
+        #[ink(topic)]
+        id: u64,
+        amount: u128,
+    }
+
+    /// Payroll run drafted by `employer`
+    #[ink(event)]
+    pub struct PayrollCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        employer: AccountId,
+    }
+
+    /// Payroll run funded with its total and ready to pay out
+    #[ink(event)]
+    pub struct PayrollFunded {
+        #[ink(topic)]
+        id: u64,
+        total: u128,
+        payees: u32,
+    }
+
+    /// Payment to a payroll entry failed; its amount is refunded on completion
+    #[ink(event)]
+    pub struct PayrollPaymentFailed {
+        #[ink(topic)]
+        id: u64,
+        index: u32,
+        payee: AccountId,
+        error: Error,
+    }
+
+    /// Every entry of the run has been processed
+    #[ink(event)]
+    pub struct PayrollCompleted {
+        #[ink(topic)]
+        id: u64,
+        paid: u128,
+        refunded: u128,
+    }
+
+    /// The owner funded dividend distribution `id` against balances at
+    /// `snapshot`
+    #[ink(event)]
+    pub struct DistributionCreated {
+        #[ink(topic)]
+        id: u64,
+        snapshot: u32,
+        amount: u128,
+    }
+
+    /// `account` claimed its `amount` share of distribution `id`
+    #[ink(event)]
+    pub struct DividendClaimed {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+    }
+
+    /// The owner funded airdrop `id` with `total` for the pairs under `root`
+    #[ink(event)]
+    pub struct AirdropCreated {
+        #[ink(topic)]
+        id: u64,
+        root: Hash,
+        total: u128,
+        expires_at: Timestamp,
+    }
+
+    /// `account` claimed `amount` from airdrop `id`
+    #[ink(event)]
+    pub struct AirdropClaimed {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+    }
+
+    /// The unclaimed `amount` of expired airdrop `id` went to the treasury
+    #[ink(event)]
+    pub struct AirdropReclaimed {
+        #[ink(topic)]
+        id: u64,
+        amount: u128,
+    }
+
+    /// Salary advance limit or fee changed
+    #[ink(event)]
+    pub struct SalaryAdvanceTermsUpdated {
+        advance_bps: u16,
+        fee_bps: u16,
+    }
+
+    /// `account` drew `amount` against its next payroll payment, owing `fee` on
+    /// top
+    #[ink(event)]
+    pub struct SalaryAdvanceDrawn {
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+        fee: u128,
+    }
+
+    /// `amount` of the salary advance owed by `account` was recovered from a
+    /// payroll payment
+    #[ink(event)]
+    pub struct SalaryAdvanceRepaid {
+        #[ink(topic)]
+        account: AccountId,
+        amount: u128,
+        outstanding: u128,
+    }
+
+    /// `payer` locked `amount` for `payee`, with `arbiter` to settle disputes
+    #[ink(event)]
+    pub struct EscrowCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        payer: AccountId,
+        #[ink(topic)]
+        payee: AccountId,
+        arbiter: AccountId,
+        amount: u128,
+        deadline: Timestamp,
+    }
+
+    /// Escrowed funds paid to the payee
+    #[ink(event)]
+    pub struct EscrowReleased {
+        #[ink(topic)]
+        id: u64,
+        amount: u128,
+    }
+
+    /// Escrowed funds returned to the payer
+    #[ink(event)]
+    pub struct EscrowRefunded {
+        #[ink(topic)]
+        id: u64,
+        amount: u128,
+    }
+
+    /// Milestone `index` of escrow `id` approved and paid to the payee
+    #[ink(event)]
+    pub struct MilestoneReleased {
+        #[ink(topic)]
+        id: u64,
+        index: u32,
+        amount: u128,
+    }
+
+    /// `merchant` asked `payer` for `amount`, payable until `expiry`
+    #[ink(event)]
+    pub struct PaymentRequestCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        merchant: AccountId,
+        #[ink(topic)]
+        payer: AccountId,
+        amount: u128,
+        expiry: Timestamp,
+    }
+
+    /// Payment request settled; carries the memo for reconciliation
+    #[ink(event)]
+    pub struct PaymentRequestPaid {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        merchant: AccountId,
+        #[ink(topic)]
+        payer: AccountId,
+        amount: u128,
+        memo: Vec<u8>,
+    }
+
+    #[ink(event)]
+    pub struct PaymentRequestCancelled {
+        #[ink(topic)]
+        id: u64,
+    }
+
+    /// Part or all of payment `payment_id` returned by its payee
+    #[ink(event)]
+    pub struct PaymentRefunded {
+        #[ink(topic)]
+        payment_id: Hash,
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        amount: u128,
+        total_refunded: u128,
+    }
+
+    /// Bill for `payee` split between participants, payable until `expiry`
+    #[ink(event)]
+    pub struct BillCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        initiator: AccountId,
+        #[ink(topic)]
+        payee: AccountId,
+        total: u128,
+        expiry: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct BillSharePaid {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        participant: AccountId,
+        amount: u128,
+    }
+
+    /// Every share was paid and the total released to the payee
+    #[ink(event)]
+    pub struct BillSettled {
+        #[ink(topic)]
+        id: u64,
+        total: u128,
+    }
+
+    /// Bill expired unpaid; collected shares were returned
+    #[ink(event)]
+    pub struct BillRefunded {
+        #[ink(topic)]
+        id: u64,
+        refunded: u128,
+    }
+
+    /// Cheque `nonce` of `payer` paid to `payee`
+    #[ink(event)]
+    pub struct ChequeCashed {
+        #[ink(topic)]
+        payer: AccountId,
+        #[ink(topic)]
+        payee: AccountId,
+        nonce: u64,
+        amount: u128,
+    }
+
+    /// Stop-payment on cheque `nonce` of `payer`
+    #[ink(event)]
+    pub struct ChequeCancelled {
+        #[ink(topic)]
+        payer: AccountId,
+        nonce: u64,
+    }
+
+    /// Gift card funded by `issuer`, redeemable with the preimage of `code_hash`
+    #[ink(event)]
+    pub struct GiftCardIssued {
+        #[ink(topic)]
+        code_hash: Hash,
+        #[ink(topic)]
+        issuer: AccountId,
+        amount: u128,
+        expiry: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct GiftCardRedeemed {
+        #[ink(topic)]
+        code_hash: Hash,
+        #[ink(topic)]
+        recipient: AccountId,
+        amount: u128,
+    }
+
+    /// Expired gift card returned to its issuer
+    #[ink(event)]
+    pub struct GiftCardReclaimed {
+        #[ink(topic)]
+        code_hash: Hash,
+        #[ink(topic)]
+        issuer: AccountId,
+        amount: u128,
+    }
+
+    /// Joint account `joint` opened; transfers above `threshold` need
+    /// `approvals_required` of the owners
+    #[ink(event)]
+    pub struct JointAccountCreated {
+        #[ink(topic)]
+        joint: AccountId,
+        owners: Vec<AccountId>,
+        approvals_required: u32,
+        threshold: u128,
+    }
+
+    /// Co-owner `proposer` asked to move `amount` out of joint account `joint`
+    #[ink(event)]
+    pub struct JointTransferProposed {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        joint: AccountId,
+        proposer: AccountId,
+        to: AccountId,
+        amount: u128,
+    }
+
+    #[ink(event)]
+    pub struct JointTransferApproved {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        owner: AccountId,
+        approvals: u32,
+    }
+
+    #[ink(event)]
+    pub struct JointTransferExecuted {
+        #[ink(topic)]
+        id: u64,
+    }
+
+    #[ink(event)]
+    pub struct JointTransferCancelled {
+        #[ink(topic)]
+        id: u64,
+    }
+
+    /// Tokens moved from `account`'s balance into envelope `name`
+    #[ink(event)]
+    pub struct EnvelopeFunded {
+        #[ink(topic)]
+        account: AccountId,
+        name: String,
+        amount: u128,
+    }
+
+    /// Tokens moved from envelope `name` back to `account`'s balance
+    #[ink(event)]
+    pub struct EnvelopeReleased {
+        #[ink(topic)]
+        account: AccountId,
+        name: String,
+        amount: u128,
+    }
+
+    /// Tokens moved between two of `account`'s envelopes
+    #[ink(event)]
+    pub struct EnvelopeMoved {
+        #[ink(topic)]
+        account: AccountId,
+        from: String,
+        to: String,
+        amount: u128,
+    }
+
+    /// Nothing can leave envelope `name` before `locked_until`
+    #[ink(event)]
+    pub struct EnvelopeLocked {
+        #[ink(topic)]
+        account: AccountId,
+        name: String,
+        locked_until: Timestamp,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        InsufficientBalance,
+        InsufficientAllowance,
+        Unauthorized,
+        Overflow,
+        InvalidAmount,
+        ContractPaused,
+        AccountBlacklisted,
+        SelfApproval,
+        SafeTransferCheckFailed,
+        InvalidSignature,
+        SignatureExpired,
+        AuthorizationNotYetValid,
+        InvalidNonce,
+        NotAContract,
+        CallbackFailed,
+        NativeTransferFailed,
+        CapExceeded,
+        DuplicateAllocation,
+        OwnershipRenounced,
+        MinterQuotaExceeded,
+        MultisigRequired,
+        InvalidThreshold,
+        ProposalNotFound,
+        ProposalExpired,
+        AlreadyConfirmed,
+        TimelockRequired,
+        OperationNotFound,
+        OperationNotReady,
+        UpgradeFailed,
+        TransferAmountExceeded,
+        TierLimitExceeded,
+        NotWhitelisted,
+        BlacklistEntryActive,
+        NotBlacklisted,
+        AccountLocked,
+        AppealPending,
+        AppealNotFound,
+        TravelRuleMemoRequired,
+        MemoTooLong,
+        InsufficientSavings,
+        TermDepositNotFound,
+        TooManyTermDeposits,
+        InsufficientShares,
+        TooManyVestingSchedules,
+        NothingToClaim,
+        LockedTransferNotFound,
+        TransferStillLocked,
+        TooManyLockedTransfers,
+        StreamNotFound,
+        StandingOrderNotFound,
+        OrderNotDue,
+        TooManyStandingOrders,
+        SubscriptionNotFound,
+        ChargeTooEarly,
+        MandateNotFound,
+        MandateLimitExceeded,
+        PayrollNotFound,
+        InvalidPayrollStatus,
+        EscrowNotFound,
+        EscrowNotExpired,
+        InvalidMilestone,
+        PaymentRequestNotFound,
+        PaymentRequestExpired,
+        PaymentRequestSettled,
+        RefundExceedsPayment,
+        PaymentNotFound,
+        BillNotFound,
+        BillExpired,
+        BillNotExpired,
+        ShareAlreadyPaid,
+        ChequeSpent,
+        GiftCardExists,
+        GiftCardNotFound,
+        GiftCardExpired,
+        GiftCardNotExpired,
+        NoDebt,
+        InsufficientCollateral,
+        NoLoan,
+        PositionHealthy,
+        RateModelActive,
+        CreditScoreTooLow,
+        JointAccountNotFound,
+        NotJointOwner,
+        DuplicateOwner,
+        JointApprovalRequired,
+        JointTransferNotFound,
+        TooManyJointTransfers,
+        InvalidEnvelopeName,
+        EnvelopeNotFound,
+        EnvelopeLocked,
+        TooManyEnvelopes,
+        SavingsGoalNotFound,
+        TooManySavingsGoals,
+        NoBeneficiary,
+        HolderStillActive,
+        DuplicateGuardian,
+        NotRecoveryGuardian,
+        RecoveryNotFound,
+        RecoveryNotReady,
+        SelfReferral,
+        MerchantNotFound,
+        SettlementNotDue,
+        ProtectedPaymentNotFound,
+        DisputeWindowOpen,
+        DisputeWindowClosed,
+        PaymentDisputed,
+        CauseNotFound,
+        CampaignNotFound,
+        CampaignEnded,
+        CampaignNotEnded,
+        TargetNotMet,
+        TargetMet,
+        InvalidBudgetName,
+        TooManyBudgets,
+        BudgetNotFound,
+        BudgetExceeded,
+        SpendNotFound,
+        GrantNotFound,
+        AccountNotDormant,
+        NoticePeriodActive,
+        DebtOutstanding,
+        BelowMinimumBalance,
+        NoPayrollIncome,
+        SalaryAdvanceLimitExceeded,
+        DistributionNotFound,
+        DividendAlreadyClaimed,
+        AirdropNotFound,
+        AirdropExpired,
+        AirdropNotExpired,
+        AirdropAlreadyClaimed,
+        InvalidMerkleProof,
+        FaucetDisabled,
+        FaucetCooldown,
+        BondingCurveDisabled,
+        BondingCurveInUse,
+        InsufficientPayment,
+        SlippageExceeded,
+        SaleNotFound,
+        SaleNotActive,
+        SaleNotEnded,
+        SaleInProgress,
+        SaleCapExceeded,
+        SwapNotConfigured,
+        ExternalTransferFailed,
+        DvpNotFound,
+        DvpExpired,
+        DvpNotExpired,
+        HtlcExists,
+        HtlcNotFound,
+        HtlcExpired,
+        HtlcNotExpired,
+        ReferrerAlreadySet,
+        InvalidExpiry,
+        DestinationNotAllowed,
+        SessionKeyNotFound,
+        SessionKeyExpired,
+        SessionCapExceeded,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// Highest transfer fee the owner can set, in basis points (10%)
+    pub const MAX_FEE_BPS: u16 = 1_000;
+
+    /// Most brackets a fee schedule can have
+    pub const MAX_FEE_BRACKETS: usize = 16;
+
+    /// Most loyalty tiers the owner can define
+    pub const MAX_LOYALTY_TIERS: usize = 8;
+
+    /// Length of a loyalty volume window (30 days)
+    pub const LOYALTY_WINDOW: Timestamp = 30 * 24 * 60 * 60 * 1000;
+
+    /// Longest memo accepted by `transfer_with_memo`
+    pub const MAX_MEMO_LEN: usize = 256;
+
+    /// Number of most recent entries kept in the admin audit log
+    pub const AUDIT_LOG_CAPACITY: u64 = 1024;
+
+    /// Highest savings APR the owner can set, in basis points (20%)
+    pub const MAX_SAVINGS_APR_BPS: u16 = 2_000;
+
+    /// Highest overdraft APR the owner can set, in basis points (30%)
+    pub const MAX_OVERDRAFT_APR_BPS: u16 = 3_000;
+
+    /// Highest loan-to-value ratio the owner can set, in basis points (90%)
+    pub const MAX_LOAN_TO_VALUE_BPS: u16 = 9_000;
+
+    /// Highest liquidation discount the owner can set, in basis points (20%)
+    pub const MAX_LIQUIDATION_BONUS_BPS: u16 = 2_000;
+
+    /// Highest loan APR an interest rate model can reach, in basis points (50%)
+    pub const MAX_LOAN_APR_BPS: u16 = 5_000;
+
+    /// Highest credit score an account can have
+    pub const MAX_CREDIT_SCORE: u16 = 1_000;
+
+    /// Fixed-point scale of the collateral price; this price values native
+    /// collateral 1:1 in tokens
+    pub const PRICE_PRECISION: u128 = 1_000_000_000;
+
+    /// Highest demurrage rate the owner can set, in basis points per year (10%)
+    pub const MAX_DEMURRAGE_BPS: u16 = 1_000;
+
+    /// Most open term deposits an account can hold
+    pub const MAX_TERM_DEPOSITS: usize = 16;
+
+    /// Most savings goals an account can have open
+    pub const MAX_SAVINGS_GOALS: usize = 16;
+
+    /// Most vesting schedules a beneficiary can have at once
+    pub const MAX_VESTING_SCHEDULES: usize = 16;
+
+    /// Most unclaimed locked transfers a sender can have at once
+    pub const MAX_LOCKED_TRANSFERS: usize = 16;
+
+    /// Most standing orders a payer can have at once
+    pub const MAX_STANDING_ORDERS: usize = 16;
+
+    /// Most milestones an escrow can be split into
+    pub const MAX_MILESTONES: usize = 16;
+
+    /// Most participants a bill can be split between
+    pub const MAX_BILL_PARTICIPANTS: usize = 16;
+
+    /// Most owners a joint account can have
+    pub const MAX_JOINT_OWNERS: usize = 16;
+
+    /// Most transfers awaiting approval on a joint account at once
+    pub const MAX_PENDING_JOINT_TRANSFERS: usize = 16;
+
+    /// Most envelopes an account can split its balance into
+    pub const MAX_ENVELOPES: usize = 16;
+
+    /// Longest envelope name, in bytes
+    pub const MAX_ENVELOPE_NAME_LEN: usize = 32;
+
+    /// Most payroll entries uploaded or paid in a single call
+    pub const MAX_PAYROLL_CHUNK: u32 = 50;
+
+    /// Highest share of the last payroll payment the owner can let accounts draw
+    /// as an advance, in basis points (50%)
+    pub const MAX_SALARY_ADVANCE_BPS: u16 = 5_000;
+
+    /// Highest salary advance fee the owner can set, in basis points (5%)
+    pub const MAX_SALARY_ADVANCE_FEE_BPS: u16 = 500;
+
+    /// Scale of bonding curve prices, so a token unit can cost a fraction of a
+    /// native unit
+    pub const CURVE_PRICE_SCALE: u128 = 1_000_000;
+
+    /// Longest Merkle proof accepted by `claim_airdrop`
+    pub const MAX_MERKLE_PROOF_LEN: usize = 32;
+
+    /// Payroll payments an account must have received to take a salary advance
+    pub const MIN_PAYROLL_PAYMENTS: u32 = 2;
+
+    /// Longest time since the last payroll payment for an account to still take
+    /// a salary advance (35 days)
+    pub const MAX_PAYROLL_GAP: Timestamp = 35 * 24 * 60 * 60 * 1000;
+
+    /// Length of a year for interest accrual, in milliseconds
+    pub const YEAR: Timestamp = 365 * 24 * 60 * 60 * 1000;
+
+    /// Shortest delay a self-locked holder can unlock with, so a stolen key can't
+    /// simply undo the lock
+    pub const MIN_SELF_UNLOCK_DELAY: Timestamp = 24 * 60 * 60 * 1000;
+
+    /// Most destinations an owner can allow a single spender
+    pub const MAX_SPENDER_DESTINATIONS: usize = 16;
+
+    /// Dispute window of new protected payments until the owner changes it (7 days)
+    pub const DEFAULT_DISPUTE_WINDOW: Timestamp = 7 * 24 * 60 * 60 * 1000;
+
+    /// Longest dispute window the owner can set (90 days)
+    pub const MAX_DISPUTE_WINDOW: Timestamp = 90 * 24 * 60 * 60 * 1000;
+
+    /// Allowances tracked per account and side for `close_account` to clear
+    pub const MAX_TRACKED_ALLOWANCES: usize = 64;
+
+    /// Shortest dormancy period the owner can set (1 year)
+    pub const MIN_DORMANCY_PERIOD: Timestamp = YEAR;
+
+    /// Highest insurance levy on transfers, in basis points (1%)
+    pub const MAX_INSURANCE_LEVY_BPS: u16 = 100;
+
+    /// Most budget lines the treasury can have
+    pub const MAX_BUDGET_LINES: usize = 32;
+
+    /// Longest budget line name, in bytes
+    pub const MAX_BUDGET_NAME_LEN: usize = 32;
+
+    /// Highest donation matching ratio, in basis points (5:1)
+    pub const MAX_MATCHING_RATIO_BPS: u16 = 50_000;
+
+    /// Settlement reports cover one day each
+    pub const SETTLEMENT_REPORT_PERIOD: Timestamp = 24 * 60 * 60 * 1000;
+
+    /// Highest cashback rate on merchant payments (10%)
+    pub const MAX_CASHBACK_BPS: u16 = 1_000;
+
+    /// Shortest inactivity period a holder can give its beneficiary (30 days)
+    pub const MIN_INACTIVITY_PERIOD: Timestamp = 30 * 24 * 60 * 60 * 1000;
+
+    /// Most recovery guardians a holder can appoint
+    pub const MAX_RECOVERY_GUARDIANS: usize = 16;
+
+    /// Time the holder has to cancel a recovery its guardians agreed on (3 days)
+    pub const RECOVERY_DELAY: Timestamp = 3 * 24 * 60 * 60 * 1000;
+
+    impl From<Error> for PSP22Error {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InsufficientBalance => PSP22Error::InsufficientBalance,
+                Error::InsufficientAllowance => PSP22Error::InsufficientAllowance,
+                Error::SafeTransferCheckFailed => PSP22Error::SafeTransferCheckFailed(
+                    String::from("recipient contract rejected the transfer"),
+                ),
+                other => PSP22Error::Custom(format!("{:?}", other)),
+            }
+        }
+    }
+
+    /// Signature over an off-chain authorization, by key type
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Signature {
+        Ecdsa([u8; 65]),
+        Sr25519([u8; 64]),
+    }
+
+    /// Token operation a signer can delegate to a relayer
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum MetaCall {
+        Transfer { to: AccountId, amount: u128 },
+        Approve { spender: AccountId, amount: u128 },
+        Burn { amount: u128 },
+    }
+
+    /// Class of operations that can be paused independently of the global switch
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum PauseScope {
+        Minting,
+        Burning,
+        Transfers,
+        Approvals,
+    }
+
+    /// Kind of privileged operation recorded in the audit log
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum AuditOperation {
+        Mint,
+        Pause,
+        Unpause,
+        Blacklist,
+        Unblacklist,
+        Freeze,
+        Seize,
+        Wipe,
+        Ownership,
+        Upgrade,
+        Config,
+    }
+
+    /// One audit log record; `params` are the SCALE-encoded operation arguments
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct AuditEntry {
+        pub caller: AccountId,
+        pub timestamp: Timestamp,
+        pub operation: AuditOperation,
+        pub params: Vec<u8>,
+    }
+
+    /// Allowance granted by an owner to a spender. Past `expires_at` it counts as
+    /// zero.
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Allowance {
+        pub value: u128,
+        pub expires_at: Option<Timestamp>,
+    }
+
+    /// Owner and spender of an allowance
+    pub type AllowanceKey = (AccountId, AccountId);
+
+    /// Allowance of `limit` per `period` ms that renews by itself. `spent` counts
+    /// what the spender used in the period starting at `period_start`.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct PeriodicAllowance {
+        pub limit: u128,
+        pub period: Timestamp,
+        pub period_start: Timestamp,
+        pub spent: u128,
+    }
+
+    /// Hot key that may send up to `cap` of a holder's tokens in total, only to
+    /// `destinations` (anywhere if empty), until `expires_at`
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct SessionKey {
+        pub cap: u128,
+        pub spent: u128,
+        pub destinations: Vec<AccountId>,
+        pub expires_at: Timestamp,
+    }
+
+    /// Payment held by the contract until `release_at`, so the payer can dispute
+    /// it first. A disputed payment waits for the compliance officer's ruling.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ProtectedPayment {
+        pub payer: AccountId,
+        pub payee: AccountId,
+        pub amount: u128,
+        pub release_at: Timestamp,
+        pub disputed: bool,
+    }
+
+    /// Accounts without activity for `dormancy_period` can be flagged dormant,
+    /// and swept to `custodian` once `notice_period` has passed after that
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct DormancyRules {
+        pub dormancy_period: Timestamp,
+        pub notice_period: Timestamp,
+        pub custodian: AccountId,
+    }
+
+    /// Reimbursement paid out of the insurance fund
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct InsurancePayout {
+        pub to: AccountId,
+        pub amount: u128,
+        pub paid_at: Timestamp,
+    }
+
+    /// Treasury budget line: what it may spend in total and what it spent
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Budget {
+        pub allocated: u128,
+        pub spent: u128,
+    }
+
+    /// Treasury payment waiting for the treasurer's approval
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct SpendProposal {
+        pub budget: String,
+        pub to: AccountId,
+        pub amount: u128,
+    }
+
+    /// Crowdfunding campaign: pledges are held by the contract until the
+    /// `deadline`, then go to the creator if they reach `target` or back to the
+    /// backers otherwise
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Campaign {
+        pub creator: AccountId,
+        pub target: u128,
+        pub deadline: Timestamp,
+        pub pledged: u128,
+        pub backers: u32,
+        pub claimed: bool,
+    }
+
+    /// Cause accepting donations, with what it received so far. Retired causes
+    /// keep their totals.
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Cause {
+        pub active: bool,
+        pub donated: u128,
+        pub matched: u128,
+    }
+
+    /// Payments to a merchant wait in `pending` until settled to `payout`, on
+    /// demand or by anyone once `settlement_period` (if non-zero) has passed
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Merchant {
+        pub payout: AccountId,
+        pub settlement_period: Timestamp,
+        pub last_settled_at: Timestamp,
+        pub pending: u128,
+    }
+
+    /// A merchant's settlements within one `SETTLEMENT_REPORT_PERIOD`
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct SettlementReport {
+        pub settlements: u32,
+        pub gross: u128,
+        pub fees: u128,
+    }
+
+    /// Referrers earn `bonus` from the rewards pool once an account they referred
+    /// has made `min_transfers` transfers worth `min_volume` in total
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ReferralProgram {
+        pub bonus: u128,
+        pub min_transfers: u32,
+        pub min_volume: u128,
+    }
+
+    /// Who referred an account and how far it is toward the bonus
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Referral {
+        pub referrer: AccountId,
+        pub transfers: u32,
+        pub volume: u128,
+        pub rewarded: bool,
+    }
+
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ReferralStats {
+        pub referred: u32,
+        pub rewarded: u32,
+        pub earned: u128,
+    }
+
+    /// Which allowance an allowance-based transfer draws on, and what is left of it
+    enum AllowanceDebit {
+        Standard(u128),
+        Periodic(PeriodicAllowance),
+    }
+
+    /// Transfers of at least `min_amount` pay `fee_bps`, until the next bracket starts
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct FeeBracket {
+        pub min_amount: u128,
+        pub fee_bps: u16,
+    }
+
+    /// Accounts that sent at least `min_volume` over `LOYALTY_WINDOW` get
+    /// `fee_discount_bps` off their transfer fees
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct LoyaltyTier {
+        pub min_volume: u128,
+        pub fee_discount_bps: u16,
+    }
+
+    /// Volume an account sent in the loyalty window starting at `window_start`
+    /// and in the window before it
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct LoyaltyVolume {
+        pub window_start: Timestamp,
+        pub current: u128,
+        pub previous: u128,
+    }
+
+    /// Latest payment made against a payment ID
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct PaymentRecord {
+        pub from: AccountId,
+        pub to: AccountId,
+        pub amount: u128,
+        pub memo: Option<Vec<u8>>,
+        pub timestamp: Timestamp,
+        pub refunded: u128,
+    }
+
+    /// Savings held for an account. `index` is the savings index interest was
+    /// last accrued at.
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct SavingsAccount {
+        pub principal: u128,
+        pub index: u128,
+    }
+
+    /// Overdraft or loan owed by an account. `index` is the overdraft or loan
+    /// index interest was last accrued at.
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Debt {
+        pub principal: u128,
+        pub index: u128,
+    }
+
+    /// What `credit_score()` is computed from: when the account first held or
+    /// sent tokens, how many transfers it has sent, and how often it has repaid
+    /// an overdraft or loan or been liquidated
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct CreditHistory {
+        pub opened_at: Timestamp,
+        pub transfers: u64,
+        pub repayments: u32,
+        pub liquidations: u32,
+    }
+
+    /// Negative interest: balances decay by `rate_bps` a year, paid to `sink`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Demurrage {
+        pub rate_bps: u16,
+        pub sink: AccountId,
+    }
+
+    /// Loans pay `base_rate_bps` plus `slope_bps` scaled by utilization, the
+    /// share of savings lent out. Savers earn the loan rate times utilization.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct InterestRateModel {
+        pub base_rate_bps: u16,
+        pub slope_bps: u16,
+    }
+
+    /// Certificate of deposit. The interest is fixed when it is opened.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct TermDeposit {
+        pub owner: AccountId,
+        pub amount: u128,
+        pub interest: u128,
+        pub opened_at: Timestamp,
+        pub matures_at: Timestamp,
+    }
+
+    /// Tokens saved toward a goal, held in the contract's custody. The goal is
+    /// met once `balance` reaches `target` and `unlock_at` has passed, where set.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct SavingsGoal {
+        pub owner: AccountId,
+        pub balance: u128,
+        pub target: Option<u128>,
+        pub unlock_at: Option<Timestamp>,
+    }
+
+    /// Linear vesting of `total` over `duration` ms from `start`. Nothing vests
+    /// until `cliff` ms after the start.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct VestingSchedule {
+        pub beneficiary: AccountId,
+        pub total: u128,
+        pub released: u128,
+        pub start: Timestamp,
+        pub cliff: Timestamp,
+        pub duration: Timestamp,
+    }
+
+    /// Transfer held in escrow until `unlock_at`
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct LockedTransfer {
+        pub from: AccountId,
+        pub to: AccountId,
+        pub amount: u128,
+        pub unlock_at: Timestamp,
+    }
+
+    /// Continuous payment: `deposit` streams to `recipient` second by second
+    /// between `start` and `end`
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Stream {
+        pub sender: AccountId,
+        pub recipient: AccountId,
+        pub deposit: u128,
+        pub withdrawn: u128,
+        pub start: Timestamp,
+        pub end: Timestamp,
+    }
+
+    /// Recurring transfer of `amount` every `interval` ms from `next_due` until
+    /// `end`, paid from the payer's balance
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct StandingOrder {
+        pub payer: AccountId,
+        pub to: AccountId,
+        pub amount: u128,
+        pub interval: Timestamp,
+        pub next_due: Timestamp,
+        pub end: Timestamp,
+    }
+
+    /// Authorization for `merchant` to pull `amount` from `subscriber` at most
+    /// once per `period` ms
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Subscription {
+        pub subscriber: AccountId,
+        pub merchant: AccountId,
+        pub amount: u128,
+        pub period: Timestamp,
+        pub next_charge_at: Timestamp,
+    }
+
+    /// Direct debit mandate: `payee` may collect up to `limit` from `payer` per
+    /// collection until the payer revokes it
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Mandate {
+        pub payer: AccountId,
+        pub payee: AccountId,
+        pub limit: u128,
+        pub dishonoured: u32,
+    }
+
+    /// Payee and amount of one payroll entry
+    pub type PayrollEntry = (AccountId, u128);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum PayrollStatus {
+        /// Entries can still be added
+        Draft,
+        /// Funded and paying out
+        Funded,
+        Completed,
+    }
+
+    /// Payroll run paid out in chunks. Entries before `next_index` have been
+    /// processed.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Payroll {
+        pub employer: AccountId,
+        pub status: PayrollStatus,
+        pub payees: u32,
+        pub total: u128,
+        pub next_index: u32,
+        pub paid: u128,
+    }
+
+    /// Payroll payments received by an account, used to size its salary advances
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct PayrollIncome {
+        pub payments: u32,
+        pub last_amount: u128,
+        pub last_paid_at: Timestamp,
+    }
+
+    /// Snapshot ID and the balance an account held when it was taken
+    pub type Checkpoint = (u32, u128);
+
+    /// Tokens the owner set aside for holders at `snapshot`, shared pro rata
+    /// over `eligible_supply`, the supply outside the contract's custody
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Distribution {
+        pub snapshot: u32,
+        pub amount: u128,
+        pub eligible_supply: u128,
+        pub claimed: u128,
+    }
+
+    /// Linear bonding curve: the price of a token unit rises from `base_price` by
+    /// `slope` for every unit sold, both scaled by `CURVE_PRICE_SCALE`
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct BondingCurve {
+        pub base_price: Balance,
+        pub slope: Balance,
+    }
+
+    /// Fixed-rate sale of `supply` tokens from the contract's custody between
+    /// `start` and `end`, at `rate` tokens per native unit. A zero `account_cap`
+    /// leaves purchases per account uncapped
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct TokenSale {
+        pub rate: u128,
+        pub start: Timestamp,
+        pub end: Timestamp,
+        pub supply: u128,
+        pub sold: u128,
+        pub account_cap: u128,
+        pub whitelist_only: bool,
+        pub raised: Balance,
+        pub unsold_reclaimed: bool,
+        pub proceeds_withdrawn: bool,
+    }
+
+    /// Another PSP22 token this one swaps against, one unit for `rate` of its
+    /// units
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct SwapPair {
+        pub token: AccountId,
+        pub rate: u128,
+    }
+
+    /// Delivery-versus-payment trade: `seller` delivers `amount` of this token
+    /// and `buyer` pays `asset_amount` of the PSP22 `asset`. Each leg is locked
+    /// in the contract by its party, and the trade settles once both are.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct DvpTrade {
+        pub seller: AccountId,
+        pub buyer: AccountId,
+        pub amount: u128,
+        pub asset: AccountId,
+        pub asset_amount: u128,
+        pub deadline: Timestamp,
+        pub seller_funded: bool,
+        pub buyer_funded: bool,
+    }
+
+    /// Hashed-timelock transfer held in the contract's custody: paid to `to`
+    /// for the preimage of its hashlock, or back to `sender` after `timelock`
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Htlc {
+        pub sender: AccountId,
+        pub to: AccountId,
+        pub amount: u128,
+        pub timelock: Timestamp,
+    }
+
+    /// Tokens set aside for the `(account, amount)` pairs committed to by `root`,
+    /// claimable until `expires_at`
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Airdrop {
+        pub root: Hash,
+        pub total: u128,
+        pub claimed: u128,
+        pub expires_at: Timestamp,
+        pub reclaimed: bool,
+    }
+
+    /// Salary advance an account still owes, recovered from its next payroll
+    /// payments
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct SalaryAdvance {
+        pub principal: u128,
+        pub fee: u128,
+    }
+
+    /// Funds held for `payee` until the payer confirms or `arbiter` rules.
+    /// The payer can take them back once `deadline` passes. `amount` is what is
+    /// still held, i.e. the sum of the unreleased milestones.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Escrow {
+        pub payer: AccountId,
+        pub payee: AccountId,
+        pub arbiter: AccountId,
+        pub amount: u128,
+        pub deadline: Timestamp,
+        pub milestones: Vec<Milestone>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Milestone {
+        pub amount: u128,
+        pub released: bool,
+    }
+
+    /// Invoice from `merchant` that `payer` settles with `pay_request`
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct PaymentRequest {
+        pub merchant: AccountId,
+        pub payer: AccountId,
+        pub amount: u128,
+        pub memo: Vec<u8>,
+        pub expiry: Timestamp,
+        pub paid: bool,
+    }
+
+    /// Bill split into shares. Paid shares are held until every share is paid,
+    /// then released to `payee`.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Bill {
+        pub initiator: AccountId,
+        pub payee: AccountId,
+        pub shares: Vec<BillShare>,
+        pub expiry: Timestamp,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct BillShare {
+        pub participant: AccountId,
+        pub amount: u128,
+        pub paid: bool,
+    }
+
+    /// Gift card held in the contract's custody until redeemed or reclaimed
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct GiftCard {
+        pub issuer: AccountId,
+        pub amount: u128,
+        pub expiry: Timestamp,
+    }
+
+    /// Account held by several keys. Any owner can move up to `threshold` at a
+    /// time; larger transfers need `approvals_required` owners to approve.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct JointAccount {
+        pub owners: Vec<AccountId>,
+        pub approvals_required: u32,
+        pub threshold: u128,
+    }
+
+    /// Transfer out of a joint account waiting for its owners' approvals
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct JointTransfer {
+        pub joint: AccountId,
+        pub proposer: AccountId,
+        pub to: AccountId,
+        pub amount: u128,
+        pub approvals: Vec<AccountId>,
+    }
+
+    /// Part of a holder's balance set aside under a name, held in the contract's
+    /// custody. Nothing can leave it before `locked_until`.
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Envelope {
+        pub balance: u128,
+        pub locked_until: Timestamp,
+    }
+
+    /// Dry run of a transfer: the fee taken, what the recipient gets and
+    /// the error the transfer would fail with, if any
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub struct TransferQuote {
+        pub fee: u128,
+        pub net: u128,
+        pub outcome: core::result::Result<(), Error>,
+    }
+
+    /// Dead-man switch: `beneficiary` can claim the holder's balance once the
+    /// holder has been inactive for `inactivity_period` since `last_active`
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Inheritance {
+        pub beneficiary: AccountId,
+        pub inactivity_period: Timestamp,
+        pub last_active: Timestamp,
+    }
+
+    /// Guardians who can move a holder's balance to a new key; `quorum` of them
+    /// have to agree
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct RecoveryConfig {
+        pub guardians: Vec<AccountId>,
+        pub quorum: u32,
+    }
+
+    /// Recovery to `new_account` backed by `approvals`. Once they reach the
+    /// quorum it can be executed from `executable_at`.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Recovery {
+        pub new_account: AccountId,
+        pub approvals: Vec<AccountId>,
+        pub executable_at: Option<Timestamp>,
+    }
+
+    /// Blacklist record; entries with an `expires_at` lapse on their own
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct BlacklistEntry {
+        pub expires_at: Option<Timestamp>,
+        pub reason: BlacklistReason,
+    }
+
+    /// Why an account was blacklisted, recorded for auditors
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum BlacklistReason {
+        #[default]
+        Unspecified,
+        Sanctions,
+        Fraud,
+        CourtOrder,
+        ComplianceReview,
+        /// Issuer-defined code
+        Other(u32),
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum AppealStatus {
+        Pending,
+        Approved,
+        Rejected,
+    }
+
+    /// Latest blacklist appeal of an account; `memo_hash` commits to the off-chain statement
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Appeal {
+        pub memo_hash: Hash,
+        pub submitted_at: Timestamp,
+        pub status: AppealStatus,
+    }
+
+    /// Verification level of an account; accounts start out `Unverified`
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum KycTier {
+        #[default]
+        Unverified,
+        Basic,
+        Full,
+    }
+
+    /// Limits applied to every account in a tier; `None` means unlimited
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct TierLimits {
+        pub max_transfer: Option<u128>,
+        pub max_balance: Option<u128>,
+    }
+
+    /// Privileged operation that needs admin multisig approval once it is enabled
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum AdminAction {
+        Mint {
+            to: AccountId,
+            amount: u128,
+        },
+        Blacklist {
+            account: AccountId,
+            expires_at: Option<Timestamp>,
+            reason: BlacklistReason,
+        },
+        Unblacklist {
+            account: AccountId,
+        },
+        Pause,
+        Unpause,
+        TransferOwnership {
+            new_owner: AccountId,
+        },
+        SetAdmins {
+            admins: Vec<AccountId>,
+            threshold: u32,
+        },
+        SetCap {
+            cap: u128,
+        },
+        SetTimelock {
+            delay: Timestamp,
+            large_mint_threshold: u128,
+        },
+        CancelOperation {
+            id: u64,
+        },
+        SetCodeHash {
+            code_hash: Hash,
+        },
+        SetScopePaused {
+            scope: PauseScope,
+            paused: bool,
+        },
+        ForceTransfer {
+            from: AccountId,
+            to: AccountId,
+            amount: u128,
+        },
+        InsurancePayout {
+            to: AccountId,
+            amount: u128,
+        },
+    }
+
+    /// Admin action waiting for its timelock to elapse
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ScheduledOperation {
+        pub action: AdminAction,
+        pub eta: Timestamp,
+    }
+
+    /// Pending multisig proposal
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Proposal {
+        pub action: AdminAction,
+        pub proposer: AccountId,
+        pub confirmations: u32,
+        pub expires_at: Timestamp,
+        pub executed: bool,
+        /// Admin set the proposal was made under; reconfiguring admins voids it
+        pub epoch: u32,
+    }
+
+    impl Default for Token {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Token {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self::new_with_metadata(None, None, 0)
+        }
+
+        /// Deploys with `initial_supply` minted to the deployer
+        #[ink(constructor, payable)]
+        pub fn new_with_supply(initial_supply: u128) -> Result<Self> {
+            let mut token = Self::new();
+            if initial_supply > 0 {
+                token._mint(Self::env().caller(), initial_supply)?;
+            }
+            Ok(token)
+        }
+
+        #[ink(constructor)]
+        pub fn new_with_metadata(
+            name: Option<String>,
+            symbol: Option<String>,
+            decimals: u8,
+        ) -> Self {
+            Self {
+                balances: Mapping::default(),
+                allowances: Mapping::default(),
+                periodic_allowances: Mapping::default(),
+                spender_destinations: Mapping::default(),
+                session_keys: Mapping::default(),
+                blacklist: Mapping::default(),
+                owner: Self::env().caller(),
+                total_supply: 0,
+                paused: false,
+                name,
+                symbol,
+                decimals,
+                nonces: Mapping::default(),
+                cap: None,
+                renounced: false,
+                minter_quotas: Mapping::default(),
+                admins: Vec::new(),
+                admin_threshold: 0,
+                admin_epoch: 0,
+                proposal_ttl: 0,
+                next_proposal_id: 0,
+                proposals: Mapping::default(),
+                confirmations: Mapping::default(),
+                timelock_delay: 0,
+                large_mint_threshold: 0,
+                next_operation_id: 0,
+                scheduled: Mapping::default(),
+                guardian: None,
+                minting_paused: false,
+                burning_paused: false,
+                transfers_paused: false,
+                approvals_paused: false,
+                breaker_window: 0,
+                breaker_mint_limit: 0,
+                breaker_transfer_limit: 0,
+                window_start: 0,
+                window_minted: 0,
+                window_transferred: 0,
+                max_transfer_amount: None,
+                compliance: None,
+                tiers: Mapping::default(),
+                tier_limits: Mapping::default(),
+                whitelist_mode: false,
+                whitelist: Mapping::default(),
+                blacklisted_accounts: Mapping::default(),
+                blacklist_index: Mapping::default(),
+                blacklist_count: 0,
+                frozen: Mapping::default(),
+                self_locks: Mapping::default(),
+                audit_log: Mapping::default(),
+                audit_log_len: 0,
+                appeals: Mapping::default(),
+                travel_rule_threshold: None,
+                payments: Mapping::default(),
+                fee_bps: 0,
+                fee_collector: None,
+                fee_schedule: Vec::new(),
+                savings: Mapping::default(),
+                savings_apr_bps: 0,
+                savings_index: 0,
+                savings_index_updated_at: 0,
+                term_deposits: Mapping::default(),
+                term_deposits_of: Mapping::default(),
+                next_term_deposit_id: 0,
+                term_deposit_apr_bps: 0,
+                early_closure_penalty_bps: 0,
+                demurrage: None,
+                demurrage_index: 0,
+                demurrage_index_updated_at: 0,
+                demurrage_index_of: Mapping::default(),
+                demurrage_since: 0,
+                vault_assets: 0,
+                vault_total_shares: 0,
+                vault_shares: Mapping::default(),
+                vault_strategy: None,
+                vesting_schedules: Mapping::default(),
+                vesting_schedules_of: Mapping::default(),
+                next_vesting_id: 0,
+                locked_transfers: Mapping::default(),
+                locked_transfers_sent: Mapping::default(),
+                next_locked_transfer_id: 0,
+                streams: Mapping::default(),
+                next_stream_id: 0,
+                standing_orders: Mapping::default(),
+                standing_orders_of: Mapping::default(),
+                next_standing_order_id: 0,
+                subscriptions: Mapping::default(),
+                next_subscription_id: 0,
+                mandates: Mapping::default(),
+                next_mandate_id: 0,
+                payrolls: Mapping::default(),
+                payroll_entries: Mapping::default(),
+                next_payroll_id: 0,
+                escrows: Mapping::default(),
+                next_escrow_id: 0,
+                payment_requests: Mapping::default(),
+                next_payment_request_id: 0,
+                open_requests: Mapping::default(),
+                open_request_count: Mapping::default(),
+                open_request_index: Mapping::default(),
+                bills: Mapping::default(),
+                next_bill_id: 0,
+                spent_cheques: Mapping::default(),
+                gift_cards: Mapping::default(),
+                credit_limits: Mapping::default(),
+                debts: Mapping::default(),
+                overdraft_apr_bps: 0,
+                overdraft_index: 0,
+                overdraft_index_updated_at: 0,
+                collateral: Mapping::default(),
+                loans: Mapping::default(),
+                loan_to_value_bps: 0,
+                collateral_price: PRICE_PRECISION,
+                price_oracle: None,
+                liquidation_threshold_bps: 10_000,
+                liquidation_bonus_bps: 0,
+                rate_model: None,
+                loan_index: 0,
+                loan_index_updated_at: 0,
+                total_loans: 0,
+                total_savings: 0,
+                credit_history: Mapping::default(),
+                min_credit_score: 0,
+                joint_accounts: Mapping::default(),
+                next_joint_account_id: 0,
+                joint_transfers: Mapping::default(),
+                pending_joint_transfers: Mapping::default(),
+                next_joint_transfer_id: 0,
+                envelopes: Mapping::default(),
+                envelope_names: Mapping::default(),
+                savings_goals: Mapping::default(),
+                savings_goals_of: Mapping::default(),
+                next_savings_goal_id: 0,
+                goal_penalty_bps: 0,
+                incentive_pool: 0,
+                inheritances: Mapping::default(),
+                recovery_configs: Mapping::default(),
+                recoveries: Mapping::default(),
+                cashback_merchants: Mapping::default(),
+                cashback_bps: 0,
+                rewards_pool: 0,
+                cashback: Mapping::default(),
+                referral_program: None,
+                referrals: Mapping::default(),
+                referral_stats: Mapping::default(),
+                loyalty_tiers: Vec::new(),
+                loyalty_volume: Mapping::default(),
+                merchants: Mapping::default(),
+                settlement_fee_bps: 0,
+                settlement_reports: Mapping::default(),
+                protected_payments: Mapping::default(),
+                next_protected_payment_id: 0,
+                dispute_window: DEFAULT_DISPUTE_WINDOW,
+                causes: Mapping::default(),
+                matching_pool: 0,
+                matching_ratio_bps: 0,
+                campaigns: Mapping::default(),
+                next_campaign_id: 0,
+                pledges: Mapping::default(),
+                campaign_pledgers: Mapping::default(),
+                treasury_balance: 0,
+                treasurer: None,
+                budgets: Mapping::default(),
+                budget_names: Vec::new(),
+                spend_proposals: Mapping::default(),
+                next_spend_id: 0,
+                grant_budgets: Mapping::default(),
+                insurance_levy_bps: 0,
+                insurance_fund: 0,
+                insurance_payouts: Mapping::default(),
+                insurance_payout_count: 0,
+                last_activity: Mapping::default(),
+                dormancy_rules: None,
+                dormant_since: Mapping::default(),
+                approved_spenders: Mapping::default(),
+                allowance_owners: Mapping::default(),
+                min_balance: 0,
+                sweep_dust: false,
+                payroll_income: Mapping::default(),
+                salary_advances: Mapping::default(),
+                salary_advance_bps: 0,
+                salary_advance_fee_bps: 0,
+                current_snapshot: 0,
+                balance_checkpoints: Mapping::default(),
+                checkpoint_counts: Mapping::default(),
+                distributions: Mapping::default(),
+                next_distribution_id: 0,
+                dividend_claims: Mapping::default(),
+                airdrops: Mapping::default(),
+                next_airdrop_id: 0,
+                airdrop_claims: Mapping::default(),
+                faucet_amount: 0,
+                faucet_cooldown: 0,
+                faucet_claims: Mapping::default(),
+                bonding_curve: None,
+                curve_supply: 0,
+                curve_reserve: 0,
+                sale: None,
+                sale_round: 0,
+                sale_purchases: Mapping::default(),
+                swap_pair: None,
+                swap_reserve: 0,
+                dvp_trades: Mapping::default(),
+                next_dvp_id: 0,
+                htlcs: Mapping::default(),
+            }
+        }
+
+        /// Full deployment in one instantiate call: metadata, optional supply cap and
+        /// initial allocations (each account at most once)
+        #[ink(constructor, payable)]
+        pub fn new_configured(
+            name: Option<String>,
+            symbol: Option<String>,
+            decimals: u8,
+            cap: Option<u128>,
+            allocations: Vec<(AccountId, u128)>,
+        ) -> Result<Self> {
+            let mut token = Self::new_with_metadata(name, symbol, decimals);
+            token.cap = cap;
+
+            for (account, amount) in allocations {
+                if token.balance_of(account) > 0 {
+                    return Err(Error::DuplicateAllocation);
+                }
+                token._mint(account, amount)?;
+            }
+
+            Ok(token)
+        }
+
+        #[inline]
+        fn only_owner(&self) -> Result<()> {
+            if self.renounced {
+                return Err(Error::OwnershipRenounced);
+            }
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+
+        /// Owner check for actions that must go through the multisig once it is enabled
+        #[inline]
+        fn only_owner_direct(&self) -> Result<()> {
+            self.only_owner()?;
+            if self.admin_threshold > 0 {
+                return Err(Error::MultisigRequired);
+            }
+            Ok(())
+        }
+
+        /// The compliance officer, or the owner while ownership hasn't been renounced
+        #[inline]
+        fn only_compliance(&self) -> Result<()> {
+            if self.compliance == Some(self.env().caller()) {
+                return Ok(());
+            }
+            self.only_owner()
+        }
+
+        #[inline]
+        fn when_not_paused(&self) -> Result<()> {
+            if self.paused {
+                return Err(Error::ContractPaused);
+            }
+            Ok(())
+        }
+
+        #[inline]
+        fn when_scope_active(&self, scope: PauseScope) -> Result<()> {
+            if self.is_scope_paused(scope) {
+                return Err(Error::ContractPaused);
+            }
+            Ok(())
+        }
+
+        #[inline]
+        fn not_blacklisted(&self, account: AccountId) -> Result<()> {
+            if self.is_blacklisted(account) {
+                return Err(Error::AccountBlacklisted);
+            }
+            Ok(())
+        }
+
+        /// Calls `PSP22Receiver::before_received` when `to` is a contract. Contracts that
+        /// don't implement the hook, or return an error from it, reject the transfer.
+        fn before_received(
+            &self,
+            operator: AccountId,
+            from: AccountId,
+            to: AccountId,
+            value: u128,
+            data: &Vec<u8>,
+        ) -> Result<()> {
+            if !self.env().is_contract(&to) {
+                return Ok(());
+            }
+
+            let result = build_call::<ink::env::DefaultEnvironment>()
+                .call(to)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                        "PSP22Receiver::before_received"
+                    )))
+                    .push_arg(operator)
+                    .push_arg(from)
+                    .push_arg(value)
+                    .push_arg(data),
+                )
+                .returns::<core::result::Result<(), PSP22ReceiverError>>()
+                .try_invoke();
+
+            match result {
+                Ok(Ok(Ok(()))) => Ok(()),
+                _ => Err(Error::SafeTransferCheckFailed),
+            }
+        }
+
+        /// The owner mints without limit; minters draw down their quota
+        #[ink(message)]
+        pub fn mint(&mut self, to: AccountId, amount: u128) -> Result<()> {
+            if self.renounced {
+                return Err(Error::OwnershipRenounced);
+            }
+            let caller = self.env().caller();
+            if caller == self.owner {
+                self.only_owner_direct()?;
+                self.not_timelocked(&AdminAction::Mint { to, amount })?;
+                return self.privileged_mint(to, amount);
+            }
+
+            let quota = self.minter_quotas.get(caller).ok_or(Error::Unauthorized)?;
+            let remaining = quota
+                .checked_sub(amount)
+                .ok_or(Error::MinterQuotaExceeded)?;
+
+            self.privileged_mint(to, amount)?;
+            self.minter_quotas.insert(caller, &remaining);
+
+            Ok(())
+        }
+
+        /// Mint by the owner, a minter or the admin multisig, recorded in the audit log
+        fn privileged_mint(&mut self, to: AccountId, amount: u128) -> Result<()> {
+            self.not_blacklisted(to)?;
+            self._mint(to, amount)?;
+            self.audit(AuditOperation::Mint, &(to, amount));
+            Ok(())
+        }
+
+        /// Grants `minter` the right to mint up to `quota` more tokens. A quota of 0
+        /// revokes the minter role.
+        #[ink(message)]
+        pub fn set_minter_quota(&mut self, minter: AccountId, quota: u128) -> Result<()> {
+            self.only_owner()?;
+
+            if quota == 0 {
+                self.minter_quotas.remove(minter);
+            } else {
+                self.minter_quotas.insert(minter, &quota);
+            }
+
+            self.audit(AuditOperation::Config, &(minter, quota));
+            self.env().emit_event(MinterQuotaUpdated { minter, quota });
+
+            Ok(())
+        }
+
+        /// Remaining amount `minter` may mint, `None` if it holds no minter role
+        #[ink(message)]
+        pub fn minter_quota_of(&self, minter: AccountId) -> Option<u128> {
+            self.minter_quotas.get(minter)
+        }
+
+        /// Lets any account mint itself `amount` at most once per `cooldown`, for
+        /// test deployments. A zero `amount` turns the faucet off
+        #[ink(message)]
+        pub fn set_faucet(&mut self, amount: u128, cooldown: Timestamp) -> Result<()> {
+            self.only_owner()?;
+
+            self.faucet_amount = amount;
+            self.faucet_cooldown = cooldown;
+
+            self.audit(AuditOperation::Config, &(amount, cooldown));
+            self.env().emit_event(FaucetUpdated { amount, cooldown });
+
+            Ok(())
+        }
+
+        /// `(amount, cooldown)` of the faucet
+        #[ink(message)]
+        pub fn faucet(&self) -> (u128, Timestamp) {
+            (self.faucet_amount, self.faucet_cooldown)
+        }
+
+        /// Earliest time `account` can next claim from the faucet
+        #[ink(message)]
+        pub fn next_faucet_claim(&self, account: AccountId) -> Timestamp {
+            self.faucet_claims.get(account).map_or(0, |claimed_at| {
+                claimed_at.saturating_add(self.faucet_cooldown)
+            })
+        }
+
+        /// Mints the faucet amount to the caller
+        #[ink(message)]
+        pub fn claim_faucet(&mut self) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            let amount = self.faucet_amount;
+            if amount == 0 {
+                return Err(Error::FaucetDisabled);
+            }
+            let now = self.env().block_timestamp();
+            if self.faucet_claims.contains(account) && now < self.next_faucet_claim(account) {
+                return Err(Error::FaucetCooldown);
+            }
+
+            self._mint(account, amount)?;
+            self.faucet_claims.insert(account, &now);
+
+            self.env().emit_event(FaucetClaimed { account, amount });
+
+            Ok(amount)
+        }
+
+        fn _mint(&mut self, to: AccountId, amount: u128) -> Result<()> {
+            self.when_scope_active(PauseScope::Minting)?;
+            self.settle_demurrage(to);
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            let balance = self.balance_of(to);
+            let new_balance = balance.checked_add(amount).ok_or(Error::Overflow)?;
+            let new_supply = self
+                .total_supply
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_cap(new_supply)?;
+            self.within_balance_limit(to, new_balance)?;
+
+            self.write_balance(to, new_balance);
+            self.total_supply = new_supply;
+
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(to),
+                value: amount,
+                data: Vec::new(),
+            });
+
+            self.open_credit_history(to);
+            self.record_volume(amount, 0);
+
+            Ok(())
+        }
+
+        #[inline]
+        fn within_cap(&self, supply: u128) -> Result<()> {
+            match self.cap {
+                Some(cap) if supply > cap => Err(Error::CapExceeded),
+                _ => Ok(()),
+            }
+        }
+
+        /// Maximum total supply, if any
+        #[ink(message)]
+        pub fn cap(&self) -> Option<u128> {
+            self.cap
+        }
+
+        /// Sets or lowers the supply cap. A cap can never be raised or removed.
+        #[ink(message)]
+        pub fn set_cap(&mut self, cap: u128) -> Result<()> {
+            self.only_owner_direct()?;
+            self.not_timelocked(&AdminAction::SetCap { cap })?;
+            self._set_cap(cap)
+        }
+
+        fn _set_cap(&mut self, cap: u128) -> Result<()> {
+            if let Some(current) = self.cap {
+                if cap > current {
+                    return Err(Error::CapExceeded);
+                }
+            }
+            if cap < self.total_supply {
+                return Err(Error::CapExceeded);
+            }
+
+            self.cap = Some(cap);
+
+            self.audit(AuditOperation::Config, &cap);
+            self.env().emit_event(CapUpdated { cap });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn burn(&mut self, amount: u128) -> Result<()> {
+            self.when_not_paused()?;
+
+            let from = self.env().caller();
+            self.not_blacklisted(from)?;
+
+            self._burn(from, amount)
+        }
+
+        /// Mints tokens 1:1 for the native value sent with the call
+        #[ink(message, payable)]
+        pub fn deposit(&mut self) -> Result<()> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+
+            let amount = self.env().transferred_value();
+            self._mint(account, amount)?;
+
+            self.env().emit_event(Deposit { account, amount });
+
+            Ok(())
+        }
+
+        /// Moves `amount` of the caller's spendable balance into savings, where it
+        /// earns interest at `savings_apr()`
+        #[ink(message)]
+        pub fn deposit_to_savings(&mut self, amount: u128) -> Result<()> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(account) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            let mut savings = self.accrue_savings(account)?;
+            savings.principal = savings
+                .principal
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.move_balance(account, self.env().account_id(), amount)?;
+            self.savings.insert(account, &savings);
+            self.total_savings = self.total_savings.saturating_add(amount);
+
+            self.env().emit_event(SavingsDeposit { account, amount });
+
+            Ok(())
+        }
+
+        /// Moves `amount` from the caller's savings, including accrued interest,
+        /// back to its balance
+        #[ink(message)]
+        pub fn withdraw_from_savings(&mut self, amount: u128) -> Result<()> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            if self.savings_of(account) < amount {
+                return Err(Error::InsufficientSavings);
+            }
+            let new_balance = self
+                .balance_of(account)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(account, new_balance)?;
+
+            let mut savings = self.accrue_savings(account)?;
+            savings.principal = savings
+                .principal
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientSavings)?;
+            self.move_balance(self.env().account_id(), account, amount)?;
+            self.savings.insert(account, &savings);
+            self.total_savings = self.total_savings.saturating_sub(amount);
+
+            self.env().emit_event(SavingsWithdrawal { account, amount });
+
+            Ok(())
+        }
+
+        /// Savings of `account`, including interest not yet minted
+        #[ink(message)]
+        pub fn savings_of(&self, account: AccountId) -> u128 {
+            let savings = self.savings.get(account).unwrap_or_default();
+            savings
+                .principal
+                .saturating_add(self.pending_interest(&savings))
+        }
+
+        /// Annual interest rate paid on savings, in basis points. Set by the
+        /// interest rate model when there is one.
+        #[ink(message)]
+        pub fn savings_apr(&self) -> u16 {
+            match self.rate_model {
+                Some(_) => Self::bps_of(self.loan_apr() as u128, self.utilization() as u16) as u16,
+                None => self.savings_apr_bps,
+            }
+        }
+
+        /// Sets the savings APR. Interest earned so far is kept at the old rate.
+        #[ink(message)]
+        pub fn set_savings_apr(&mut self, apr_bps: u16) -> Result<()> {
+            self.only_owner()?;
+            if apr_bps > MAX_SAVINGS_APR_BPS {
+                return Err(Error::InvalidAmount);
+            }
+            if self.rate_model.is_some() {
+                return Err(Error::RateModelActive);
+            }
+
+            self.accrue_rates();
+            self.savings_apr_bps = apr_bps;
+
+            self.audit(AuditOperation::Config, &apr_bps);
+            self.env().emit_event(SavingsRateUpdated { apr_bps });
+
+            Ok(())
+        }
+
+        /// Sum of `savings_apr() * elapsed_ms` since deployment
+        fn current_savings_index(&self) -> u128 {
+            let elapsed = self
+                .env()
+                .block_timestamp()
+                .saturating_sub(self.savings_index_updated_at);
+            self.savings_index
+                .saturating_add((self.savings_apr() as u128).saturating_mul(elapsed as u128))
+        }
+
+        /// Interest `savings` has earned since it was last accrued, limited to
+        /// what the cap allows
+        fn pending_interest(&self, savings: &SavingsAccount) -> u128 {
+            let growth = self.current_savings_index().saturating_sub(savings.index);
+            let interest = Self::interest_for(savings.principal, growth);
+            match self.cap {
+                Some(cap) => interest.min(cap.saturating_sub(self.total_supply)),
+                None => interest,
+            }
+        }
+
+        /// Interest on `principal` for `growth` basis-point milliseconds, i.e. the
+        /// APR in basis points times the time held
+        fn interest_for(principal: u128, growth: u128) -> u128 {
+            let scale = 10_000 * YEAR as u128;
+            (principal / scale)
+                .saturating_mul(growth)
+                .saturating_add(principal % scale * growth / scale)
+        }
+
+        /// Mints the interest `account` has earned into the contract's custody and
+        /// returns its updated savings
+        fn accrue_savings(&mut self, account: AccountId) -> Result<SavingsAccount> {
+            let mut savings = self.savings.get(account).unwrap_or_default();
+            let interest = self.pending_interest(&savings);
+
+            self.accrue_rates();
+            savings.index = self.savings_index;
+
+            if interest > 0 {
+                savings.principal = savings
+                    .principal
+                    .checked_add(interest)
+                    .ok_or(Error::Overflow)?;
+                self.total_savings = self.total_savings.saturating_add(interest);
+                self.mint_to_custody(interest)?;
+
+                self.env().emit_event(InterestPaid {
+                    account,
+                    amount: interest,
+                });
+            }
+
+            Ok(savings)
+        }
+
+        /// Mints interest into the contract's custody, outside the usual mint checks
+        fn mint_to_custody(&mut self, amount: u128) -> Result<()> {
+            let custody = self.env().account_id();
+            let new_custody_balance = self
+                .balance_of(custody)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.total_supply = self
+                .total_supply
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.write_balance(custody, new_custody_balance);
+
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(custody),
+                value: amount,
+                data: Vec::new(),
+            });
+
+            Ok(())
+        }
+
+        /// Lets `account` transfer up to `limit` more than its spendable balance,
+        /// owing the difference as debt. Lowering the limit below the current
+        /// debt only stops further drawing.
+        #[ink(message)]
+        pub fn set_credit_limit(&mut self, account: AccountId, limit: u128) -> Result<()> {
+            self.only_owner()?;
+
+            if limit == 0 {
+                self.credit_limits.remove(account);
+            } else {
+                self.credit_limits.insert(account, &limit);
+            }
+
+            self.audit(AuditOperation::Config, &(account, limit));
+            self.env().emit_event(CreditLimitUpdated { account, limit });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn credit_limit_of(&self, account: AccountId) -> u128 {
+            self.credit_limits.get(account).unwrap_or(0)
+        }
+
+        /// Overdraft owed by `account`, including interest not yet accrued
+        #[ink(message)]
+        pub fn debt_of(&self, account: AccountId) -> u128 {
+            let debt = self.debts.get(account).unwrap_or_default();
+            debt.principal
+                .saturating_add(self.pending_debt_interest(&debt))
+        }
+
+        /// How much more `account` can currently overdraw
+        #[ink(message)]
+        pub fn available_credit(&self, account: AccountId) -> u128 {
+            self.credit_limit_of(account)
+                .saturating_sub(self.debt_of(account))
+        }
+
+        /// Annual interest rate charged on overdraft debt, in basis points
+        #[ink(message)]
+        pub fn overdraft_apr(&self) -> u16 {
+            self.overdraft_apr_bps
+        }
+
+        /// Sets the overdraft APR. Interest charged so far is kept at the old rate.
+        #[ink(message)]
+        pub fn set_overdraft_apr(&mut self, apr_bps: u16) -> Result<()> {
+            self.only_owner()?;
+            if apr_bps > MAX_OVERDRAFT_APR_BPS {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.overdraft_index = self.current_overdraft_index();
+            self.overdraft_index_updated_at = self.env().block_timestamp();
+            self.overdraft_apr_bps = apr_bps;
+
+            self.audit(AuditOperation::Config, &apr_bps);
+            self.env().emit_event(OverdraftRateUpdated { apr_bps });
+
+            Ok(())
+        }
+
+        /// Burns up to `amount` of the caller's tokens against its overdraft debt
+        /// and returns the amount repaid
+        #[ink(message)]
+        pub fn repay_overdraft(&mut self, amount: u128) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            let mut debt = self.accrue_debt(account);
+            if debt.principal == 0 {
+                return Err(Error::NoDebt);
+            }
+            let repaid = amount.min(debt.principal);
+            self._burn(account, repaid)?;
+            debt.principal -= repaid;
+            if debt.principal == 0 {
+                self.debts.remove(account);
+            } else {
+                self.debts.insert(account, &debt);
+            }
+
+            self.record_credit_event(account, |history| {
+                history.repayments = history.repayments.saturating_add(1)
+            });
+
+            self.env().emit_event(OverdraftRepaid {
+                account,
+                amount: repaid,
+            });
+
+            Ok(repaid)
+        }
+
+        /// Sum of `overdraft_apr_bps * elapsed_ms` since deployment
+        fn current_overdraft_index(&self) -> u128 {
+            let elapsed = self
+                .env()
+                .block_timestamp()
+                .saturating_sub(self.overdraft_index_updated_at);
+            self.overdraft_index
+                .saturating_add((self.overdraft_apr_bps as u128).saturating_mul(elapsed as u128))
+        }
+
+        /// Interest `debt` has run up since it was last accrued
+        fn pending_debt_interest(&self, debt: &Debt) -> u128 {
+            let growth = self.current_overdraft_index().saturating_sub(debt.index);
+            Self::interest_for(debt.principal, growth)
+        }
+
+        /// Adds the interest `account` owes to its debt and returns the updated debt
+        fn accrue_debt(&mut self, account: AccountId) -> Debt {
+            let mut debt = self.debts.get(account).unwrap_or_default();
+            let interest = self.pending_debt_interest(&debt);
+
+            self.overdraft_index = self.current_overdraft_index();
+            self.overdraft_index_updated_at = self.env().block_timestamp();
+            debt.index = self.overdraft_index;
+            debt.principal = debt.principal.saturating_add(interest);
+
+            debt
+        }
+
+        /// Part of a transfer of `amount` that `from` has to borrow, checked
+        /// against its available credit and the cap
+        fn overdraft_needed(&self, from: AccountId, amount: u128) -> Result<u128> {
+            let shortfall = amount.saturating_sub(self.spendable_balance_of(from));
+            if shortfall == 0 {
+                return Ok(0);
+            }
+            if self.available_credit(from) < shortfall {
+                return Err(Error::InsufficientBalance);
+            }
+            self.meets_credit_score(from)?;
+            self.within_cap(
+                self.total_supply
+                    .checked_add(shortfall)
+                    .ok_or(Error::Overflow)?,
+            )?;
+
+            Ok(shortfall)
+        }
+
+        /// Mints `amount` to `account` and adds it to its overdraft debt
+        fn draw_overdraft(&mut self, account: AccountId, amount: u128) -> Result<()> {
+            let mut debt = self.accrue_debt(account);
+            debt.principal = debt.principal.checked_add(amount).ok_or(Error::Overflow)?;
+            let new_balance = self
+                .balance_of(account)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.total_supply = self
+                .total_supply
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.write_balance(account, new_balance);
+            self.debts.insert(account, &debt);
+
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(account),
+                value: amount,
+                data: Vec::new(),
+            });
+            self.env().emit_event(OverdraftDrawn { account, amount });
+
+            Ok(())
+        }
+
+        /// Locks the native value sent with the call as collateral for the caller's
+        /// loan
+        #[ink(message, payable)]
+        pub fn deposit_collateral(&mut self) -> Result<()> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            let amount = self.env().transferred_value();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            let collateral = self
+                .collateral_of(account)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.collateral.insert(account, &collateral);
+
+            self.env()
+                .emit_event(CollateralDeposited { account, amount });
+
+            Ok(())
+        }
+
+        /// Returns `amount` of the caller's collateral, as long as what is left
+        /// still covers its loan
+        #[ink(message)]
+        pub fn withdraw_collateral(&mut self, amount: Balance) -> Result<()> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            let collateral = self
+                .collateral_of(account)
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientCollateral)?;
+            if self.max_loan_for(collateral) < self.loan_of(account) {
+                return Err(Error::InsufficientCollateral);
+            }
+            if collateral == 0 {
+                self.collateral.remove(account);
+            } else {
+                self.collateral.insert(account, &collateral);
+            }
+            self.env()
+                .transfer(account, amount)
+                .map_err(|_| Error::NativeTransferFailed)?;
+
+            self.env()
+                .emit_event(CollateralWithdrawn { account, amount });
+
+            Ok(())
+        }
+
+        /// Mints `amount` to the caller as a loan against its collateral
+        #[ink(message)]
+        pub fn borrow(&mut self, amount: u128) -> Result<()> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            if self.borrowing_power(account) < amount {
+                return Err(Error::InsufficientCollateral);
+            }
+            self.meets_credit_score(account)?;
+
+            let mut loan = self.accrue_loan(account);
+            loan.principal = loan.principal.checked_add(amount).ok_or(Error::Overflow)?;
+            self._mint(account, amount)?;
+            self.loans.insert(account, &loan);
+            self.total_loans = self.total_loans.saturating_add(amount);
+
+            self.env().emit_event(LoanBorrowed { account, amount });
+
+            Ok(())
+        }
+
+        /// Burns up to `amount` of the caller's tokens against its loan and
+        /// returns the amount repaid
+        #[ink(message)]
+        pub fn repay_loan(&mut self, amount: u128) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            let mut loan = self.accrue_loan(account);
+            if loan.principal == 0 {
+                return Err(Error::NoLoan);
+            }
+            let repaid = amount.min(loan.principal);
+            self._burn(account, repaid)?;
+            loan.principal -= repaid;
+            if loan.principal == 0 {
+                self.loans.remove(account);
+            } else {
+                self.loans.insert(account, &loan);
+            }
+            self.total_loans = self.total_loans.saturating_sub(repaid);
+            self.record_credit_event(account, |history| {
+                history.repayments = history.repayments.saturating_add(1)
+            });
+
+            self.env().emit_event(LoanRepaid {
+                account,
+                amount: repaid,
+            });
+
+            Ok(repaid)
+        }
+
+        /// Native collateral locked by `account`
+        #[ink(message)]
+        pub fn collateral_of(&self, account: AccountId) -> Balance {
+            self.collateral.get(account).unwrap_or(0)
+        }
+
+        /// Tokens `account` owes on its loan, including interest not yet accrued
+        #[ink(message)]
+        pub fn loan_of(&self, account: AccountId) -> u128 {
+            let loan = self.loans.get(account).unwrap_or_default();
+            let growth = self.current_loan_index().saturating_sub(loan.index);
+            loan.principal
+                .saturating_add(Self::interest_for(loan.principal, growth))
+        }
+
+        /// How much more `account` can borrow against its current collateral
+        #[ink(message)]
+        pub fn borrowing_power(&self, account: AccountId) -> u128 {
+            self.max_loan_for(self.collateral_of(account))
+                .saturating_sub(self.loan_of(account))
+        }
+
+        /// Share of collateral value that can be borrowed, in basis points
+        #[ink(message)]
+        pub fn loan_to_value(&self) -> u16 {
+            self.loan_to_value_bps
+        }
+
+        /// Sets the loan-to-value ratio. Existing loans above the new limit stay
+        /// open but block further borrowing and collateral withdrawals.
+        #[ink(message)]
+        pub fn set_loan_to_value(&mut self, ltv_bps: u16) -> Result<()> {
+            self.only_owner()?;
+            if ltv_bps > MAX_LOAN_TO_VALUE_BPS || ltv_bps > self.liquidation_threshold_bps {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.loan_to_value_bps = ltv_bps;
+
+            self.audit(AuditOperation::Config, &ltv_bps);
+            self.env().emit_event(LoanToValueUpdated { ltv_bps });
+
+            Ok(())
+        }
+
+        /// Tokens one unit of native collateral is worth, scaled by
+        /// `PRICE_PRECISION`
+        #[ink(message)]
+        pub fn collateral_price(&self) -> u128 {
+            self.collateral_price
+        }
+
+        /// Sets the collateral price. Only the owner or the price oracle may call it.
+        #[ink(message)]
+        pub fn set_collateral_price(&mut self, price: u128) -> Result<()> {
+            let by = self.env().caller();
+            if self.price_oracle != Some(by) {
+                self.only_owner()?;
+            }
+            if price == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.collateral_price = price;
+
+            self.env().emit_event(CollateralPriceUpdated { by, price });
+
+            Ok(())
+        }
+
+        /// Sets the account, besides the owner, that may update the collateral price
+        #[ink(message)]
+        pub fn set_price_oracle(&mut self, oracle: Option<AccountId>) -> Result<()> {
+            self.only_owner()?;
+
+            self.price_oracle = oracle;
+
+            self.audit(AuditOperation::Config, &oracle);
+            self.env().emit_event(PriceOracleUpdated { oracle });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn price_oracle(&self) -> Option<AccountId> {
+            self.price_oracle
+        }
+
+        /// Liquidation threshold and discount, in basis points
+        #[ink(message)]
+        pub fn liquidation_terms(&self) -> (u16, u16) {
+            (self.liquidation_threshold_bps, self.liquidation_bonus_bps)
+        }
+
+        /// Sets the share of collateral value a loan may reach before it can be
+        /// liquidated, and the discount liquidators get on seized collateral
+        #[ink(message)]
+        pub fn set_liquidation_terms(&mut self, threshold_bps: u16, bonus_bps: u16) -> Result<()> {
+            self.only_owner()?;
+            if threshold_bps > 10_000
+                || threshold_bps < self.loan_to_value_bps
+                || bonus_bps > MAX_LIQUIDATION_BONUS_BPS
+            {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.liquidation_threshold_bps = threshold_bps;
+            self.liquidation_bonus_bps = bonus_bps;
+
+            self.audit(AuditOperation::Config, &(threshold_bps, bonus_bps));
+            self.env().emit_event(LiquidationTermsUpdated {
+                threshold_bps,
+                bonus_bps,
+            });
+
+            Ok(())
+        }
+
+        /// Collateral value at the liquidation threshold over the loan, in basis
+        /// points; below 10 000 the loan can be liquidated. `None` without a loan.
+        #[ink(message)]
+        pub fn health_factor(&self, account: AccountId) -> Option<u128> {
+            let loan = self.loan_of(account);
+            if loan == 0 {
+                return None;
+            }
+            let covered = Self::bps_of(
+                self.collateral_value(self.collateral_of(account)),
+                self.liquidation_threshold_bps,
+            );
+
+            Some(Self::mul_div(covered, 10_000, loan, false).unwrap_or(u128::MAX))
+        }
+
+        /// Repays the whole loan of an unhealthy `borrower` from the caller's
+        /// balance, in exchange for the collateral it was worth plus the
+        /// liquidation discount. Returns the collateral seized.
+        #[ink(message)]
+        pub fn liquidate(&mut self, borrower: AccountId) -> Result<Balance> {
+            self.when_not_paused()?;
+
+            let liquidator = self.env().caller();
+            self.not_blacklisted(liquidator)?;
+            let repaid = self.loan_of(borrower);
+            if repaid == 0 {
+                return Err(Error::NoLoan);
+            }
+            if self
+                .health_factor(borrower)
+                .is_some_and(|health| health >= 10_000)
+            {
+                return Err(Error::PositionHealthy);
+            }
+            self.accrue_loan(borrower);
+
+            let collateral = self.collateral_of(borrower);
+            let owed = repaid
+                .checked_add(Self::bps_of(repaid, self.liquidation_bonus_bps))
+                .ok_or(Error::Overflow)?;
+            let seized =
+                Self::mul_div(owed, PRICE_PRECISION, self.collateral_price, false)?.min(collateral);
+
+            self._burn(liquidator, repaid)?;
+            self.loans.remove(borrower);
+            self.total_loans = self.total_loans.saturating_sub(repaid);
+            if collateral == seized {
+                self.collateral.remove(borrower);
+            } else {
+                self.collateral.insert(borrower, &(collateral - seized));
+            }
+            self.env()
+                .transfer(liquidator, seized)
+                .map_err(|_| Error::NativeTransferFailed)?;
+            self.record_credit_event(borrower, |history| {
+                history.liquidations = history.liquidations.saturating_add(1)
+            });
+
+            self.env().emit_event(Liquidation {
+                borrower,
+                liquidator,
+                repaid,
+                seized,
+                price: self.collateral_price,
+            });
+
+            Ok(seized)
+        }
+
+        /// Annual interest rate charged on loans, in basis points
+        #[ink(message)]
+        pub fn loan_apr(&self) -> u16 {
+            self.rate_model.map_or(0, |model| {
+                model
+                    .base_rate_bps
+                    .saturating_add(
+                        Self::bps_of(model.slope_bps as u128, self.utilization() as u16) as u16,
+                    )
+            })
+        }
+
+        /// Share of savings lent out, in basis points. Loans beyond the savings
+        /// count as full utilization.
+        #[ink(message)]
+        pub fn utilization(&self) -> u128 {
+            if self.total_loans >= self.total_savings {
+                return if self.total_loans == 0 { 0 } else { 10_000 };
+            }
+            Self::mul_div(self.total_loans, 10_000, self.total_savings, false).unwrap_or(10_000)
+        }
+
+        #[ink(message)]
+        pub fn interest_rate_model(&self) -> Option<InterestRateModel> {
+            self.rate_model
+        }
+
+        /// Lets loan and savings rates follow utilization under `model`, or returns
+        /// to the fixed savings APR and interest-free loans with `None`. Interest
+        /// so far is kept at the old rates.
+        #[ink(message)]
+        pub fn set_interest_rate_model(&mut self, model: Option<InterestRateModel>) -> Result<()> {
+            self.only_owner()?;
+            if model.is_some_and(|model| {
+                model.base_rate_bps as u32 + model.slope_bps as u32 > MAX_LOAN_APR_BPS as u32
+            }) {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.accrue_rates();
+            self.rate_model = model;
+
+            self.audit(AuditOperation::Config, &model);
+            self.env().emit_event(InterestRateModelUpdated { model });
+
+            Ok(())
+        }
+
+        /// Sum of `loan_apr() * elapsed_ms` since deployment
+        fn current_loan_index(&self) -> u128 {
+            let elapsed = self
+                .env()
+                .block_timestamp()
+                .saturating_sub(self.loan_index_updated_at);
+            self.loan_index
+                .saturating_add((self.loan_apr() as u128).saturating_mul(elapsed as u128))
+        }
+
+        /// Brings the savings and loan indexes up to date at the current rates,
+        /// before anything that changes them
+        fn accrue_rates(&mut self) {
+            let now = self.env().block_timestamp();
+            self.savings_index = self.current_savings_index();
+            self.savings_index_updated_at = now;
+            self.loan_index = self.current_loan_index();
+            self.loan_index_updated_at = now;
+        }
+
+        /// Adds the interest `account` owes to its loan and returns the updated loan
+        fn accrue_loan(&mut self, account: AccountId) -> Debt {
+            let mut loan = self.loans.get(account).unwrap_or_default();
+            let growth = self.current_loan_index().saturating_sub(loan.index);
+            let interest = Self::interest_for(loan.principal, growth);
+
+            self.accrue_rates();
+            loan.index = self.loan_index;
+            loan.principal = loan.principal.saturating_add(interest);
+            self.total_loans = self.total_loans.saturating_add(interest);
+
+            loan
+        }
+
+        /// Tokens `collateral` is worth at the current price
+        fn collateral_value(&self, collateral: Balance) -> u128 {
+            Self::mul_div(collateral, self.collateral_price, PRICE_PRECISION, false)
+                .unwrap_or(u128::MAX)
+        }
+
+        /// Largest loan `collateral` supports at the current price
+        fn max_loan_for(&self, collateral: Balance) -> u128 {
+            Self::bps_of(self.collateral_value(collateral), self.loan_to_value_bps)
+        }
+
+        /// Score from 0 to `MAX_CREDIT_SCORE` built from account age (up to 300
+        /// after a year), repayments (50 each, up to 400) and monthly transfer
+        /// velocity (10 per transfer a month, up to 300), less 300 per liquidation
+        #[ink(message)]
+        pub fn credit_score(&self, account: AccountId) -> u16 {
+            let Some(history) = self.credit_history.get(account) else {
+                return 0;
+            };
+            const MONTH: Timestamp = YEAR / 12;
+            let age = self
+                .env()
+                .block_timestamp()
+                .saturating_sub(history.opened_at);
+
+            let age_points = (age as u128 * 300 / YEAR as u128).min(300);
+            let repayment_points = (history.repayments as u128 * 50).min(400);
+            let monthly_transfers =
+                history.transfers as u128 * MONTH as u128 / age.max(MONTH) as u128;
+            let velocity_points = (monthly_transfers * 10).min(300);
+
+            (age_points + repayment_points + velocity_points)
+                .saturating_sub(history.liquidations as u128 * 300)
+                .min(MAX_CREDIT_SCORE as u128) as u16
+        }
+
+        #[ink(message)]
+        pub fn credit_history(&self, account: AccountId) -> Option<CreditHistory> {
+            self.credit_history.get(account)
+        }
+
+        /// Credit score needed to draw an overdraft or borrow; 0 disables the check
+        #[ink(message)]
+        pub fn min_credit_score(&self) -> u16 {
+            self.min_credit_score
+        }
+
+        /// Only lets accounts with at least `min_score` draw overdrafts or take out
+        /// loans. Debt already drawn is unaffected.
+        #[ink(message)]
+        pub fn set_min_credit_score(&mut self, min_score: u16) -> Result<()> {
+            self.only_owner()?;
+            if min_score > MAX_CREDIT_SCORE {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.min_credit_score = min_score;
+
+            self.audit(AuditOperation::Config, &min_score);
+            self.env().emit_event(MinCreditScoreUpdated { min_score });
+
+            Ok(())
+        }
+
+        fn meets_credit_score(&self, account: AccountId) -> Result<()> {
+            if self.min_credit_score > 0 && self.credit_score(account) < self.min_credit_score {
+                return Err(Error::CreditScoreTooLow);
+            }
+            Ok(())
+        }
+
+        /// Starts the credit history of an account the first time it holds tokens
+        fn open_credit_history(&mut self, account: AccountId) {
+            if !self.credit_history.contains(account) {
+                self.record_credit_event(account, |_| {});
+            }
+        }
+
+        fn record_credit_event(
+            &mut self,
+            account: AccountId,
+            update: impl FnOnce(&mut CreditHistory),
+        ) {
+            let mut history = self.credit_history.get(account).unwrap_or(CreditHistory {
+                opened_at: self.env().block_timestamp(),
+                ..Default::default()
+            });
+            update(&mut history);
+            self.credit_history.insert(account, &history);
+        }
+
+        /// Locks `amount` of the caller's spendable balance for `term` ms at the
+        /// current term deposit rate and returns the deposit ID
+        #[ink(message)]
+        pub fn open_term_deposit(&mut self, amount: u128, term: Timestamp) -> Result<u64> {
+            self.when_not_paused()?;
+
+            let owner = self.env().caller();
+            self.not_blacklisted(owner)?;
+            if amount == 0 || term == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(owner) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+            let mut ids = self.term_deposits_of.get(owner).unwrap_or_default();
+            if ids.len() >= MAX_TERM_DEPOSITS {
+                return Err(Error::TooManyTermDeposits);
+            }
+
+            let opened_at = self.env().block_timestamp();
+            let matures_at = opened_at.checked_add(term).ok_or(Error::Overflow)?;
+            let interest = Self::interest_for(
+                amount,
+                (self.term_deposit_apr_bps as u128).saturating_mul(term as u128),
+            );
+
+            let id = self.next_term_deposit_id;
+            self.next_term_deposit_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.move_balance(owner, self.env().account_id(), amount)?;
+            self.term_deposits.insert(
+                id,
+                &TermDeposit {
+                    owner,
+                    amount,
+                    interest,
+                    opened_at,
+                    matures_at,
+                },
+            );
+            ids.push(id);
+            self.term_deposits_of.insert(owner, &ids);
+
+            self.env().emit_event(TermDepositOpened {
+                id,
+                owner,
+                amount,
+                interest,
+                matures_at,
+            });
+
+            Ok(id)
+        }
+
+        /// Closes one of the caller's term deposits. At maturity it pays out the
+        /// amount plus interest; before then the early-closure penalty is burned
+        /// from the amount and no interest is paid. Returns the payout.
+        #[ink(message)]
+        pub fn close_term_deposit(&mut self, id: u64) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let owner = self.env().caller();
+            self.not_blacklisted(owner)?;
+            let deposit = self
+                .term_deposits
+                .get(id)
+                .filter(|deposit| deposit.owner == owner)
+                .ok_or(Error::TermDepositNotFound)?;
+
+            let custody = self.env().account_id();
+            let (payout, penalty) = if self.env().block_timestamp() >= deposit.matures_at {
+                let interest = match self.cap {
+                    Some(cap) => deposit.interest.min(cap.saturating_sub(self.total_supply)),
+                    None => deposit.interest,
+                };
+                (deposit.amount.saturating_add(interest), 0)
+            } else {
+                let penalty = Self::bps_of(deposit.amount, self.early_closure_penalty_bps);
+                (deposit.amount - penalty, penalty)
+            };
+            let new_balance = self
+                .balance_of(owner)
+                .checked_add(payout)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(owner, new_balance)?;
+
+            if payout > deposit.amount {
+                self.mint_to_custody(payout - deposit.amount)?;
+            }
+            if penalty > 0 {
+                let new_custody_balance = self
+                    .balance_of(custody)
+                    .checked_sub(penalty)
+                    .ok_or(Error::Overflow)?;
+                self.write_balance(custody, new_custody_balance);
+                self.total_supply = self.total_supply.saturating_sub(penalty);
+
+                self.env().emit_event(Transfer {
+                    from: Some(custody),
+                    to: None,
+                    value: penalty,
+                    data: Vec::new(),
+                });
+            }
+            self.move_balance(custody, owner, payout)?;
+
+            self.term_deposits.remove(id);
+            let mut ids = self.term_deposits_of.get(owner).unwrap_or_default();
+            ids.retain(|open| *open != id);
+            self.term_deposits_of.insert(owner, &ids);
+
+            self.env().emit_event(TermDepositClosed {
+                id,
+                owner,
+                payout,
+                penalty,
+            });
+
+            Ok(payout)
+        }
+
+        /// An open term deposit
+        #[ink(message)]
+        pub fn term_deposit(&self, id: u64) -> Option<TermDeposit> {
+            self.term_deposits.get(id)
+        }
+
+        /// IDs of `account`'s open term deposits, oldest first
+        #[ink(message)]
+        pub fn term_deposits_of(&self, account: AccountId) -> Vec<u64> {
+            self.term_deposits_of.get(account).unwrap_or_default()
+        }
+
+        /// Annual rate for new term deposits and the early-closure penalty, in
+        /// basis points
+        #[ink(message)]
+        pub fn term_deposit_terms(&self) -> (u16, u16) {
+            (self.term_deposit_apr_bps, self.early_closure_penalty_bps)
+        }
+
+        /// Sets the rate for new term deposits and the penalty on the amount of
+        /// deposits closed before maturity. Open deposits keep their interest.
+        #[ink(message)]
+        pub fn set_term_deposit_terms(&mut self, apr_bps: u16, penalty_bps: u16) -> Result<()> {
+            self.only_owner()?;
+            if apr_bps > MAX_SAVINGS_APR_BPS || penalty_bps > 10_000 {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.term_deposit_apr_bps = apr_bps;
+            self.early_closure_penalty_bps = penalty_bps;
+
+            self.audit(AuditOperation::Config, &(apr_bps, penalty_bps));
+            self.env().emit_event(TermDepositTermsUpdated {
+                apr_bps,
+                penalty_bps,
+            });
+
+            Ok(())
+        }
+
+        /// Opens a savings goal with a target amount, an unlock date or both and
+        /// returns its ID
+        #[ink(message)]
+        pub fn create_savings_goal(
+            &mut self,
+            target: Option<u128>,
+            unlock_at: Option<Timestamp>,
+        ) -> Result<u64> {
+            let owner = self.env().caller();
+            self.not_blacklisted(owner)?;
+            if target.is_none() && unlock_at.is_none() || target == Some(0) {
+                return Err(Error::InvalidAmount);
+            }
+            let mut ids = self.savings_goals_of.get(owner).unwrap_or_default();
+            if ids.len() >= MAX_SAVINGS_GOALS {
+                return Err(Error::TooManySavingsGoals);
+            }
+
+            let id = self.next_savings_goal_id;
+            self.next_savings_goal_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.savings_goals.insert(
+                id,
+                &SavingsGoal {
+                    owner,
+                    balance: 0,
+                    target,
+                    unlock_at,
+                },
+            );
+            ids.push(id);
+            self.savings_goals_of.insert(owner, &ids);
+
+            self.env().emit_event(SavingsGoalCreated {
+                id,
+                owner,
+                target,
+                unlock_at,
+            });
+
+            Ok(id)
+        }
+
+        /// Moves `amount` of the caller's spendable balance into its savings goal
+        #[ink(message)]
+        pub fn deposit_to_goal(&mut self, id: u64, amount: u128) -> Result<()> {
+            self.when_not_paused()?;
+
+            let owner = self.env().caller();
+            self.not_blacklisted(owner)?;
+            let mut goal = self.owned_savings_goal(id, owner)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(owner) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            goal.balance = goal.balance.checked_add(amount).ok_or(Error::Overflow)?;
+            self.move_balance(owner, self.env().account_id(), amount)?;
+            self.savings_goals.insert(id, &goal);
+
+            self.env().emit_event(SavingsGoalDeposit { id, amount });
+
+            Ok(())
+        }
+
+        /// Takes `amount` out of the caller's savings goal. Before the goal is met
+        /// the goal penalty goes to the incentive pool. Returns what the caller
+        /// received; the goal is closed once it is empty.
+        #[ink(message)]
+        pub fn withdraw_from_goal(&mut self, id: u64, amount: u128) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let owner = self.env().caller();
+            self.not_blacklisted(owner)?;
+            let mut goal = self.owned_savings_goal(id, owner)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            let penalty = if self.is_goal_met(&goal) {
+                0
+            } else {
+                Self::bps_of(amount, self.goal_penalty_bps)
+            };
+            goal.balance = goal
+                .balance
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientBalance)?;
+            let payout = amount - penalty;
+            let new_balance = self
+                .balance_of(owner)
+                .checked_add(payout)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(owner, new_balance)?;
+
+            self.incentive_pool = self
+                .incentive_pool
+                .checked_add(penalty)
+                .ok_or(Error::Overflow)?;
+            self.move_balance(self.env().account_id(), owner, payout)?;
+            if goal.balance == 0 {
+                self.savings_goals.remove(id);
+                let mut ids = self.savings_goals_of.get(owner).unwrap_or_default();
+                ids.retain(|open| *open != id);
+                self.savings_goals_of.insert(owner, &ids);
+            } else {
+                self.savings_goals.insert(id, &goal);
+            }
+
+            self.env().emit_event(SavingsGoalWithdrawal {
+                id,
+                amount,
+                penalty,
+            });
+
+            Ok(payout)
+        }
+
+        #[ink(message)]
+        pub fn savings_goal(&self, id: u64) -> Option<SavingsGoal> {
+            self.savings_goals.get(id)
+        }
+
+        /// IDs of the savings goals `account` has open
+        #[ink(message)]
+        pub fn savings_goals_of(&self, account: AccountId) -> Vec<u64> {
+            self.savings_goals_of.get(account).unwrap_or_default()
+        }
+
+        /// Whether savings goal `id` has reached its target and unlock date
+        #[ink(message)]
+        pub fn goal_met(&self, id: u64) -> bool {
+            self.savings_goals
+                .get(id)
+                .is_some_and(|goal| self.is_goal_met(&goal))
+        }
+
+        /// Share of a withdrawal from an unmet savings goal paid to the incentive
+        /// pool, in basis points
+        #[ink(message)]
+        pub fn goal_penalty(&self) -> u16 {
+            self.goal_penalty_bps
+        }
+
+        #[ink(message)]
+        pub fn set_goal_penalty(&mut self, penalty_bps: u16) -> Result<()> {
+            self.only_owner()?;
+            if penalty_bps > 10_000 {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.goal_penalty_bps = penalty_bps;
+
+            self.audit(AuditOperation::Config, &penalty_bps);
+            self.env().emit_event(GoalPenaltyUpdated { penalty_bps });
+
+            Ok(())
+        }
+
+        /// Penalties collected from savings goals, held in the contract's custody
+        #[ink(message)]
+        pub fn incentive_pool(&self) -> u128 {
+            self.incentive_pool
+        }
+
+        /// Pays `amount` out of the incentive pool, e.g. as a bonus to savers who
+        /// met their goals
+        #[ink(message)]
+        pub fn pay_incentive(&mut self, to: AccountId, amount: u128) -> Result<()> {
+            self.only_owner()?;
+            self.not_blacklisted(to)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.incentive_pool = self
+                .incentive_pool
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientBalance)?;
+            self.move_balance(self.env().account_id(), to, amount)?;
+
+            self.audit(AuditOperation::Config, &(to, amount));
+            self.env().emit_event(IncentivePaid { to, amount });
+
+            Ok(())
+        }
+
+        fn owned_savings_goal(&self, id: u64, owner: AccountId) -> Result<SavingsGoal> {
+            self.savings_goals
+                .get(id)
+                .filter(|goal| goal.owner == owner)
+                .ok_or(Error::SavingsGoalNotFound)
+        }
+
+        fn is_goal_met(&self, goal: &SavingsGoal) -> bool {
+            goal.target.is_none_or(|target| goal.balance >= target)
+                && goal
+                    .unlock_at
+                    .is_none_or(|unlock_at| self.env().block_timestamp() >= unlock_at)
+        }
+
+        /// Sets the minimum balance an account may hold; 0 turns it off.
+        /// Transfers can't leave a recipient below it. A sender left with less
+        /// either has the transfer refused or, with `sweep_dust`, sends the
+        /// leftover dust along with it. Moves into and out of the contract's own
+        /// custody are exempt.
+        #[ink(message)]
+        pub fn set_min_balance(&mut self, min_balance: u128, sweep_dust: bool) -> Result<()> {
+            self.only_owner()?;
+
+            self.min_balance = min_balance;
+            self.sweep_dust = sweep_dust;
+
+            self.audit(AuditOperation::Config, &(min_balance, sweep_dust));
+            self.env().emit_event(MinBalanceUpdated {
+                min_balance,
+                sweep_dust,
+            });
+
+            Ok(())
+        }
+
+        /// `(min_balance, sweep_dust)`
+        #[ink(message)]
+        pub fn min_balance(&self) -> (u128, bool) {
+            (self.min_balance, self.sweep_dust)
+        }
+
+        /// `amount` plus whatever would be left below the minimum balance, when
+        /// dust is swept. The contract's own custody is exempt.
+        fn with_swept_dust(&self, from: AccountId, amount: u128) -> u128 {
+            if from == self.env().account_id() {
+                return amount;
+            }
+            let balance = self.balance_of(from);
+            let remainder = balance.saturating_sub(amount);
+            if self.sweep_dust && remainder > 0 && remainder < self.min_balance {
+                balance
+            } else {
+                amount
+            }
+        }
+
+        /// Moves the caller's whole balance to `beneficiary` and clears its
+        /// balance and the allowances it granted and received, releasing their
+        /// storage deposits. Fails while the caller owes an overdraft or a loan.
+        /// Only the first `MAX_TRACKED_ALLOWANCES` counterparties per side are
+        /// cleared.
+        #[ink(message)]
+        pub fn close_account(&mut self, beneficiary: AccountId) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            self.not_blacklisted(beneficiary)?;
+            if account == beneficiary {
+                return Err(Error::InvalidAmount);
+            }
+            if self.debt_of(account) > 0
+                || self.loan_of(account) > 0
+                || self.salary_advances.contains(account)
+            {
+                return Err(Error::DebtOutstanding);
+            }
+
+            let amount = self.balance_of(account);
+            if amount > 0 {
+                self._transfer(account, beneficiary, amount, Vec::new())?;
+            }
+            self.checkpoint_balance(account);
+            self.balances.remove(account);
+            for spender in self.approved_spenders.take(account).unwrap_or_default() {
+                self.allowances.remove((account, spender));
+            }
+            for owner in self.allowance_owners.take(account).unwrap_or_default() {
+                self.allowances.remove((owner, account));
+            }
+
+            self.env().emit_event(AccountClosed {
+                account,
+                beneficiary,
+                amount,
+            });
+
+            Ok(amount)
+        }
+
+        /// Remembers the pair so `close_account` can clear the allowance
+        fn track_allowance(&mut self, owner: AccountId, spender: AccountId) {
+            let mut spenders = self.approved_spenders.get(owner).unwrap_or_default();
+            if !spenders.contains(&spender) && spenders.len() < MAX_TRACKED_ALLOWANCES {
+                spenders.push(spender);
+                self.approved_spenders.insert(owner, &spenders);
+            }
+            let mut owners = self.allowance_owners.get(spender).unwrap_or_default();
+            if !owners.contains(&owner) && owners.len() < MAX_TRACKED_ALLOWANCES {
+                owners.push(owner);
+                self.allowance_owners.insert(spender, &owners);
+            }
+        }
+
+        /// Sets the dormancy rules, or with `None` stops flagging and sweeping
+        /// dormant accounts
+        #[ink(message)]
+        pub fn set_dormancy_rules(&mut self, rules: Option<DormancyRules>) -> Result<()> {
+            self.only_owner()?;
+            if rules.is_some_and(|rules| rules.dormancy_period < MIN_DORMANCY_PERIOD) {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.dormancy_rules = rules;
+
+            self.audit(AuditOperation::Config, &rules);
+            self.env().emit_event(DormancyRulesUpdated { rules });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn dormancy_rules(&self) -> Option<DormancyRules> {
+            self.dormancy_rules
+        }
+
+        /// When `account` last sent tokens or checked in, or else first received
+        /// tokens
+        #[ink(message)]
+        pub fn last_activity(&self, account: AccountId) -> Timestamp {
+            self.last_activity.get(account).unwrap_or_else(|| {
+                self.credit_history
+                    .get(account)
+                    .map_or(0, |history| history.opened_at)
+            })
+        }
+
+        /// When `account` was flagged dormant, if it hasn't been active since
+        #[ink(message)]
+        pub fn dormant_since(&self, account: AccountId) -> Option<Timestamp> {
+            self.dormant_since.get(account)
+        }
+
+        /// Compliance flags `account` as dormant once it has been inactive for the
+        /// dormancy period, starting the notice period before it can be swept
+        #[ink(message)]
+        pub fn flag_dormant(&mut self, account: AccountId) -> Result<()> {
+            self.only_compliance()?;
+            let rules = self.dormancy_rules.ok_or(Error::AccountNotDormant)?;
+            let now = self.env().block_timestamp();
+            if self.dormant_since.contains(account)
+                || now
+                    < self
+                        .last_activity(account)
+                        .saturating_add(rules.dormancy_period)
+            {
+                return Err(Error::AccountNotDormant);
+            }
+
+            self.dormant_since.insert(account, &now);
+
+            self.audit(AuditOperation::Config, &account);
+            self.env().emit_event(DormancyNotice {
+                account,
+                escheat_after: now.saturating_add(rules.notice_period),
+            });
+
+            Ok(())
+        }
+
+        /// Sweeps the whole balance of an account flagged dormant to the custodian
+        /// once the notice period has passed. Returns the amount.
+        #[ink(message)]
+        pub fn escheat(&mut self, account: AccountId) -> Result<u128> {
+            self.only_compliance()?;
+            let rules = self.dormancy_rules.ok_or(Error::AccountNotDormant)?;
+            let flagged_at = self
+                .dormant_since
+                .get(account)
+                .ok_or(Error::AccountNotDormant)?;
+            if self.env().block_timestamp() < flagged_at.saturating_add(rules.notice_period) {
+                return Err(Error::NoticePeriodActive);
+            }
+
+            let amount = self.balance_of(account);
+            self.move_balance(account, rules.custodian, amount)?;
+            self.dormant_since.remove(account);
+
+            self.audit(AuditOperation::Seize, &(account, rules.custodian, amount));
+            self.env().emit_event(Escheated {
+                account,
+                custodian: rules.custodian,
+                amount,
+            });
+
+            Ok(amount)
+        }
+
+        /// Sets the share of every transfer routed into the insurance fund, in
+        /// basis points. 0 turns the levy off.
+        #[ink(message)]
+        pub fn set_insurance_levy(&mut self, levy_bps: u16) -> Result<()> {
+            self.only_owner()?;
+            if levy_bps > MAX_INSURANCE_LEVY_BPS {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.insurance_levy_bps = levy_bps;
+
+            self.audit(AuditOperation::Config, &levy_bps);
+            self.env().emit_event(InsuranceLevyUpdated { levy_bps });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn insurance_levy(&self) -> u16 {
+            self.insurance_levy_bps
+        }
+
+        /// Levies collected and not paid out yet, held in the contract's custody
+        #[ink(message)]
+        pub fn insurance_fund(&self) -> u128 {
+            self.insurance_fund
+        }
+
+        /// Reimburses `to` from the insurance fund after an incident. Once the
+        /// admin multisig is enabled this has to go through
+        /// `AdminAction::InsurancePayout`.
+        #[ink(message)]
+        pub fn pay_insurance(&mut self, to: AccountId, amount: u128) -> Result<()> {
+            self.only_owner_direct()?;
+            self.pay_from_insurance(to, amount)
+        }
+
+        /// Insurance payouts with IDs in `offset..offset + limit`, oldest first
+        #[ink(message)]
+        pub fn insurance_payouts(&self, offset: u64, limit: u32) -> Vec<(u64, InsurancePayout)> {
+            let end = offset
+                .saturating_add(limit as u64)
+                .min(self.insurance_payout_count);
+            (offset..end)
+                .filter_map(|id| self.insurance_payouts.get(id).map(|payout| (id, payout)))
+                .collect()
+        }
+
+        fn pay_from_insurance(&mut self, to: AccountId, amount: u128) -> Result<()> {
+            self.not_blacklisted(to)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            self.insurance_fund = self
+                .insurance_fund
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientBalance)?;
+            let new_balance = self
+                .balance_of(to)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(to, new_balance)?;
+
+            self.move_balance(self.env().account_id(), to, amount)?;
+            let id = self.insurance_payout_count;
+            self.insurance_payout_count = id.saturating_add(1);
+            self.insurance_payouts.insert(
+                id,
+                &InsurancePayout {
+                    to,
+                    amount,
+                    paid_at: self.env().block_timestamp(),
+                },
+            );
+
+            self.audit(AuditOperation::Config, &(to, amount));
+            self.env().emit_event(InsurancePaid { id, to, amount });
+
+            Ok(())
+        }
+
+        /// Levy a transfer of `amount` pays; moves to and from the contract's own
+        /// custody are exempt
+        fn insurance_levy_for(&self, from: AccountId, to: AccountId, amount: u128) -> u128 {
+            let custody = self.env().account_id();
+            if from == custody || to == custody {
+                return 0;
+            }
+            Self::bps_of(amount, self.insurance_levy_bps)
+        }
+
+        /// Credits a levy already taken off a transfer from `from` to the fund
+        fn collect_insurance_levy(&mut self, from: AccountId, levy: u128) -> Result<()> {
+            let custody = self.env().account_id();
+            self.settle_demurrage(custody);
+            let new_custody_balance = self
+                .balance_of(custody)
+                .checked_add(levy)
+                .ok_or(Error::Overflow)?;
+            self.insurance_fund = self
+                .insurance_fund
+                .checked_add(levy)
+                .ok_or(Error::Overflow)?;
+            self.write_balance(custody, new_custody_balance);
+
+            self.env().emit_event(Transfer {
+                from: Some(from),
+                to: Some(custody),
+                value: levy,
+                data: Vec::new(),
+            });
+
+            Ok(())
+        }
+
+        /// Moves `amount` of the caller's tokens into the treasury
+        #[ink(message)]
+        pub fn fund_treasury(&mut self, amount: u128) -> Result<()> {
+            self.when_not_paused()?;
+
+            let from = self.env().caller();
+            self.not_blacklisted(from)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(from) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.treasury_balance = self
+                .treasury_balance
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.move_balance(from, self.env().account_id(), amount)?;
+
+            self.env().emit_event(TreasuryFunded { from, amount });
+
+            Ok(())
+        }
+
+        /// Tokens held by the treasury, in the contract's custody
+        #[ink(message)]
+        pub fn treasury_balance(&self) -> u128 {
+            self.treasury_balance
+        }
+
+        /// Sets the account that approves treasury spending
+        #[ink(message)]
+        pub fn set_treasurer(&mut self, treasurer: Option<AccountId>) -> Result<()> {
+            self.only_owner()?;
+
+            self.treasurer = treasurer;
+
+            self.audit(AuditOperation::Config, &treasurer);
+            self.env().emit_event(TreasurerUpdated { treasurer });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn treasurer(&self) -> Option<AccountId> {
+            self.treasurer
+        }
+
+        /// Creates budget line `name` or changes its allocation. The allocation
+        /// can't go below what the line already spent.
+        #[ink(message)]
+        pub fn set_budget(&mut self, name: String, allocated: u128) -> Result<()> {
+            self.only_owner()?;
+            if name.is_empty() || name.len() > MAX_BUDGET_NAME_LEN {
+                return Err(Error::InvalidBudgetName);
+            }
+
+            let mut budget = match self.budgets.get(&name) {
+                Some(budget) => budget,
+                None => {
+                    if self.budget_names.len() >= MAX_BUDGET_LINES {
+                        return Err(Error::TooManyBudgets);
+                    }
+                    self.budget_names.push(name.clone());
+                    Budget::default()
+                }
+            };
+            if allocated < budget.spent {
+                return Err(Error::InvalidAmount);
+            }
+            budget.allocated = allocated;
+            self.budgets.insert(&name, &budget);
+
+            self.audit(AuditOperation::Config, &(&name, allocated));
+            self.env().emit_event(BudgetSet { name, allocated });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn budget(&self, name: String) -> Option<Budget> {
+            self.budgets.get(name)
+        }
+
+        /// Every budget line with its allocation and spending so far
+        #[ink(message)]
+        pub fn budgets(&self) -> Vec<(String, Budget)> {
+            self.budget_names
+                .iter()
+                .filter_map(|name| self.budgets.get(name).map(|budget| (name.clone(), budget)))
+                .collect()
+        }
+
+        /// Proposes paying `amount` to `to` out of budget line `budget`, for the
+        /// treasurer to approve. Returns the proposal ID.
+        #[ink(message)]
+        pub fn propose_spend(
+            &mut self,
+            budget: String,
+            to: AccountId,
+            amount: u128,
+        ) -> Result<u64> {
+            self.only_owner()?;
+            self.budgets.get(&budget).ok_or(Error::BudgetNotFound)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            let id = self.next_spend_id;
+            self.next_spend_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.spend_proposals.insert(
+                id,
+                &SpendProposal {
+                    budget: budget.clone(),
+                    to,
+                    amount,
+                },
+            );
+
+            self.env().emit_event(SpendProposed {
+                id,
+                budget,
+                to,
+                amount,
+            });
+
+            Ok(id)
+        }
+
+        /// Treasurer decision on spend proposal `id`: pays it out of the treasury
+        /// if `approve` is true and its budget line has room, otherwise drops it
+        #[ink(message)]
+        pub fn decide_spend(&mut self, id: u64, approve: bool) -> Result<()> {
+            self.only_treasurer()?;
+            let proposal = self.spend_proposals.get(id).ok_or(Error::SpendNotFound)?;
+
+            if approve {
+                self.when_not_paused()?;
+                self.not_blacklisted(proposal.to)?;
+                let mut budget = self
+                    .budgets
+                    .get(&proposal.budget)
+                    .ok_or(Error::BudgetNotFound)?;
+                budget.spent = budget
+                    .spent
+                    .checked_add(proposal.amount)
+                    .filter(|spent| *spent <= budget.allocated)
+                    .ok_or(Error::BudgetExceeded)?;
+                self.treasury_balance = self
+                    .treasury_balance
+                    .checked_sub(proposal.amount)
+                    .ok_or(Error::InsufficientBalance)?;
+                let new_balance = self
+                    .balance_of(proposal.to)
+                    .checked_add(proposal.amount)
+                    .ok_or(Error::Overflow)?;
+                self.within_balance_limit(proposal.to, new_balance)?;
+
+                self.move_balance(self.env().account_id(), proposal.to, proposal.amount)?;
+                self.budgets.insert(&proposal.budget, &budget);
+            }
+            self.spend_proposals.remove(id);
+
+            self.audit(AuditOperation::Config, &(id, approve));
+            self.env().emit_event(SpendDecided {
+                id,
+                approved: approve,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn spend_proposal(&self, id: u64) -> Option<SpendProposal> {
+            self.spend_proposals.get(id)
+        }
+
+        /// Grants `total` from the treasury to `grantee`, charged to budget line
+        /// `budget` and vesting like `create_vesting_schedule`. The grantee
+        /// claims with `claim_vested`. Only the treasurer can issue grants.
+        /// Returns the vesting schedule ID.
+        #[ink(message)]
+        pub fn issue_grant(
+            &mut self,
+            budget: String,
+            grantee: AccountId,
+            total: u128,
+            start: Timestamp,
+            cliff: Timestamp,
+            duration: Timestamp,
+        ) -> Result<u64> {
+            self.only_treasurer()?;
+            self.not_blacklisted(grantee)?;
+            if total == 0 || duration == 0 || cliff > duration {
+                return Err(Error::InvalidAmount);
+            }
+            start.checked_add(duration).ok_or(Error::Overflow)?;
+            let mut line = self.budgets.get(&budget).ok_or(Error::BudgetNotFound)?;
+            line.spent = line
+                .spent
+                .checked_add(total)
+                .filter(|spent| *spent <= line.allocated)
+                .ok_or(Error::BudgetExceeded)?;
+            let treasury_balance = self
+                .treasury_balance
+                .checked_sub(total)
+                .ok_or(Error::InsufficientBalance)?;
+            let mut ids = self.vesting_schedules_of.get(grantee).unwrap_or_default();
+            if ids.len() >= MAX_VESTING_SCHEDULES {
+                return Err(Error::TooManyVestingSchedules);
+            }
+
+            let id = self.next_vesting_id;
+            self.next_vesting_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            // The tokens are already in custody, they just stop being the treasury's
+            self.treasury_balance = treasury_balance;
+            self.budgets.insert(&budget, &line);
+            self.vesting_schedules.insert(
+                id,
+                &VestingSchedule {
+                    beneficiary: grantee,
+                    total,
+                    released: 0,
+                    start,
+                    cliff,
+                    duration,
+                },
+            );
+            ids.push(id);
+            self.vesting_schedules_of.insert(grantee, &ids);
+            self.grant_budgets.insert(id, &budget);
+
+            self.env().emit_event(VestingScheduleCreated {
+                id,
+                beneficiary: grantee,
+                total,
+                start,
+                cliff,
+                duration,
+            });
+            self.env().emit_event(GrantIssued { id, budget });
+
+            Ok(id)
+        }
+
+        /// Stops grant `id` from vesting further. What vested so far stays
+        /// claimable; the rest returns to the treasury and its budget line.
+        /// Returns the unvested amount.
+        #[ink(message)]
+        pub fn revoke_grant(&mut self, id: u64) -> Result<u128> {
+            self.only_treasurer()?;
+            let budget = self.grant_budgets.get(id).ok_or(Error::GrantNotFound)?;
+            let mut schedule = self.vesting_schedules.get(id).ok_or(Error::GrantNotFound)?;
+
+            let vested = self.vested(&schedule);
+            let unvested = schedule.total - vested;
+            self.treasury_balance = self
+                .treasury_balance
+                .checked_add(unvested)
+                .ok_or(Error::Overflow)?;
+            if let Some(mut line) = self.budgets.get(&budget) {
+                line.spent = line.spent.saturating_sub(unvested);
+                self.budgets.insert(&budget, &line);
+            }
+            if schedule.released == vested {
+                let mut ids = self
+                    .vesting_schedules_of
+                    .get(schedule.beneficiary)
+                    .unwrap_or_default();
+                ids.retain(|open| *open != id);
+                self.vesting_schedules_of.insert(schedule.beneficiary, &ids);
+                self.vesting_schedules.remove(id);
+            } else {
+                // Fully vested at the new total from now on
+                schedule.total = vested;
+                schedule.cliff = 0;
+                schedule.duration = self.env().block_timestamp() - schedule.start;
+                self.vesting_schedules.insert(id, &schedule);
+            }
+            self.grant_budgets.remove(id);
+
+            self.audit(AuditOperation::Config, &id);
+            self.env().emit_event(GrantRevoked { id, unvested });
+
+            Ok(unvested)
+        }
+
+        /// Budget line grant `id` is charged to, while it can still be revoked
+        #[ink(message)]
+        pub fn grant_budget(&self, id: u64) -> Option<String> {
+            self.grant_budgets.get(id)
+        }
+
+        fn only_treasurer(&self) -> Result<()> {
+            if self.treasurer != Some(self.env().caller()) {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+
+        /// Opens a campaign raising `target` by `deadline` and returns its ID
+        #[ink(message)]
+        pub fn create_campaign(&mut self, target: u128, deadline: Timestamp) -> Result<u64> {
+            let creator = self.env().caller();
+            self.not_blacklisted(creator)?;
+            if target == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if deadline <= self.env().block_timestamp() {
+                return Err(Error::InvalidExpiry);
+            }
+
+            let id = self.next_campaign_id;
+            self.next_campaign_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.campaigns.insert(
+                id,
+                &Campaign {
+                    creator,
+                    target,
+                    deadline,
+                    pledged: 0,
+                    backers: 0,
+                    claimed: false,
+                },
+            );
+
+            self.env().emit_event(CampaignCreated {
+                id,
+                creator,
+                target,
+                deadline,
+            });
+
+            Ok(id)
+        }
+
+        /// Pledges `amount` of the caller's tokens to campaign `id` before its
+        /// deadline. Pledging can go past the target.
+        #[ink(message)]
+        pub fn pledge(&mut self, id: u64, amount: u128) -> Result<()> {
+            self.when_not_paused()?;
+
+            let backer = self.env().caller();
+            self.not_blacklisted(backer)?;
+            let mut campaign = self.campaigns.get(id).ok_or(Error::CampaignNotFound)?;
+            if self.env().block_timestamp() >= campaign.deadline {
+                return Err(Error::CampaignEnded);
+            }
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(backer) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            let pledge = self.pledge_of(id, backer);
+            if pledge == 0 {
+                self.campaign_pledgers
+                    .insert((id, campaign.backers), &backer);
+                campaign.backers = campaign.backers.checked_add(1).ok_or(Error::Overflow)?;
+            }
+            campaign.pledged = campaign
+                .pledged
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.move_balance(backer, self.env().account_id(), amount)?;
+            self.pledges
+                .insert((id, backer), &pledge.saturating_add(amount));
+            self.campaigns.insert(id, &campaign);
+
+            self.env().emit_event(Pledged { id, backer, amount });
+
+            Ok(())
+        }
+
+        /// Pays everything pledged to campaign `id` to its creator, once the
+        /// deadline has passed with the target met
+        #[ink(message)]
+        pub fn claim_campaign(&mut self, id: u64) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let creator = self.env().caller();
+            self.not_blacklisted(creator)?;
+            let mut campaign = self
+                .campaigns
+                .get(id)
+                .filter(|campaign| campaign.creator == creator && !campaign.claimed)
+                .ok_or(Error::CampaignNotFound)?;
+            if self.env().block_timestamp() < campaign.deadline {
+                return Err(Error::CampaignNotEnded);
+            }
+            if campaign.pledged < campaign.target {
+                return Err(Error::TargetNotMet);
+            }
+            let amount = campaign.pledged;
+            let new_balance = self
+                .balance_of(creator)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(creator, new_balance)?;
+
+            campaign.claimed = true;
+            self.campaigns.insert(id, &campaign);
+            self.move_balance(self.env().account_id(), creator, amount)?;
+
+            self.env().emit_event(CampaignClaimed { id, amount });
+
+            Ok(amount)
+        }
+
+        /// Returns the caller's pledge to campaign `id` after it ended below its
+        /// target
+        #[ink(message)]
+        pub fn refund_pledge(&mut self, id: u64) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let backer = self.env().caller();
+            self.not_blacklisted(backer)?;
+            let campaign = self.campaigns.get(id).ok_or(Error::CampaignNotFound)?;
+            if self.env().block_timestamp() < campaign.deadline {
+                return Err(Error::CampaignNotEnded);
+            }
+            if campaign.pledged >= campaign.target {
+                return Err(Error::TargetMet);
+            }
+            let amount = self.pledge_of(id, backer);
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            let new_balance = self
+                .balance_of(backer)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(backer, new_balance)?;
+
+            self.pledges.insert((id, backer), &0);
+            self.move_balance(self.env().account_id(), backer, amount)?;
+
+            self.env().emit_event(PledgeRefunded { id, backer, amount });
+
+            Ok(amount)
+        }
+
+        #[ink(message)]
+        pub fn campaign(&self, id: u64) -> Option<Campaign> {
+            self.campaigns.get(id)
+        }
+
+        /// Campaigns with IDs in `offset..offset + limit`
+        #[ink(message)]
+        pub fn campaigns(&self, offset: u64, limit: u32) -> Vec<(u64, Campaign)> {
+            let end = offset
+                .saturating_add(limit as u64)
+                .min(self.next_campaign_id);
+            (offset..end)
+                .filter_map(|id| self.campaigns.get(id).map(|campaign| (id, campaign)))
+                .collect()
+        }
+
+        /// What `backer` has pledged to campaign `id` and not taken back
+        #[ink(message)]
+        pub fn pledge_of(&self, id: u64, backer: AccountId) -> u128 {
+            self.pledges.get((id, backer)).unwrap_or(0)
+        }
+
+        /// Page through campaign `id`'s backers in the order they first pledged,
+        /// with their current pledges
+        #[ink(message)]
+        pub fn pledges(&self, id: u64, offset: u32, limit: u32) -> Vec<(AccountId, u128)> {
+            let backers = self
+                .campaigns
+                .get(id)
+                .map_or(0, |campaign| campaign.backers);
+            let end = offset.saturating_add(limit).min(backers);
+            (offset..end)
+                .filter_map(|index| self.campaign_pledgers.get((id, index)))
+                .map(|backer| (backer, self.pledge_of(id, backer)))
+                .collect()
+        }
+
+        /// Lets `cause` receive matched donations, or with `active` false retires it
+        #[ink(message)]
+        pub fn set_cause(&mut self, cause: AccountId, active: bool) -> Result<()> {
+            self.only_owner()?;
+
+            let mut record = self.causes.get(cause).unwrap_or_default();
+            record.active = active;
+            self.causes.insert(cause, &record);
+
+            self.audit(AuditOperation::Config, &(cause, active));
+            self.env().emit_event(CauseUpdated { cause, active });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn cause(&self, cause: AccountId) -> Option<Cause> {
+            self.causes.get(cause)
+        }
+
+        /// Sets how much the pool adds per donated token, in basis points:
+        /// 10_000 matches donations 1:1
+        #[ink(message)]
+        pub fn set_matching_ratio(&mut self, ratio_bps: u16) -> Result<()> {
+            self.only_owner()?;
+            if ratio_bps > MAX_MATCHING_RATIO_BPS {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.matching_ratio_bps = ratio_bps;
+
+            self.audit(AuditOperation::Config, &ratio_bps);
+            self.env().emit_event(MatchingUpdated {
+                ratio_bps,
+                pool: self.matching_pool,
+            });
+
+            Ok(())
+        }
+
+        /// Moves `amount` of the owner's tokens into the matching pool
+        #[ink(message)]
+        pub fn fund_matching_pool(&mut self, amount: u128) -> Result<()> {
+            self.only_owner()?;
+            let owner = self.env().caller();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(owner) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.matching_pool = self
+                .matching_pool
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.move_balance(owner, self.env().account_id(), amount)?;
+
+            self.env().emit_event(MatchingUpdated {
+                ratio_bps: self.matching_ratio_bps,
+                pool: self.matching_pool,
+            });
+
+            Ok(())
+        }
+
+        /// `(ratio_bps, pool)`
+        #[ink(message)]
+        pub fn matching(&self) -> (u16, u128) {
+            (self.matching_ratio_bps, self.matching_pool)
+        }
+
+        /// Gives `amount` to an active `cause` and tops it up from the matching
+        /// pool at the matching ratio, as far as the pool goes. Returns the
+        /// matched amount.
+        #[ink(message)]
+        pub fn donate(&mut self, cause: AccountId, amount: u128) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let donor = self.env().caller();
+            self.not_blacklisted(donor)?;
+            self.not_blacklisted(cause)?;
+            let mut record = self
+                .causes
+                .get(cause)
+                .filter(|record| record.active)
+                .ok_or(Error::CauseNotFound)?;
+
+            self._transfer(donor, cause, amount, Vec::new())?;
+            let matched = Self::bps_of(amount, self.matching_ratio_bps).min(self.matching_pool);
+            if matched > 0 {
+                self.matching_pool -= matched;
+                self.move_balance(self.env().account_id(), cause, matched)?;
+            }
+            record.donated = record.donated.saturating_add(amount);
+            record.matched = record.matched.saturating_add(matched);
+            self.causes.insert(cause, &record);
+
+            self.env().emit_event(Donation {
+                donor,
+                cause,
+                amount,
+                matched,
+            });
+
+            Ok(matched)
+        }
+
+        /// Pays `amount` to `payee` through the contract: the payee only gets it
+        /// once the dispute window has passed without a dispute. Returns the
+        /// payment ID.
+        #[ink(message)]
+        pub fn pay_protected(&mut self, payee: AccountId, amount: u128) -> Result<u64> {
+            self.when_not_paused()?;
+
+            let payer = self.env().caller();
+            self.not_blacklisted(payer)?;
+            self.not_blacklisted(payee)?;
+            if amount == 0 || payer == payee {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(payer) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            let id = self.next_protected_payment_id;
+            self.next_protected_payment_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            let release_at = self
+                .env()
+                .block_timestamp()
+                .saturating_add(self.dispute_window);
+            self.move_balance(payer, self.env().account_id(), amount)?;
+            self.protected_payments.insert(
+                id,
+                &ProtectedPayment {
+                    payer,
+                    payee,
+                    amount,
+                    release_at,
+                    disputed: false,
+                },
+            );
+
+            self.env().emit_event(ProtectedPaymentCreated {
+                id,
+                payer,
+                payee,
+                amount,
+                release_at,
+            });
+
+            Ok(id)
+        }
+
+        /// Pays protected payment `id` to the payee. The payer may release it at
+        /// any time, anyone else once the dispute window has passed.
+        #[ink(message)]
+        pub fn release_protected_payment(&mut self, id: u64) -> Result<()> {
+            self.when_not_paused()?;
+
+            let payment = self
+                .protected_payments
+                .get(id)
+                .ok_or(Error::ProtectedPaymentNotFound)?;
+            if payment.disputed {
+                return Err(Error::PaymentDisputed);
+            }
+            if self.env().caller() != payment.payer
+                && self.env().block_timestamp() < payment.release_at
+            {
+                return Err(Error::DisputeWindowOpen);
+            }
+
+            self.settle_protected_payment(id, payment, false)
+        }
+
+        /// Disputes protected payment `id`, holding it until the compliance
+        /// officer rules. Only the payer can, and only within the window.
+        #[ink(message)]
+        pub fn dispute_payment(&mut self, id: u64) -> Result<()> {
+            let payer = self.env().caller();
+            let mut payment = self
+                .protected_payments
+                .get(id)
+                .filter(|payment| payment.payer == payer)
+                .ok_or(Error::ProtectedPaymentNotFound)?;
+            if payment.disputed {
+                return Err(Error::PaymentDisputed);
+            }
+            if self.env().block_timestamp() >= payment.release_at {
+                return Err(Error::DisputeWindowClosed);
+            }
+
+            payment.disputed = true;
+            self.protected_payments.insert(id, &payment);
+
+            self.env().emit_event(PaymentDisputed { id });
+
+            Ok(())
+        }
+
+        /// Compliance ruling on a disputed payment: refunds the payer if `refund`
+        /// is true, otherwise pays the payee
+        #[ink(message)]
+        pub fn resolve_dispute(&mut self, id: u64, refund: bool) -> Result<()> {
+            self.only_compliance()?;
+            let payment = self
+                .protected_payments
+                .get(id)
+                .filter(|payment| payment.disputed)
+                .ok_or(Error::ProtectedPaymentNotFound)?;
+
+            self.settle_protected_payment(id, payment, refund)
+        }
+
+        #[ink(message)]
+        pub fn protected_payment(&self, id: u64) -> Option<ProtectedPayment> {
+            self.protected_payments.get(id)
+        }
+
+        #[ink(message)]
+        pub fn dispute_window(&self) -> Timestamp {
+            self.dispute_window
+        }
+
+        /// Sets the dispute window of protected payments made from now on
+        #[ink(message)]
+        pub fn set_dispute_window(&mut self, window: Timestamp) -> Result<()> {
+            self.only_owner()?;
+            if window > MAX_DISPUTE_WINDOW {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.dispute_window = window;
+
+            self.audit(AuditOperation::Config, &window);
+            self.env().emit_event(DisputeWindowUpdated { window });
+
+            Ok(())
+        }
+
+        fn settle_protected_payment(
+            &mut self,
+            id: u64,
+            payment: ProtectedPayment,
+            refund: bool,
+        ) -> Result<()> {
+            let to = if refund { payment.payer } else { payment.payee };
+            self.not_blacklisted(to)?;
+            let new_balance = self
+                .balance_of(to)
+                .checked_add(payment.amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(to, new_balance)?;
+
+            self.protected_payments.remove(id);
+            self.move_balance(self.env().account_id(), to, payment.amount)?;
+
+            self.env().emit_event(ProtectedPaymentSettled {
+                id,
+                refunded: refund,
+            });
+
+            Ok(())
+        }
+
+        /// Registers the caller as a merchant whose payments are settled to
+        /// `payout`, or updates its settings. With a non-zero `settlement_period`
+        /// anyone can trigger the settlement once that long has passed since the
+        /// last one.
+        #[ink(message)]
+        pub fn register_merchant(
+            &mut self,
+            payout: AccountId,
+            settlement_period: Timestamp,
+        ) -> Result<()> {
+            let merchant = self.env().caller();
+            self.not_blacklisted(merchant)?;
+            self.not_blacklisted(payout)?;
+
+            let now = self.env().block_timestamp();
+            let record = match self.merchants.get(merchant) {
+                Some(record) => Merchant {
+                    payout,
+                    settlement_period,
+                    ..record
+                },
+                None => Merchant {
+                    payout,
+                    settlement_period,
+                    last_settled_at: now,
+                    pending: 0,
+                },
+            };
+            self.merchants.insert(merchant, &record);
+
+            self.env().emit_event(MerchantRegistered {
+                merchant,
+                payout,
+                settlement_period,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn merchant(&self, merchant: AccountId) -> Option<Merchant> {
+            self.merchants.get(merchant)
+        }
+
+        /// Pays `amount` into `merchant`'s settlement balance, held by the
+        /// contract until the next settlement
+        #[ink(message)]
+        pub fn pay_merchant(&mut self, merchant: AccountId, amount: u128) -> Result<()> {
+            self.when_not_paused()?;
+
+            let payer = self.env().caller();
+            self.not_blacklisted(payer)?;
+            self.not_blacklisted(merchant)?;
+            let mut record = self
+                .merchants
+                .get(merchant)
+                .ok_or(Error::MerchantNotFound)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(payer) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            record.pending = record.pending.checked_add(amount).ok_or(Error::Overflow)?;
+            self.move_balance(payer, self.env().account_id(), amount)?;
+            self.merchants.insert(merchant, &record);
+            self.accrue_cashback(payer, merchant, amount);
+
+            self.env().emit_event(MerchantPayment {
+                payer,
+                merchant,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Pays `merchant`'s settlement balance, less the settlement fee, to its
+        /// payout account and returns the net amount. The merchant can settle at
+        /// any time, anyone else once its settlement period has passed. The fee
+        /// goes to the fee collector; without one there is none.
+        #[ink(message)]
+        pub fn settle_merchant(&mut self, merchant: AccountId) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let mut record = self
+                .merchants
+                .get(merchant)
+                .ok_or(Error::MerchantNotFound)?;
+            let now = self.env().block_timestamp();
+            if self.env().caller() != merchant
+                && (record.settlement_period == 0
+                    || now
+                        < record
+                            .last_settled_at
+                            .saturating_add(record.settlement_period))
+            {
+                return Err(Error::SettlementNotDue);
+            }
+            self.not_blacklisted(record.payout)?;
+
+            let gross = record.pending;
+            let fee = match self.fee_collector {
+                Some(_) => Self::bps_of(gross, self.settlement_fee_bps),
+                None => 0,
+            };
+            let net = gross - fee;
+            let new_balance = self
+                .balance_of(record.payout)
+                .checked_add(net)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(record.payout, new_balance)?;
+
+            let custody = self.env().account_id();
+            self.move_balance(custody, record.payout, net)?;
+            if let Some(collector) = self.fee_collector.filter(|_| fee > 0) {
+                self.move_balance(custody, collector, fee)?;
+            }
+            record.pending = 0;
+            record.last_settled_at = now;
+            self.merchants.insert(merchant, &record);
+
+            let period = now / SETTLEMENT_REPORT_PERIOD;
+            let mut report = self.settlement_report(merchant, period);
+            report.settlements = report.settlements.saturating_add(1);
+            report.gross = report.gross.saturating_add(gross);
+            report.fees = report.fees.saturating_add(fee);
+            self.settlement_reports.insert((merchant, period), &report);
+
+            self.env().emit_event(MerchantSettled {
+                merchant,
+                payout: record.payout,
+                gross,
+                fee,
+            });
+
+            Ok(net)
+        }
+
+        /// `merchant`'s settlements on day `period`, counted in
+        /// `SETTLEMENT_REPORT_PERIOD`s since the epoch
+        #[ink(message)]
+        pub fn settlement_report(&self, merchant: AccountId, period: u64) -> SettlementReport {
+            self.settlement_reports
+                .get((merchant, period))
+                .unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn settlement_fee(&self) -> u16 {
+            self.settlement_fee_bps
+        }
+
+        #[ink(message)]
+        pub fn set_settlement_fee(&mut self, fee_bps: u16) -> Result<()> {
+            self.only_owner()?;
+            if fee_bps > MAX_FEE_BPS {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.settlement_fee_bps = fee_bps;
+
+            self.audit(AuditOperation::Config, &fee_bps);
+            self.env().emit_event(SettlementFeeUpdated { fee_bps });
+
+            Ok(())
+        }
+
+        /// Sets the share of payments to eligible merchants credited back to the
+        /// payer, in basis points
+        #[ink(message)]
+        pub fn set_cashback_rate(&mut self, cashback_bps: u16) -> Result<()> {
+            self.only_owner()?;
+            if cashback_bps > MAX_CASHBACK_BPS {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.cashback_bps = cashback_bps;
+
+            self.audit(AuditOperation::Config, &cashback_bps);
+            self.env().emit_event(CashbackConfigUpdated {
+                merchant: None,
+                cashback_bps,
+                eligible: cashback_bps > 0,
+            });
+
+            Ok(())
+        }
+
+        /// Makes payments to `merchant` earn cashback, or stops them from earning it
+        #[ink(message)]
+        pub fn set_cashback_merchant(&mut self, merchant: AccountId, eligible: bool) -> Result<()> {
+            self.only_owner()?;
+
+            if eligible {
+                self.cashback_merchants.insert(merchant, &());
+            } else {
+                self.cashback_merchants.remove(merchant);
+            }
+
+            self.audit(AuditOperation::Config, &(merchant, eligible));
+            self.env().emit_event(CashbackConfigUpdated {
+                merchant: Some(merchant),
+                cashback_bps: self.cashback_bps,
+                eligible,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_cashback_merchant(&self, merchant: AccountId) -> bool {
+            self.cashback_merchants.contains(merchant)
+        }
+
+        #[ink(message)]
+        pub fn cashback_rate(&self) -> u16 {
+            self.cashback_bps
+        }
+
+        /// Moves `amount` of the caller's tokens into the rewards pool that pays
+        /// out cashback
+        #[ink(message)]
+        pub fn fund_rewards_pool(&mut self, amount: u128) -> Result<()> {
+            self.when_not_paused()?;
+
+            let from = self.env().caller();
+            self.not_blacklisted(from)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(from) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.rewards_pool = self
+                .rewards_pool
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.move_balance(from, self.env().account_id(), amount)?;
+
+            self.env().emit_event(RewardsPoolFunded { from, amount });
+
+            Ok(())
+        }
+
+        /// Rewards not yet promised to anyone, held in the contract's custody
+        #[ink(message)]
+        pub fn rewards_pool(&self) -> u128 {
+            self.rewards_pool
+        }
+
+        /// Cashback `account` has earned and not claimed yet
+        #[ink(message)]
+        pub fn cashback_of(&self, account: AccountId) -> u128 {
+            self.cashback.get(account).unwrap_or(0)
+        }
+
+        /// Pays out the caller's accrued cashback and returns the amount
+        #[ink(message)]
+        pub fn claim_cashback(&mut self) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            let amount = self.cashback_of(account);
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            let new_balance = self
+                .balance_of(account)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(account, new_balance)?;
+
+            self.cashback.remove(account);
+            self.move_balance(self.env().account_id(), account, amount)?;
+
+            self.env().emit_event(CashbackClaimed { account, amount });
+
+            Ok(amount)
+        }
+
+        /// Starts, changes or with `None` ends the referral program. Bonuses are
+        /// paid from the rewards pool.
+        #[ink(message)]
+        pub fn set_referral_program(&mut self, program: Option<ReferralProgram>) -> Result<()> {
+            self.only_owner()?;
+            if program.is_some_and(|program| program.bonus == 0) {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.referral_program = program;
+
+            self.audit(AuditOperation::Config, &program);
+            self.env().emit_event(ReferralProgramUpdated { program });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn referral_program(&self) -> Option<ReferralProgram> {
+            self.referral_program
+        }
+
+        /// Records `referrer` as the account that referred the caller. It can be
+        /// set once, only before the caller's first transfer, and not to the
+        /// caller itself or to an account the caller referred.
+        #[ink(message)]
+        pub fn set_referrer(&mut self, referrer: AccountId) -> Result<()> {
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            self.not_blacklisted(referrer)?;
+            if referrer == account
+                || self
+                    .referrals
+                    .get(referrer)
+                    .is_some_and(|referral| referral.referrer == account)
+            {
+                return Err(Error::SelfReferral);
+            }
+            if self.referrals.contains(account)
+                || self
+                    .credit_history
+                    .get(account)
+                    .is_some_and(|history| history.transfers > 0)
+            {
+                return Err(Error::ReferrerAlreadySet);
+            }
+
+            self.referrals.insert(
+                account,
+                &Referral {
+                    referrer,
+                    transfers: 0,
+                    volume: 0,
+                    rewarded: false,
+                },
+            );
+            let mut stats = self.referral_stats(referrer);
+            stats.referred = stats.referred.saturating_add(1);
+            self.referral_stats.insert(referrer, &stats);
+
+            self.env().emit_event(ReferrerSet { account, referrer });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn referral_of(&self, account: AccountId) -> Option<Referral> {
+            self.referrals.get(account)
+        }
+
+        /// How many accounts `referrer` referred, how many earned it a bonus and
+        /// the bonuses paid in total
+        #[ink(message)]
+        pub fn referral_stats(&self, referrer: AccountId) -> ReferralStats {
+            self.referral_stats.get(referrer).unwrap_or_default()
+        }
+
+        /// Counts a transfer toward `account`'s referral thresholds and pays the
+        /// referrer's bonus once they are met, if the rewards pool covers it
+        fn record_referral(&mut self, account: AccountId, amount: u128) -> Result<()> {
+            let Some(mut referral) = self.referrals.get(account).filter(|r| !r.rewarded) else {
+                return Ok(());
+            };
+            referral.transfers = referral.transfers.saturating_add(1);
+            referral.volume = referral.volume.saturating_add(amount);
+
+            if let Some(program) = self.referral_program {
+                if referral.transfers >= program.min_transfers
+                    && referral.volume >= program.min_volume
+                    && self.rewards_pool >= program.bonus
+                {
+                    let referrer = referral.referrer;
+                    self.rewards_pool -= program.bonus;
+                    self.move_balance(self.env().account_id(), referrer, program.bonus)?;
+                    referral.rewarded = true;
+
+                    let mut stats = self.referral_stats(referrer);
+                    stats.rewarded = stats.rewarded.saturating_add(1);
+                    stats.earned = stats.earned.saturating_add(program.bonus);
+                    self.referral_stats.insert(referrer, &stats);
+
+                    self.env().emit_event(ReferralBonusPaid {
+                        referrer,
+                        account,
+                        bonus: program.bonus,
+                    });
+                }
+            }
+            self.referrals.insert(account, &referral);
+
+            Ok(())
+        }
+
+        /// Sets aside cashback for `payer` on a payment to an eligible merchant,
+        /// as far as the rewards pool allows
+        fn accrue_cashback(&mut self, payer: AccountId, merchant: AccountId, amount: u128) {
+            if self.cashback_bps == 0 || !self.cashback_merchants.contains(merchant) {
+                return;
+            }
+            let reward = Self::bps_of(amount, self.cashback_bps).min(self.rewards_pool);
+            if reward == 0 {
+                return;
+            }
+
+            self.rewards_pool -= reward;
+            self.cashback
+                .insert(payer, &self.cashback_of(payer).saturating_add(reward));
+
+            self.env().emit_event(CashbackAccrued {
+                payer,
+                merchant,
+                amount: reward,
+            });
+        }
+
+        /// Switches demurrage on at `rate_bps` a year with decayed balances paid to
+        /// `sink`, or off with `None`. A rate change keeps decay so far at the old
+        /// rate; switching off forgives decay that hasn't been collected.
+        #[ink(message)]
+        pub fn set_demurrage(&mut self, demurrage: Option<Demurrage>) -> Result<()> {
+            self.only_owner()?;
+            if demurrage.is_some_and(|demurrage| demurrage.rate_bps > MAX_DEMURRAGE_BPS) {
+                return Err(Error::InvalidAmount);
+            }
+
+            // Decay owed to the old sink is paid to the new one when settled
+            self.demurrage_index = self.current_demurrage_index();
+            self.demurrage_index_updated_at = self.env().block_timestamp();
+            if self.demurrage.is_none() {
+                self.demurrage_since = self.demurrage_index;
+            }
+            self.demurrage = demurrage;
+
+            self.audit(AuditOperation::Config, &demurrage);
+            self.env().emit_event(DemurrageUpdated { demurrage });
+
+            Ok(())
+        }
+
+        /// The demurrage rate and sink, if demurrage is on
+        #[ink(message)]
+        pub fn demurrage(&self) -> Option<Demurrage> {
+            self.demurrage
+        }
+
+        /// Decay `account` owes the sink that hasn't been collected yet.
+        /// `balance_of` already excludes it.
+        #[ink(message)]
+        pub fn demurrage_owed(&self, account: AccountId) -> u128 {
+            if self.demurrage_exempt(account) {
+                return 0;
+            }
+            let balance = self.balances.get(account).unwrap_or(0);
+            let settled_at = self
+                .demurrage_index_of
+                .get(account)
+                .unwrap_or(0)
+                .max(self.demurrage_since);
+            let growth = self.current_demurrage_index().saturating_sub(settled_at);
+            Self::interest_for(balance, growth).min(balance)
+        }
+
+        /// Pays `account`'s owed decay to the sink. Anyone may call it; it also
+        /// happens whenever the account's balance changes.
+        #[ink(message)]
+        pub fn collect_demurrage(&mut self, account: AccountId) {
+            self.settle_demurrage(account);
+        }
+
+        /// Sum of `rate_bps * elapsed_ms` since deployment
+        fn current_demurrage_index(&self) -> u128 {
+            let rate = self.demurrage.map_or(0, |demurrage| demurrage.rate_bps);
+            let elapsed = self
+                .env()
+                .block_timestamp()
+                .saturating_sub(self.demurrage_index_updated_at);
+            self.demurrage_index
+                .saturating_add((rate as u128).saturating_mul(elapsed as u128))
+        }
+
+        /// The sink and the contract's own custody never decay
+        fn demurrage_exempt(&self, account: AccountId) -> bool {
+            account == self.env().account_id()
+                || self
+                    .demurrage
+                    .is_none_or(|demurrage| demurrage.sink == account)
+        }
+
+        /// Moves owed decay from `account` to the sink and restarts its accrual.
+        /// Must run before any write to the account's stored balance.
+        fn settle_demurrage(&mut self, account: AccountId) {
+            let Some(Demurrage { sink, .. }) = self.demurrage else {
+                return;
+            };
+            let owed = self.demurrage_owed(account);
+            self.demurrage_index_of
+                .insert(account, &self.current_demurrage_index());
+            if owed == 0 {
+                return;
+            }
+
+            let new_balance = self.balances.get(account).unwrap_or(0) - owed;
+            let new_sink_balance = self.balance_of(sink).saturating_add(owed);
+            self.write_balance(account, new_balance);
+            self.write_balance(sink, new_sink_balance);
+            if self.frozen_balance_of(account) > new_balance {
+                self.set_frozen(account, new_balance);
+            }
+
+            self.env().emit_event(Transfer {
+                from: Some(account),
+                to: Some(sink),
+                value: owed,
+                data: Vec::new(),
+            });
+        }
+
+        /// Deposits `assets` of the caller's spendable balance into the vault and
+        /// returns the shares minted for them
+        #[ink(message)]
+        pub fn vault_deposit(&mut self, assets: u128) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            let shares = self.preview_deposit(assets)?;
+            if shares == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(account) < assets {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.move_balance(account, self.env().account_id(), assets)?;
+            self.vault_assets = self
+                .vault_assets
+                .checked_add(assets)
+                .ok_or(Error::Overflow)?;
+            self.vault_total_shares = self
+                .vault_total_shares
+                .checked_add(shares)
+                .ok_or(Error::Overflow)?;
+            let new_shares = self.vault_shares_of(account).saturating_add(shares);
+            self.vault_shares.insert(account, &new_shares);
+
+            self.env().emit_event(VaultDeposit {
+                account,
+                assets,
+                shares,
+            });
+
+            Ok(shares)
+        }
+
+        /// Withdraws exactly `assets` from the vault and returns the shares burned
+        #[ink(message)]
+        pub fn vault_withdraw(&mut self, assets: u128) -> Result<u128> {
+            let shares = self.preview_withdraw(assets)?;
+            self.exit_vault(assets, shares)?;
+            Ok(shares)
+        }
+
+        /// Redeems `shares` and returns the assets paid out
+        #[ink(message)]
+        pub fn vault_redeem(&mut self, shares: u128) -> Result<u128> {
+            let assets = self.preview_redeem(shares)?;
+            self.exit_vault(assets, shares)?;
+            Ok(assets)
+        }
+
+        fn exit_vault(&mut self, assets: u128, shares: u128) -> Result<()> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            if assets == 0 || shares == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            let remaining_shares = self
+                .vault_shares_of(account)
+                .checked_sub(shares)
+                .ok_or(Error::InsufficientShares)?;
+            let new_balance = self
+                .balance_of(account)
+                .checked_add(assets)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(account, new_balance)?;
+
+            self.vault_assets = self
+                .vault_assets
+                .checked_sub(assets)
+                .ok_or(Error::InsufficientShares)?;
+            self.vault_total_shares = self.vault_total_shares.saturating_sub(shares);
+            self.vault_shares.insert(account, &remaining_shares);
+            self.move_balance(self.env().account_id(), account, assets)?;
+
+            self.env().emit_event(VaultWithdrawal {
+                account,
+                assets,
+                shares,
+            });
+
+            Ok(())
+        }
+
+        /// Adds `amount` of the caller's balance to the vault as yield. Only the
+        /// owner or the vault strategy may call it.
+        #[ink(message)]
+        pub fn credit_vault_yield(&mut self, amount: u128) -> Result<()> {
+            let from = self.env().caller();
+            if self.vault_strategy != Some(from) {
+                self.only_owner()?;
+            }
+            if amount == 0 || self.vault_total_shares == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(from) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.move_balance(from, self.env().account_id(), amount)?;
+            self.vault_assets = self
+                .vault_assets
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+
+            self.env().emit_event(VaultYieldCredited { from, amount });
+
+            Ok(())
+        }
+
+        /// Sets the account, besides the owner, that may credit vault yield
+        #[ink(message)]
+        pub fn set_vault_strategy(&mut self, strategy: Option<AccountId>) -> Result<()> {
+            self.only_owner()?;
+
+            self.vault_strategy = strategy;
+
+            self.audit(AuditOperation::Config, &strategy);
+            self.env().emit_event(VaultStrategyUpdated { strategy });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn vault_strategy(&self) -> Option<AccountId> {
+            self.vault_strategy
+        }
+
+        /// Tokens held by the vault, including credited yield
+        #[ink(message)]
+        pub fn vault_total_assets(&self) -> u128 {
+            self.vault_assets
+        }
+
+        #[ink(message)]
+        pub fn vault_total_shares(&self) -> u128 {
+            self.vault_total_shares
+        }
+
+        #[ink(message)]
+        pub fn vault_shares_of(&self, account: AccountId) -> u128 {
+            self.vault_shares.get(account).unwrap_or(0)
+        }
+
+        /// Shares `assets` are worth at the current share price, rounded down
+        #[ink(message)]
+        pub fn convert_to_shares(&self, assets: u128) -> Result<u128> {
+            Self::mul_div(
+                assets,
+                self.vault_total_shares.saturating_add(1),
+                self.vault_assets.saturating_add(1),
+                false,
+            )
+        }
+
+        /// Assets `shares` are worth at the current share price, rounded down
+        #[ink(message)]
+        pub fn convert_to_assets(&self, shares: u128) -> Result<u128> {
+            Self::mul_div(
+                shares,
+                self.vault_assets.saturating_add(1),
+                self.vault_total_shares.saturating_add(1),
+                false,
+            )
+        }
+
+        /// Shares `vault_deposit(assets)` would mint
+        #[ink(message)]
+        pub fn preview_deposit(&self, assets: u128) -> Result<u128> {
+            self.convert_to_shares(assets)
+        }
+
+        /// Shares `vault_withdraw(assets)` would burn, rounded up
+        #[ink(message)]
+        pub fn preview_withdraw(&self, assets: u128) -> Result<u128> {
+            Self::mul_div(
+                assets,
+                self.vault_total_shares.saturating_add(1),
+                self.vault_assets.saturating_add(1),
+                true,
+            )
+        }
+
+        /// Assets `vault_redeem(shares)` would pay out
+        #[ink(message)]
+        pub fn preview_redeem(&self, shares: u128) -> Result<u128> {
+            self.convert_to_assets(shares)
+        }
+
+        /// `a * b / d`. The vault counts one virtual share and asset so the first
+        /// depositor can't inflate the share price.
+        fn mul_div(a: u128, b: u128, d: u128, round_up: bool) -> Result<u128> {
+            let product = a.checked_mul(b).ok_or(Error::Overflow)?;
+            let quotient = product / d;
+            if round_up && product % d != 0 {
+                return quotient.checked_add(1).ok_or(Error::Overflow);
+            }
+            Ok(quotient)
+        }
+
+        /// Moves `total` of the owner's balance into custody, vesting to
+        /// `beneficiary`, and returns the schedule ID
+        #[ink(message)]
+        pub fn create_vesting_schedule(
+            &mut self,
+            beneficiary: AccountId,
+            total: u128,
+            start: Timestamp,
+            cliff: Timestamp,
+            duration: Timestamp,
+        ) -> Result<u64> {
+            self.only_owner()?;
+            self.not_blacklisted(beneficiary)?;
+            if total == 0 || duration == 0 || cliff > duration {
+                return Err(Error::InvalidAmount);
+            }
+            start.checked_add(duration).ok_or(Error::Overflow)?;
+            if self.spendable_balance_of(self.owner) < total {
+                return Err(Error::InsufficientBalance);
+            }
+            let mut ids = self
+                .vesting_schedules_of
+                .get(beneficiary)
+                .unwrap_or_default();
+            if ids.len() >= MAX_VESTING_SCHEDULES {
+                return Err(Error::TooManyVestingSchedules);
+            }
+
+            let id = self.next_vesting_id;
+            self.next_vesting_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.move_balance(self.owner, self.env().account_id(), total)?;
+            self.vesting_schedules.insert(
+                id,
+                &VestingSchedule {
+                    beneficiary,
+                    total,
+                    released: 0,
+                    start,
+                    cliff,
+                    duration,
+                },
+            );
+            ids.push(id);
+            self.vesting_schedules_of.insert(beneficiary, &ids);
+
+            self.env().emit_event(VestingScheduleCreated {
+                id,
+                beneficiary,
+                total,
+                start,
+                cliff,
+                duration,
+            });
+
+            Ok(id)
+        }
+
+        /// Releases everything vested so far across the caller's schedules and
+        /// returns the amount. Fully released schedules are removed.
+        #[ink(message)]
+        pub fn claim_vested(&mut self) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let beneficiary = self.env().caller();
+            self.not_blacklisted(beneficiary)?;
+
+            let ids = self
+                .vesting_schedules_of
+                .get(beneficiary)
+                .unwrap_or_default();
+            let mut amount: u128 = 0;
+            let mut remaining = Vec::new();
+            let mut updated = Vec::new();
+            for id in ids {
+                let Some(mut schedule) = self.vesting_schedules.get(id) else {
+                    continue;
+                };
+                let releasable = self.vested(&schedule).saturating_sub(schedule.released);
+                amount = amount.checked_add(releasable).ok_or(Error::Overflow)?;
+                schedule.released = schedule.released.saturating_add(releasable);
+                if schedule.released < schedule.total {
+                    remaining.push(id);
+                }
+                updated.push((id, schedule));
+            }
+            if amount == 0 {
+                return Err(Error::NothingToClaim);
+            }
+            let new_balance = self
+                .balance_of(beneficiary)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(beneficiary, new_balance)?;
+
+            for (id, schedule) in updated {
+                if schedule.released < schedule.total {
+                    self.vesting_schedules.insert(id, &schedule);
+                } else {
+                    self.vesting_schedules.remove(id);
+                }
+            }
+            self.vesting_schedules_of.insert(beneficiary, &remaining);
+            self.move_balance(self.env().account_id(), beneficiary, amount)?;
+
+            self.env().emit_event(VestedClaimed {
+                beneficiary,
+                amount,
+            });
+
+            Ok(amount)
+        }
+
+        #[ink(message)]
+        pub fn vesting_schedule(&self, id: u64) -> Option<VestingSchedule> {
+            self.vesting_schedules.get(id)
+        }
+
+        /// IDs of `account`'s schedules that still hold unreleased tokens
+        #[ink(message)]
+        pub fn vesting_schedules_of(&self, account: AccountId) -> Vec<u64> {
+            self.vesting_schedules_of.get(account).unwrap_or_default()
+        }
+
+        /// Amount of schedule `id` vested so far, released or not
+        #[ink(message)]
+        pub fn vested_amount(&self, id: u64) -> u128 {
+            self.vesting_schedules
+                .get(id)
+                .map_or(0, |schedule| self.vested(&schedule))
+        }
+
+        /// Amount of schedule `id` vested but not yet claimed
+        #[ink(message)]
+        pub fn releasable_amount(&self, id: u64) -> u128 {
+            self.vesting_schedules.get(id).map_or(0, |schedule| {
+                self.vested(&schedule).saturating_sub(schedule.released)
+            })
+        }
+
+        fn vested(&self, schedule: &VestingSchedule) -> u128 {
+            let elapsed = self.env().block_timestamp().saturating_sub(schedule.start);
+            if elapsed < schedule.cliff {
+                return 0;
+            }
+            if elapsed >= schedule.duration {
+                return schedule.total;
+            }
+            let (elapsed, duration) = (elapsed as u128, schedule.duration as u128);
+            (schedule.total / duration)
+                .saturating_mul(elapsed)
+                .saturating_add(schedule.total % duration * elapsed / duration)
+        }
+
+        /// Escrows `amount` for `to`, who can claim it with `claim_locked_transfer`
+        /// from `unlock_at`. Returns the transfer ID.
+        #[ink(message)]
+        pub fn transfer_locked(
+            &mut self,
+            to: AccountId,
+            amount: u128,
+            unlock_at: Timestamp,
+        ) -> Result<u64> {
+            self.when_not_paused()?;
+            self.when_scope_active(PauseScope::Transfers)?;
+
+            let from = self.env().caller();
+            self.not_blacklisted(from)?;
+            self.not_blacklisted(to)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.is_account_locked(from) {
+                return Err(Error::AccountLocked);
+            }
+            if self.spendable_balance_of(from) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+            let mut ids = self.locked_transfers_sent.get(from).unwrap_or_default();
+            if ids.len() >= MAX_LOCKED_TRANSFERS {
+                return Err(Error::TooManyLockedTransfers);
+            }
+
+            let id = self.next_locked_transfer_id;
+            self.next_locked_transfer_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.move_balance(from, self.env().account_id(), amount)?;
+            self.locked_transfers.insert(
+                id,
+                &LockedTransfer {
+                    from,
+                    to,
+                    amount,
+                    unlock_at,
+                },
+            );
+            ids.push(id);
+            self.locked_transfers_sent.insert(from, &ids);
+
+            self.env().emit_event(LockedTransferCreated {
+                id,
+                from,
+                to,
+                amount,
+                unlock_at,
+            });
+
+            Ok(id)
+        }
+
+        /// Pays out locked transfer `id` to the caller once it has unlocked
+        #[ink(message)]
+        pub fn claim_locked_transfer(&mut self, id: u64) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let to = self.env().caller();
+            self.not_blacklisted(to)?;
+            let locked = self
+                .locked_transfers
+                .get(id)
+                .filter(|locked| locked.to == to)
+                .ok_or(Error::LockedTransferNotFound)?;
+            if self.env().block_timestamp() < locked.unlock_at {
+                return Err(Error::TransferStillLocked);
+            }
+            let new_balance = self
+                .balance_of(to)
+                .checked_add(locked.amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(to, new_balance)?;
+
+            self.locked_transfers.remove(id);
+            let mut ids = self
+                .locked_transfers_sent
+                .get(locked.from)
+                .unwrap_or_default();
+            ids.retain(|pending| *pending != id);
+            self.locked_transfers_sent.insert(locked.from, &ids);
+            self.move_balance(self.env().account_id(), to, locked.amount)?;
+
+            self.env().emit_event(LockedTransferClaimed {
+                id,
+                to,
+                amount: locked.amount,
+            });
+
+            Ok(locked.amount)
+        }
+
+        #[ink(message)]
+        pub fn locked_transfer(&self, id: u64) -> Option<LockedTransfer> {
+            self.locked_transfers.get(id)
+        }
+
+        /// IDs of `sender`'s locked transfers that haven't been claimed yet
+        #[ink(message)]
+        pub fn pending_locked_transfers(&self, sender: AccountId) -> Vec<u64> {
+            self.locked_transfers_sent.get(sender).unwrap_or_default()
+        }
+
+        /// Escrows `deposit` and streams it to `to` between `start` and `end`.
+        /// Returns the stream ID.
+        #[ink(message)]
+        pub fn create_stream(
+            &mut self,
+            to: AccountId,
+            deposit: u128,
+            start: Timestamp,
+            end: Timestamp,
+        ) -> Result<u64> {
+            self.when_not_paused()?;
+            self.when_scope_active(PauseScope::Transfers)?;
+
+            let sender = self.env().caller();
+            self.not_blacklisted(sender)?;
+            self.not_blacklisted(to)?;
+            if deposit == 0 || sender == to || end.saturating_sub(start) < 1000 {
+                return Err(Error::InvalidAmount);
+            }
+            if end <= self.env().block_timestamp() {
+                return Err(Error::InvalidAmount);
+            }
+            if self.is_account_locked(sender) {
+                return Err(Error::AccountLocked);
+            }
+            if self.spendable_balance_of(sender) < deposit {
+                return Err(Error::InsufficientBalance);
+            }
+
+            let id = self.next_stream_id;
+            self.next_stream_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.move_balance(sender, self.env().account_id(), deposit)?;
+            self.streams.insert(
+                id,
+                &Stream {
+                    sender,
+                    recipient: to,
+                    deposit,
+                    withdrawn: 0,
+                    start,
+                    end,
+                },
+            );
+
+            self.env().emit_event(StreamCreated {
+                id,
+                sender,
+                recipient: to,
+                deposit,
+                start,
+                end,
+            });
+
+            Ok(id)
+        }
+
+        /// Pays the recipient of stream `id` everything streamed so far and
+        /// returns the amount
+        #[ink(message)]
+        pub fn withdraw_from_stream(&mut self, id: u64) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let caller = self.env().caller();
+            self.not_blacklisted(caller)?;
+            let mut stream = self
+                .streams
+                .get(id)
+                .filter(|stream| stream.recipient == caller)
+                .ok_or(Error::StreamNotFound)?;
+            let amount = self.withdrawable_from_stream(id);
+            if amount == 0 {
+                return Err(Error::NothingToClaim);
+            }
+            let new_balance = self
+                .balance_of(caller)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(caller, new_balance)?;
+
+            stream.withdrawn = stream.withdrawn.saturating_add(amount);
+            if stream.withdrawn < stream.deposit {
+                self.streams.insert(id, &stream);
+            } else {
+                self.streams.remove(id);
+            }
+            self.move_balance(self.env().account_id(), caller, amount)?;
+
+            self.env().emit_event(StreamWithdrawal { id, amount });
+
+            Ok(amount)
+        }
+
+        /// Stops stream `id`. The sender or the recipient may cancel; the
+        /// recipient is paid what has streamed and the sender gets the rest back.
+        #[ink(message)]
+        pub fn cancel_stream(&mut self, id: u64) -> Result<()> {
+            self.when_not_paused()?;
+
+            let caller = self.env().caller();
+            let stream = self
+                .streams
+                .get(id)
+                .filter(|stream| caller == stream.sender || caller == stream.recipient)
+                .ok_or(Error::StreamNotFound)?;
+            let recipient_amount = self.withdrawable_from_stream(id);
+            let sender_amount = stream
+                .deposit
+                .saturating_sub(stream.withdrawn)
+                .saturating_sub(recipient_amount);
+
+            self.streams.remove(id);
+            let custody = self.env().account_id();
+            if recipient_amount > 0 {
+                self.move_balance(custody, stream.recipient, recipient_amount)?;
+            }
+            if sender_amount > 0 {
+                self.move_balance(custody, stream.sender, sender_amount)?;
+            }
+
+            self.env().emit_event(StreamCancelled {
+                id,
+                recipient_amount,
+                sender_amount,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn stream(&self, id: u64) -> Option<Stream> {
+            self.streams.get(id)
+        }
+
+        /// Amount of stream `id` streamed but not yet withdrawn
+        #[ink(message)]
+        pub fn withdrawable_from_stream(&self, id: u64) -> u128 {
+            let Some(stream) = self.streams.get(id) else {
+                return 0;
+            };
+            let now = self.env().block_timestamp();
+            let streamed = if now >= stream.end {
+                stream.deposit
+            } else {
+                // Accrues per whole second
+                let elapsed = (now.saturating_sub(stream.start) / 1000) as u128;
+                let duration = ((stream.end - stream.start) / 1000) as u128;
+                (stream.deposit / duration)
+                    .saturating_mul(elapsed)
+                    .saturating_add(stream.deposit % duration * elapsed / duration)
+            };
+            streamed.saturating_sub(stream.withdrawn)
+        }
+
+        /// Sets up a transfer of `amount` to `to` every `interval` ms, first due at
+        /// `first_due` and last due no later than `end`. Returns the order ID.
+        #[ink(message)]
+        pub fn create_standing_order(
+            &mut self,
+            to: AccountId,
+            amount: u128,
+            first_due: Timestamp,
+            interval: Timestamp,
+            end: Timestamp,
+        ) -> Result<u64> {
+            let payer = self.env().caller();
+            self.not_blacklisted(payer)?;
+            self.not_blacklisted(to)?;
+            if amount == 0 || interval == 0 || payer == to || first_due > end {
+                return Err(Error::InvalidAmount);
+            }
+            let mut ids = self.standing_orders_of.get(payer).unwrap_or_default();
+            if ids.len() >= MAX_STANDING_ORDERS {
+                return Err(Error::TooManyStandingOrders);
+            }
+
+            let id = self.next_standing_order_id;
+            self.next_standing_order_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.standing_orders.insert(
+                id,
+                &StandingOrder {
+                    payer,
+                    to,
+                    amount,
+                    interval,
+                    next_due: first_due,
+                    end,
+                },
+            );
+            ids.push(id);
+            self.standing_orders_of.insert(payer, &ids);
+
+            self.env().emit_event(StandingOrderCreated {
+                id,
+                payer,
+                to,
+                amount,
+                first_due,
+                interval,
+                end,
+            });
+
+            Ok(id)
+        }
+
+        #[ink(message)]
+        pub fn cancel_standing_order(&mut self, id: u64) -> Result<()> {
+            let payer = self.env().caller();
+            self.standing_orders
+                .get(id)
+                .filter(|order| order.payer == payer)
+                .ok_or(Error::StandingOrderNotFound)?;
+
+            self.remove_standing_order(id, payer);
+
+            self.env().emit_event(StandingOrderCancelled { id });
+
+            Ok(())
+        }
+
+        /// Makes one due payment for each of `ids`. Anyone may call it, e.g. a
+        /// keeper bot. A payment that fails is reported with `StandingOrderFailed`
+        /// and skipped until the next interval; orders past their end are removed.
+        /// Returns each order's outcome, `Err(OrderNotDue)` if it wasn't due yet.
+        #[ink(message)]
+        pub fn process_due_orders(&mut self, ids: Vec<u64>) -> Vec<Result<()>> {
+            ids.into_iter()
+                .map(|id| self.process_due_order(id))
+                .collect()
+        }
+
+        fn process_due_order(&mut self, id: u64) -> Result<()> {
+            let mut order = self
+                .standing_orders
+                .get(id)
+                .ok_or(Error::StandingOrderNotFound)?;
+            let due = order.next_due;
+            if self.env().block_timestamp() < due {
+                return Err(Error::OrderNotDue);
+            }
+
+            let result = self
+                .when_not_paused()
+                .and_then(|_| self.not_blacklisted(order.payer))
+                .and_then(|_| self.not_blacklisted(order.to))
+                .and_then(|_| self._transfer(order.payer, order.to, order.amount, Vec::new()));
+
+            order.next_due = due.saturating_add(order.interval);
+            if order.next_due > order.end {
+                self.remove_standing_order(id, order.payer);
+            } else {
+                self.standing_orders.insert(id, &order);
+            }
+
+            match result {
+                Ok(()) => self.env().emit_event(StandingOrderExecuted {
+                    id,
+                    amount: order.amount,
+                    due,
+                }),
+                Err(ref error) => self.env().emit_event(StandingOrderFailed {
+                    id,
+                    due,
+                    error: error.clone(),
+                }),
+            }
+
+            result
+        }
+
+        fn remove_standing_order(&mut self, id: u64, payer: AccountId) {
+            self.standing_orders.remove(id);
+            let mut ids = self.standing_orders_of.get(payer).unwrap_or_default();
+            ids.retain(|order| *order != id);
+            self.standing_orders_of.insert(payer, &ids);
+        }
+
+        #[ink(message)]
+        pub fn standing_order(&self, id: u64) -> Option<StandingOrder> {
+            self.standing_orders.get(id)
+        }
+
+        /// IDs of `payer`'s active standing orders
+        #[ink(message)]
+        pub fn standing_orders_of(&self, payer: AccountId) -> Vec<u64> {
+            self.standing_orders_of.get(payer).unwrap_or_default()
+        }
+
+        /// Lets `merchant` pull `amount` from the caller once per `period` ms,
+        /// starting now. Returns the subscription ID.
+        #[ink(message)]
+        pub fn create_subscription(
+            &mut self,
+            merchant: AccountId,
+            amount: u128,
+            period: Timestamp,
+        ) -> Result<u64> {
+            let subscriber = self.env().caller();
+            self.not_blacklisted(subscriber)?;
+            self.not_blacklisted(merchant)?;
+            if amount == 0 || period == 0 || subscriber == merchant {
+                return Err(Error::InvalidAmount);
+            }
+
+            let id = self.next_subscription_id;
+            self.next_subscription_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.subscriptions.insert(
+                id,
+                &Subscription {
+                    subscriber,
+                    merchant,
+                    amount,
+                    period,
+                    next_charge_at: self.env().block_timestamp(),
+                },
+            );
+
+            self.env().emit_event(SubscriptionCreated {
+                id,
+                subscriber,
+                merchant,
+                amount,
+                period,
+            });
+
+            Ok(id)
+        }
+
+        /// Pulls the subscription amount from the subscriber. Only the merchant
+        /// may call it, and at most once per period.
+        #[ink(message)]
+        pub fn charge_subscription(&mut self, id: u64) -> Result<()> {
+            self.when_not_paused()?;
+
+            let merchant = self.env().caller();
+            let mut subscription = self
+                .subscriptions
+                .get(id)
+                .filter(|subscription| subscription.merchant == merchant)
+                .ok_or(Error::SubscriptionNotFound)?;
+            let now = self.env().block_timestamp();
+            if now < subscription.next_charge_at {
+                return Err(Error::ChargeTooEarly);
+            }
+            self.not_blacklisted(subscription.subscriber)?;
+            self.not_blacklisted(merchant)?;
+
+            self._transfer(
+                subscription.subscriber,
+                merchant,
+                subscription.amount,
+                Vec::new(),
+            )?;
+            subscription.next_charge_at = now.saturating_add(subscription.period);
+            self.subscriptions.insert(id, &subscription);
+
+            self.env().emit_event(SubscriptionCharged {
+                id,
+                amount: subscription.amount,
+                next_charge_at: subscription.next_charge_at,
+            });
+
+            Ok(())
+        }
+
+        /// Ends a subscription. The subscriber or the merchant may cancel.
+        #[ink(message)]
+        pub fn cancel_subscription(&mut self, id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            self.subscriptions
+                .get(id)
+                .filter(|subscription| {
+                    caller == subscription.subscriber || caller == subscription.merchant
+                })
+                .ok_or(Error::SubscriptionNotFound)?;
+
+            self.subscriptions.remove(id);
+
+            self.env().emit_event(SubscriptionCancelled { id });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn subscription(&self, id: u64) -> Option<Subscription> {
+            self.subscriptions.get(id)
+        }
+
+        /// Authorizes `payee` to collect direct debits of up to `limit` from the
+        /// caller. Returns the mandate ID.
+        #[ink(message)]
+        pub fn create_mandate(&mut self, payee: AccountId, limit: u128) -> Result<u64> {
+            let payer = self.env().caller();
+            self.not_blacklisted(payer)?;
+            self.not_blacklisted(payee)?;
+            if limit == 0 || payer == payee {
+                return Err(Error::InvalidAmount);
+            }
+
+            let id = self.next_mandate_id;
+            self.next_mandate_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.mandates.insert(
+                id,
+                &Mandate {
+                    payer,
+                    payee,
+                    limit,
+                    dishonoured: 0,
+                },
+            );
+
+            self.env().emit_event(DirectDebitMandateCreated {
+                id,
+                payer,
+                payee,
+                limit,
+            });
+
+            Ok(id)
+        }
+
+        /// Cancels a mandate. Only its payer may revoke it.
+        #[ink(message)]
+        pub fn revoke_mandate(&mut self, id: u64) -> Result<()> {
+            let payer = self.env().caller();
+            self.mandates
+                .get(id)
+                .filter(|mandate| mandate.payer == payer)
+                .ok_or(Error::MandateNotFound)?;
+
+            self.mandates.remove(id);
+
+            self.env().emit_event(DirectDebitMandateRevoked { id });
+
+            Ok(())
+        }
+
+        /// Collects `amount` under mandate `id`; only its payee may present it.
+        /// Returns `Ok(false)` and emits `DirectDebitDishonoured` when the payer's
+        /// spendable balance doesn't cover the debit.
+        #[ink(message)]
+        pub fn collect_direct_debit(&mut self, id: u64, amount: u128) -> Result<bool> {
+            self.when_not_paused()?;
+
+            let payee = self.env().caller();
+            let mut mandate = self
+                .mandates
+                .get(id)
+                .filter(|mandate| mandate.payee == payee)
+                .ok_or(Error::MandateNotFound)?;
+            if amount > mandate.limit {
+                return Err(Error::MandateLimitExceeded);
+            }
+            self.not_blacklisted(mandate.payer)?;
+            self.not_blacklisted(payee)?;
+
+            if self.spendable_balance_of(mandate.payer) < amount {
+                mandate.dishonoured = mandate.dishonoured.saturating_add(1);
+                self.mandates.insert(id, &mandate);
+                self.env().emit_event(DirectDebitDishonoured { id, amount });
+                return Ok(false);
+            }
+
+            self._transfer(mandate.payer, payee, amount, Vec::new())?;
+
+            self.env().emit_event(DirectDebitCollected { id, amount });
+
+            Ok(true)
+        }
+
+        #[ink(message)]
+        pub fn mandate(&self, id: u64) -> Option<Mandate> {
+            self.mandates.get(id)
+        }
+
+        /// Starts a draft payroll run for the caller and returns its ID
+        #[ink(message)]
+        pub fn create_payroll(&mut self) -> Result<u64> {
+            let employer = self.env().caller();
+            self.not_blacklisted(employer)?;
+
+            let id = self.next_payroll_id;
+            self.next_payroll_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.payrolls.insert(
+                id,
+                &Payroll {
+                    employer,
+                    status: PayrollStatus::Draft,
+                    payees: 0,
+                    total: 0,
+                    next_index: 0,
+                    paid: 0,
+                },
+            );
+
+            self.env().emit_event(PayrollCreated { id, employer });
+
+            Ok(id)
+        }
+
+        /// Appends up to `MAX_PAYROLL_CHUNK` payees to a draft run
+        #[ink(message)]
+        pub fn add_payroll_entries(&mut self, id: u64, entries: Vec<PayrollEntry>) -> Result<()> {
+            let mut payroll = self.employer_payroll(id, PayrollStatus::Draft)?;
+            if entries.is_empty() || entries.len() > MAX_PAYROLL_CHUNK as usize {
+                return Err(Error::InvalidAmount);
+            }
+
+            let mut total = payroll.total;
+            for (_, amount) in &entries {
+                if *amount == 0 {
+                    return Err(Error::InvalidAmount);
+                }
+                total = total.checked_add(*amount).ok_or(Error::Overflow)?;
+            }
+            let payees = payroll
+                .payees
+                .checked_add(entries.len() as u32)
+                .ok_or(Error::Overflow)?;
+
+            for (entry, index) in entries.iter().zip(payroll.payees..) {
+                self.payroll_entries.insert((id, index), entry);
+            }
+            payroll.payees = payees;
+            payroll.total = total;
+            self.payrolls.insert(id, &payroll);
+
+            Ok(())
+        }
+
+        /// Moves the run's total from the employer into custody. No more entries
+        /// can be added afterwards.
+        #[ink(message)]
+        pub fn fund_payroll(&mut self, id: u64) -> Result<()> {
+            self.when_not_paused()?;
+
+            let mut payroll = self.employer_payroll(id, PayrollStatus::Draft)?;
+            if payroll.payees == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(payroll.employer) < payroll.total {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.move_balance(payroll.employer, self.env().account_id(), payroll.total)?;
+            payroll.status = PayrollStatus::Funded;
+            self.payrolls.insert(id, &payroll);
+
+            self.env().emit_event(PayrollFunded {
+                id,
+                total: payroll.total,
+                payees: payroll.payees,
+            });
+
+            Ok(())
+        }
+
+        /// Pays the next `max_payments` entries (at most `MAX_PAYROLL_CHUNK`) of a
+        /// funded run and returns how many were processed. A payee that can't be
+        /// paid, e.g. because it is blacklisted, is skipped and its amount refunded
+        /// to the employer once the run completes.
+        #[ink(message)]
+        pub fn run_payroll(&mut self, id: u64, max_payments: u32) -> Result<u32> {
+            self.when_not_paused()?;
+
+            let mut payroll = self.employer_payroll(id, PayrollStatus::Funded)?;
+            let custody = self.env().account_id();
+            let end = payroll
+                .next_index
+                .saturating_add(max_payments.min(MAX_PAYROLL_CHUNK))
+                .min(payroll.payees);
+
+            for index in payroll.next_index..end {
+                let Some((payee, amount)) = self.payroll_entries.get((id, index)) else {
+                    continue;
+                };
+                let result = self.not_blacklisted(payee).and_then(|_| {
+                    let new_balance = self
+                        .balance_of(payee)
+                        .checked_add(amount)
+                        .ok_or(Error::Overflow)?;
+                    self.within_balance_limit(payee, new_balance)?;
+                    self.move_balance(custody, payee, amount)
+                });
+                match result {
+                    Ok(()) => {
+                        payroll.paid = payroll.paid.saturating_add(amount);
+                        self.record_payroll_income(payee, amount)?;
+                    }
+                    Err(error) => self.env().emit_event(PayrollPaymentFailed {
+                        id,
+                        index,
+                        payee,
+                        error,
+                    }),
+                }
+            }
+            let processed = end - payroll.next_index;
+            payroll.next_index = end;
+
+            if payroll.next_index == payroll.payees {
+                let refunded = payroll.total - payroll.paid;
+                if refunded > 0 {
+                    self.move_balance(custody, payroll.employer, refunded)?;
+                }
+                payroll.status = PayrollStatus::Completed;
+                self.env().emit_event(PayrollCompleted {
+                    id,
+                    paid: payroll.paid,
+                    refunded,
+                });
+            }
+            self.payrolls.insert(id, &payroll);
+
+            Ok(processed)
+        }
+
+        /// Status and progress of payroll run `id`
+        #[ink(message)]
+        pub fn payroll(&self, id: u64) -> Option<Payroll> {
+            self.payrolls.get(id)
+        }
+
+        /// Payee and amount of entry `index` of payroll run `id`
+        #[ink(message)]
+        pub fn payroll_entry(&self, id: u64, index: u32) -> Option<PayrollEntry> {
+            self.payroll_entries.get((id, index))
+        }
+
+        /// The caller's payroll run `id`, provided it is in `status`
+        fn employer_payroll(&self, id: u64, status: PayrollStatus) -> Result<Payroll> {
+            let payroll = self
+                .payrolls
+                .get(id)
+                .filter(|payroll| payroll.employer == self.env().caller())
+                .ok_or(Error::PayrollNotFound)?;
+            if payroll.status != status {
+                return Err(Error::InvalidPayrollStatus);
+            }
+            Ok(payroll)
+        }
+
+        /// Records a payroll payment of `amount` to `payee` and recovers any salary
+        /// advance it owes from it. The principal is burned and the fee goes to
+        /// the fee collector, or is burned too when there is none
+        fn record_payroll_income(&mut self, payee: AccountId, amount: u128) -> Result<()> {
+            let mut income = self.payroll_income.get(payee).unwrap_or_default();
+            income.payments = income.payments.saturating_add(1);
+            income.last_amount = amount;
+            income.last_paid_at = self.env().block_timestamp();
+            self.payroll_income.insert(payee, &income);
+
+            let Some(mut advance) = self.salary_advances.get(payee) else {
+                return Ok(());
+            };
+            let principal = advance.principal.min(amount);
+            let fee = advance.fee.min(amount - principal);
+            let collector = self.fee_collector.filter(|_| fee > 0);
+            if let Some(collector) = collector {
+                self.move_balance(payee, collector, fee)?;
+            }
+            let burned = if collector.is_some() {
+                principal
+            } else {
+                principal + fee
+            };
+            if burned > 0 {
+                let new_balance = self
+                    .balance_of(payee)
+                    .checked_sub(burned)
+                    .ok_or(Error::InsufficientBalance)?;
+                self.write_balance(payee, new_balance);
+                self.total_supply = self.total_supply.saturating_sub(burned);
+                self.env().emit_event(Transfer {
+                    from: Some(payee),
+                    to: None,
+                    value: burned,
+                    data: Vec::new(),
+                });
+            }
+
+            advance.principal -= principal;
+            advance.fee -= fee;
+            let outstanding = advance.principal + advance.fee;
+            if outstanding == 0 {
+                self.salary_advances.remove(payee);
+            } else {
+                self.salary_advances.insert(payee, &advance);
+            }
+
+            self.env().emit_event(SalaryAdvanceRepaid {
+                account: payee,
+                amount: principal + fee,
+                outstanding,
+            });
+
+            Ok(())
+        }
+
+        /// Sets how much of its last payroll payment an account can draw as an
+        /// advance, and the fee charged on it, both in basis points. A zero
+        /// `advance_bps` turns salary advances off
+        #[ink(message)]
+        pub fn set_salary_advance_terms(&mut self, advance_bps: u16, fee_bps: u16) -> Result<()> {
+            self.only_owner()?;
+            if advance_bps > MAX_SALARY_ADVANCE_BPS || fee_bps > MAX_SALARY_ADVANCE_FEE_BPS {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.salary_advance_bps = advance_bps;
+            self.salary_advance_fee_bps = fee_bps;
+
+            self.audit(AuditOperation::Config, &(advance_bps, fee_bps));
+            self.env().emit_event(SalaryAdvanceTermsUpdated {
+                advance_bps,
+                fee_bps,
+            });
+
+            Ok(())
+        }
+
+        /// `(advance_bps, fee_bps)`
+        #[ink(message)]
+        pub fn salary_advance_terms(&self) -> (u16, u16) {
+            (self.salary_advance_bps, self.salary_advance_fee_bps)
+        }
+
+        /// Payroll payments received by `account`
+        #[ink(message)]
+        pub fn payroll_income(&self, account: AccountId) -> Option<PayrollIncome> {
+            self.payroll_income.get(account)
+        }
+
+        /// Salary advance `account` still owes, if any
+        #[ink(message)]
+        pub fn salary_advance_of(&self, account: AccountId) -> Option<SalaryAdvance> {
+            self.salary_advances.get(account)
+        }
+
+        /// How much more `account` can draw as a salary advance right now
+        #[ink(message)]
+        pub fn available_salary_advance(&self, account: AccountId) -> u128 {
+            self.regular_payroll_income(account)
+                .map_or(0, |income| self.salary_advance_limit(account, &income))
+        }
+
+        /// Mints `amount` to the caller against its next payroll payment, which
+        /// repays it along with the advance fee. Returns the fee
+        #[ink(message)]
+        pub fn request_salary_advance(&mut self, amount: u128) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            let income = self
+                .regular_payroll_income(account)
+                .ok_or(Error::NoPayrollIncome)?;
+            if amount > self.salary_advance_limit(account, &income) {
+                return Err(Error::SalaryAdvanceLimitExceeded);
+            }
+
+            let fee = Self::bps_of(amount, self.salary_advance_fee_bps);
+            let mut advance = self.salary_advances.get(account).unwrap_or_default();
+            advance.principal = advance
+                .principal
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            advance.fee = advance.fee.checked_add(fee).ok_or(Error::Overflow)?;
+            self._mint(account, amount)?;
+            self.salary_advances.insert(account, &advance);
+
+            self.env().emit_event(SalaryAdvanceDrawn {
+                account,
+                amount,
+                fee,
+            });
+
+            Ok(fee)
+        }
+
+        /// `account`'s payroll income, provided it has been paid regularly and
+        /// recently enough to take a salary advance
+        fn regular_payroll_income(&self, account: AccountId) -> Option<PayrollIncome> {
+            self.payroll_income.get(account).filter(|income| {
+                income.payments >= MIN_PAYROLL_PAYMENTS
+                    && self.env().block_timestamp()
+                        <= income.last_paid_at.saturating_add(MAX_PAYROLL_GAP)
+            })
+        }
+
+        /// Share of the last payroll payment that can be advanced, less what
+        /// `account` already owes
+        fn salary_advance_limit(&self, account: AccountId, income: &PayrollIncome) -> u128 {
+            let owed = self
+                .salary_advances
+                .get(account)
+                .map_or(0, |advance| advance.principal);
+            Self::bps_of(income.last_amount, self.salary_advance_bps).saturating_sub(owed)
+        }
+
+        /// Locks `amount` of the caller's balance for `payee` and returns the
+        /// escrow ID
+        #[ink(message)]
+        pub fn create_escrow(
+            &mut self,
+            payee: AccountId,
+            arbiter: AccountId,
+            amount: u128,
+            deadline: Timestamp,
+        ) -> Result<u64> {
+            self.create_milestone_escrow(payee, arbiter, ink::prelude::vec![amount], deadline)
+        }
+
+        /// Locks the sum of `milestones` for `payee`. Each milestone can be
+        /// approved and paid on its own with `approve_milestone`.
+        #[ink(message)]
+        pub fn create_milestone_escrow(
+            &mut self,
+            payee: AccountId,
+            arbiter: AccountId,
+            milestones: Vec<u128>,
+            deadline: Timestamp,
+        ) -> Result<u64> {
+            self.when_not_paused()?;
+
+            let payer = self.env().caller();
+            self.not_blacklisted(payer)?;
+            self.not_blacklisted(payee)?;
+            if milestones.is_empty() || milestones.len() > MAX_MILESTONES {
+                return Err(Error::InvalidMilestone);
+            }
+            let mut amount: u128 = 0;
+            for milestone in &milestones {
+                if *milestone == 0 {
+                    return Err(Error::InvalidAmount);
+                }
+                amount = amount.checked_add(*milestone).ok_or(Error::Overflow)?;
+            }
+            if payer == payee || arbiter == payer || arbiter == payee {
+                return Err(Error::InvalidAmount);
+            }
+            if deadline <= self.env().block_timestamp() {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(payer) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            let id = self.next_escrow_id;
+            self.next_escrow_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.move_balance(payer, self.env().account_id(), amount)?;
+            self.escrows.insert(
+                id,
+                &Escrow {
+                    payer,
+                    payee,
+                    arbiter,
+                    amount,
+                    deadline,
+                    milestones: milestones
+                        .into_iter()
+                        .map(|amount| Milestone {
+                            amount,
+                            released: false,
+                        })
+                        .collect(),
+                },
+            );
+
+            self.env().emit_event(EscrowCreated {
+                id,
+                payer,
+                payee,
+                arbiter,
+                amount,
+                deadline,
+            });
+
+            Ok(id)
+        }
+
+        /// Pays milestone `index` to the payee. The payer or the arbiter may
+        /// approve it.
+        #[ink(message)]
+        pub fn approve_milestone(&mut self, id: u64, index: u32) -> Result<()> {
+            let mut escrow = self.escrow_for(id, |escrow, caller| {
+                caller == escrow.payer || caller == escrow.arbiter
+            })?;
+            let milestone = escrow
+                .milestones
+                .get_mut(index as usize)
+                .filter(|milestone| !milestone.released)
+                .ok_or(Error::InvalidMilestone)?;
+            let amount = milestone.amount;
+            milestone.released = true;
+
+            let payee = escrow.payee;
+            self.not_blacklisted(payee)?;
+            let new_balance = self
+                .balance_of(payee)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(payee, new_balance)?;
+
+            escrow.amount = escrow.amount.saturating_sub(amount);
+            if escrow.amount == 0 {
+                self.escrows.remove(id);
+            } else {
+                self.escrows.insert(id, &escrow);
+            }
+            self.move_balance(self.env().account_id(), payee, amount)?;
+
+            self.env()
+                .emit_event(MilestoneReleased { id, index, amount });
+
+            Ok(())
+        }
+
+        /// Refunds the unreleased milestones to the payer. The payee or the
+        /// arbiter may cancel.
+        #[ink(message)]
+        pub fn cancel_escrow(&mut self, id: u64) -> Result<()> {
+            let escrow = self.escrow_for(id, |escrow, caller| {
+                caller == escrow.payee || caller == escrow.arbiter
+            })?;
+            self.settle_escrow(id, escrow, false)
+        }
+
+        /// Payer confirmation: pays the escrowed funds to the payee
+        #[ink(message)]
+        pub fn release_escrow(&mut self, id: u64) -> Result<()> {
+            let escrow = self.escrow_for(id, |escrow, caller| escrow.payer == caller)?;
+            self.settle_escrow(id, escrow, true)
+        }
+
+        /// Arbiter ruling: pays the unreleased milestones to the payee if
+        /// `release` is true, otherwise refunds them to the payer
+        #[ink(message)]
+        pub fn resolve_escrow(&mut self, id: u64, release: bool) -> Result<()> {
+            let escrow = self.escrow_for(id, |escrow, caller| escrow.arbiter == caller)?;
+            self.settle_escrow(id, escrow, release)
+        }
+
+        /// Refunds the payer after the deadline. Anyone may call it.
+        #[ink(message)]
+        pub fn refund_expired_escrow(&mut self, id: u64) -> Result<()> {
+            let escrow = self.escrow_for(id, |_, _| true)?;
+            if self.env().block_timestamp() < escrow.deadline {
+                return Err(Error::EscrowNotExpired);
+            }
+            self.settle_escrow(id, escrow, false)
+        }
+
+        #[ink(message)]
+        pub fn escrow(&self, id: u64) -> Option<Escrow> {
+            self.escrows.get(id)
+        }
+
+        /// Escrow `id`, if `allowed` lets the caller act on it
+        fn escrow_for(
+            &self,
+            id: u64,
+            allowed: impl FnOnce(&Escrow, AccountId) -> bool,
+        ) -> Result<Escrow> {
+            self.when_not_paused()?;
+            let caller = self.env().caller();
+            self.escrows
+                .get(id)
+                .filter(|escrow| allowed(escrow, caller))
+                .ok_or(Error::EscrowNotFound)
+        }
+
+        /// Pays out escrow `id` to the payee, or back to the payer, and removes it
+        fn settle_escrow(&mut self, id: u64, escrow: Escrow, release: bool) -> Result<()> {
+            let to = if release { escrow.payee } else { escrow.payer };
+            self.not_blacklisted(to)?;
+            let new_balance = self
+                .balance_of(to)
+                .checked_add(escrow.amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(to, new_balance)?;
+
+            self.escrows.remove(id);
+            self.move_balance(self.env().account_id(), to, escrow.amount)?;
+
+            let amount = escrow.amount;
+            if release {
+                self.env().emit_event(EscrowReleased { id, amount });
+            } else {
+                self.env().emit_event(EscrowRefunded { id, amount });
+            }
+
+            Ok(())
+        }
+
+        /// Asks `payer` to pay the caller `amount` before `expiry` and returns the
+        /// request ID
+        #[ink(message)]
+        pub fn create_payment_request(
+            &mut self,
+            payer: AccountId,
+            amount: u128,
+            memo: Vec<u8>,
+            expiry: Timestamp,
+        ) -> Result<u64> {
+            let merchant = self.env().caller();
+            self.not_blacklisted(merchant)?;
+            if amount == 0 || payer == merchant || expiry <= self.env().block_timestamp() {
+                return Err(Error::InvalidAmount);
+            }
+            if memo.len() > MAX_MEMO_LEN {
+                return Err(Error::MemoTooLong);
+            }
+
+            let id = self.next_payment_request_id;
+            self.next_payment_request_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            let count = self.open_request_count.get(merchant).unwrap_or(0);
+            let new_count = count.checked_add(1).ok_or(Error::Overflow)?;
+            self.open_requests.insert((merchant, count), &id);
+            self.open_request_index.insert(id, &count);
+            self.open_request_count.insert(merchant, &new_count);
+            self.payment_requests.insert(
+                id,
+                &PaymentRequest {
+                    merchant,
+                    payer,
+                    amount,
+                    memo,
+                    expiry,
+                    paid: false,
+                },
+            );
+
+            self.env().emit_event(PaymentRequestCreated {
+                id,
+                merchant,
+                payer,
+                amount,
+                expiry,
+            });
+
+            Ok(id)
+        }
+
+        /// Pays request `id` in full and marks it paid. Only its payer may pay it.
+        #[ink(message)]
+        pub fn pay_request(&mut self, id: u64) -> Result<()> {
+            let payer = self.env().caller();
+            let mut request = self
+                .payment_requests
+                .get(id)
+                .filter(|request| request.payer == payer)
+                .ok_or(Error::PaymentRequestNotFound)?;
+            if request.paid {
+                return Err(Error::PaymentRequestSettled);
+            }
+            if self.env().block_timestamp() >= request.expiry {
+                return Err(Error::PaymentRequestExpired);
+            }
+
+            self.transfer_with_data(request.merchant, request.amount, request.memo.clone())?;
+            request.paid = true;
+            self.close_payment_request(id, request.merchant);
+            self.payment_requests.insert(id, &request);
+
+            self.env().emit_event(PaymentRequestPaid {
+                id,
+                merchant: request.merchant,
+                payer,
+                amount: request.amount,
+                memo: request.memo,
+            });
+
+            Ok(())
+        }
+
+        /// Withdraws an unpaid request. Only its merchant may cancel it.
+        #[ink(message)]
+        pub fn cancel_payment_request(&mut self, id: u64) -> Result<()> {
+            let merchant = self.env().caller();
+            let request = self
+                .payment_requests
+                .get(id)
+                .filter(|request| request.merchant == merchant)
+                .ok_or(Error::PaymentRequestNotFound)?;
+            if request.paid {
+                return Err(Error::PaymentRequestSettled);
+            }
+
+            self.close_payment_request(id, merchant);
+            self.payment_requests.remove(id);
+
+            self.env().emit_event(PaymentRequestCancelled { id });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn payment_request(&self, id: u64) -> Option<PaymentRequest> {
+            self.payment_requests.get(id)
+        }
+
+        /// Page through `merchant`'s unpaid requests, including expired ones.
+        /// Order is not stable across payments and cancellations.
+        #[ink(message)]
+        pub fn open_payment_requests(
+            &self,
+            merchant: AccountId,
+            offset: u32,
+            limit: u32,
+        ) -> Vec<(u64, PaymentRequest)> {
+            let end = offset
+                .saturating_add(limit)
+                .min(self.open_payment_request_count(merchant));
+            (offset..end)
+                .filter_map(|index| self.open_requests.get((merchant, index)))
+                .filter_map(|id| self.payment_requests.get(id).map(|request| (id, request)))
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn open_payment_request_count(&self, merchant: AccountId) -> u32 {
+            self.open_request_count.get(merchant).unwrap_or(0)
+        }
+
+        /// Swap-removes request `id` from `merchant`'s open requests
+        fn close_payment_request(&mut self, id: u64, merchant: AccountId) {
+            let Some(index) = self.open_request_index.take(id) else {
+                return;
+            };
+            let last = self.open_payment_request_count(merchant).saturating_sub(1);
+            if index != last {
+                if let Some(moved) = self.open_requests.get((merchant, last)) {
+                    self.open_requests.insert((merchant, index), &moved);
+                    self.open_request_index.insert(moved, &index);
+                }
+            }
+            self.open_requests.remove((merchant, last));
+            self.open_request_count.insert(merchant, &last);
+        }
+
+        /// Splits a bill for `payee` into `shares`, payable until `expiry`.
+        /// Returns the bill ID.
+        #[ink(message)]
+        pub fn create_bill(
+            &mut self,
+            payee: AccountId,
+            shares: Vec<(AccountId, u128)>,
+            expiry: Timestamp,
+        ) -> Result<u64> {
+            let initiator = self.env().caller();
+            self.not_blacklisted(initiator)?;
+            self.not_blacklisted(payee)?;
+            if shares.is_empty() || shares.len() > MAX_BILL_PARTICIPANTS {
+                return Err(Error::InvalidAmount);
+            }
+            if expiry <= self.env().block_timestamp() {
+                return Err(Error::InvalidAmount);
+            }
+            let mut total: u128 = 0;
+            for (index, (participant, amount)) in shares.iter().enumerate() {
+                if *amount == 0
+                    || shares[..index]
+                        .iter()
+                        .any(|(other, _)| other == participant)
+                {
+                    return Err(Error::InvalidAmount);
+                }
+                total = total.checked_add(*amount).ok_or(Error::Overflow)?;
+            }
+
+            let id = self.next_bill_id;
+            self.next_bill_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.bills.insert(
+                id,
+                &Bill {
+                    initiator,
+                    payee,
+                    shares: shares
+                        .into_iter()
+                        .map(|(participant, amount)| BillShare {
+                            participant,
+                            amount,
+                            paid: false,
+                        })
+                        .collect(),
+                    expiry,
+                },
+            );
+
+            self.env().emit_event(BillCreated {
+                id,
+                initiator,
+                payee,
+                total,
+                expiry,
+            });
+
+            Ok(id)
+        }
+
+        /// Pays the caller's share of bill `id`. The last share releases the
+        /// total to the payee.
+        #[ink(message)]
+        pub fn pay_bill_share(&mut self, id: u64) -> Result<()> {
+            self.when_not_paused()?;
+
+            let participant = self.env().caller();
+            self.not_blacklisted(participant)?;
+            let mut bill = self.bills.get(id).ok_or(Error::BillNotFound)?;
+            if self.env().block_timestamp() >= bill.expiry {
+                return Err(Error::BillExpired);
+            }
+            let share = bill
+                .shares
+                .iter_mut()
+                .find(|share| share.participant == participant)
+                .ok_or(Error::BillNotFound)?;
+            if share.paid {
+                return Err(Error::ShareAlreadyPaid);
+            }
+            let amount = share.amount;
+            if self.spendable_balance_of(participant) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+            share.paid = true;
+
+            let custody = self.env().account_id();
+            self.move_balance(participant, custody, amount)?;
+            self.env().emit_event(BillSharePaid {
+                id,
+                participant,
+                amount,
+            });
+
+            if bill.shares.iter().all(|share| share.paid) {
+                let total = bill.shares.iter().map(|share| share.amount).sum();
+                self.bills.remove(id);
+                self.move_balance(custody, bill.payee, total)?;
+                self.env().emit_event(BillSettled { id, total });
+            } else {
+                self.bills.insert(id, &bill);
+            }
+
+            Ok(())
+        }
+
+        /// Returns the paid shares of an expired, incomplete bill to their
+        /// participants. Anyone may call it.
+        #[ink(message)]
+        pub fn refund_bill(&mut self, id: u64) -> Result<()> {
+            let bill = self.bills.get(id).ok_or(Error::BillNotFound)?;
+            if self.env().block_timestamp() < bill.expiry {
+                return Err(Error::BillNotExpired);
+            }
+
+            self.bills.remove(id);
+            let custody = self.env().account_id();
+            let mut refunded: u128 = 0;
+            for share in bill.shares.iter().filter(|share| share.paid) {
+                self.move_balance(custody, share.participant, share.amount)?;
+                refunded = refunded.saturating_add(share.amount);
+            }
+
+            self.env().emit_event(BillRefunded { id, refunded });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn bill(&self, id: u64) -> Option<Bill> {
+            self.bills.get(id)
+        }
+
+        /// Locks `amount` of the caller's tokens in a gift card that pays
+        /// whoever presents a code whose Blake2x256 hash is `code_hash`,
+        /// until `expiry`
+        #[ink(message)]
+        pub fn issue_gift_card(
+            &mut self,
+            code_hash: Hash,
+            amount: u128,
+            expiry: Timestamp,
+        ) -> Result<()> {
+            self.when_not_paused()?;
+
+            let issuer = self.env().caller();
+            self.not_blacklisted(issuer)?;
+            if amount == 0 || expiry <= self.env().block_timestamp() {
+                return Err(Error::InvalidAmount);
+            }
+            if self.gift_cards.contains(code_hash) {
+                return Err(Error::GiftCardExists);
+            }
+            if self.spendable_balance_of(issuer) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.move_balance(issuer, self.env().account_id(), amount)?;
+            self.gift_cards.insert(
+                code_hash,
+                &GiftCard {
+                    issuer,
+                    amount,
+                    expiry,
+                },
+            );
+
+            self.env().emit_event(GiftCardIssued {
+                code_hash,
+                issuer,
+                amount,
+                expiry,
+            });
+
+            Ok(())
+        }
+
+        /// Pays the gift card whose code is `code` to the caller
+        #[ink(message)]
+        pub fn redeem_gift_card(&mut self, code: Vec<u8>) -> Result<()> {
+            self.when_not_paused()?;
+
+            let recipient = self.env().caller();
+            self.not_blacklisted(recipient)?;
+            let code_hash = Hash::from(self.env().hash_bytes::<Blake2x256>(&code));
+            let card = self
+                .gift_cards
+                .get(code_hash)
+                .ok_or(Error::GiftCardNotFound)?;
+            if self.env().block_timestamp() >= card.expiry {
+                return Err(Error::GiftCardExpired);
+            }
+
+            self.gift_cards.remove(code_hash);
+            self.move_balance(self.env().account_id(), recipient, card.amount)?;
+
+            self.env().emit_event(GiftCardRedeemed {
+                code_hash,
+                recipient,
+                amount: card.amount,
+            });
+
+            Ok(())
+        }
+
+        /// Returns an expired, unredeemed gift card to its issuer
+        #[ink(message)]
+        pub fn reclaim_gift_card(&mut self, code_hash: Hash) -> Result<()> {
+            let card = self
+                .gift_cards
+                .get(code_hash)
+                .ok_or(Error::GiftCardNotFound)?;
+            if self.env().caller() != card.issuer {
+                return Err(Error::Unauthorized);
+            }
+            if self.env().block_timestamp() < card.expiry {
+                return Err(Error::GiftCardNotExpired);
+            }
+
+            self.gift_cards.remove(code_hash);
+            self.move_balance(self.env().account_id(), card.issuer, card.amount)?;
+
+            self.env().emit_event(GiftCardReclaimed {
+                code_hash,
+                issuer: card.issuer,
+                amount: card.amount,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn gift_card(&self, code_hash: Hash) -> Option<GiftCard> {
+            self.gift_cards.get(code_hash)
+        }
+
+        /// Opens a joint account held by `owners`, which must include the caller,
+        /// and returns its address. Tokens are paid into it like any account.
+        #[ink(message)]
+        pub fn create_joint_account(
+            &mut self,
+            owners: Vec<AccountId>,
+            approvals_required: u32,
+            threshold: u128,
+        ) -> Result<AccountId> {
+            let creator = self.env().caller();
+            self.not_blacklisted(creator)?;
+            if !owners.contains(&creator) {
+                return Err(Error::NotJointOwner);
+            }
+            if owners.len() > MAX_JOINT_OWNERS
+                || approvals_required == 0
+                || approvals_required as usize > owners.len()
+            {
+                return Err(Error::InvalidThreshold);
+            }
+            for (i, owner) in owners.iter().enumerate() {
+                if owners[..i].contains(owner) {
+                    return Err(Error::DuplicateOwner);
+                }
+            }
+
+            let id = self.next_joint_account_id;
+            self.next_joint_account_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            let joint = AccountId::from(self.env().hash_encoded::<Blake2x256, _>(&(
+                b"JointAccount",
+                self.env().account_id(),
+                id,
+            )));
+            self.joint_accounts.insert(
+                joint,
+                &JointAccount {
+                    owners: owners.clone(),
+                    approvals_required,
+                    threshold,
+                },
+            );
+
+            self.env().emit_event(JointAccountCreated {
+                joint,
+                owners,
+                approvals_required,
+                threshold,
+            });
+
+            Ok(joint)
+        }
+
+        /// Moves up to the joint account's threshold out of it without approvals
+        #[ink(message)]
+        pub fn transfer_from_joint(
+            &mut self,
+            joint: AccountId,
+            to: AccountId,
+            amount: u128,
+        ) -> Result<()> {
+            self.when_not_paused()?;
+
+            let owner = self.env().caller();
+            let account = self.joint_owner(joint, owner)?;
+            if amount > account.threshold {
+                return Err(Error::JointApprovalRequired);
+            }
+            self.not_blacklisted(owner)?;
+            self.not_blacklisted(joint)?;
+            self.not_blacklisted(to)?;
+
+            self._transfer(joint, to, amount, Vec::new())
+        }
+
+        /// Proposes a transfer out of a joint account and returns its ID; the
+        /// proposer's approval is counted
+        #[ink(message)]
+        pub fn propose_joint_transfer(
+            &mut self,
+            joint: AccountId,
+            to: AccountId,
+            amount: u128,
+        ) -> Result<u64> {
+            let proposer = self.env().caller();
+            self.joint_owner(joint, proposer)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            let mut pending = self.pending_joint_transfers.get(joint).unwrap_or_default();
+            if pending.len() >= MAX_PENDING_JOINT_TRANSFERS {
+                return Err(Error::TooManyJointTransfers);
+            }
+
+            let id = self.next_joint_transfer_id;
+            self.next_joint_transfer_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.joint_transfers.insert(
+                id,
+                &JointTransfer {
+                    joint,
+                    proposer,
+                    to,
+                    amount,
+                    approvals: Vec::new(),
+                },
+            );
+            pending.push(id);
+            self.pending_joint_transfers.insert(joint, &pending);
+
+            self.env().emit_event(JointTransferProposed {
+                id,
+                joint,
+                proposer,
+                to,
+                amount,
+            });
+
+            self.approve_joint_transfer(id)?;
+
+            Ok(id)
+        }
+
+        #[ink(message)]
+        pub fn approve_joint_transfer(&mut self, id: u64) -> Result<()> {
+            let owner = self.env().caller();
+            let mut transfer = self
+                .joint_transfers
+                .get(id)
+                .ok_or(Error::JointTransferNotFound)?;
+            self.joint_owner(transfer.joint, owner)?;
+            if transfer.approvals.contains(&owner) {
+                return Err(Error::AlreadyConfirmed);
+            }
+
+            transfer.approvals.push(owner);
+            self.joint_transfers.insert(id, &transfer);
+
+            self.env().emit_event(JointTransferApproved {
+                id,
+                owner,
+                approvals: transfer.approvals.len() as u32,
+            });
+
+            Ok(())
+        }
+
+        /// Makes a proposed transfer once enough owners have approved it
+        #[ink(message)]
+        pub fn execute_joint_transfer(&mut self, id: u64) -> Result<()> {
+            self.when_not_paused()?;
+
+            let owner = self.env().caller();
+            let transfer = self
+                .joint_transfers
+                .get(id)
+                .ok_or(Error::JointTransferNotFound)?;
+            let account = self.joint_owner(transfer.joint, owner)?;
+            if (transfer.approvals.len() as u32) < account.approvals_required {
+                return Err(Error::JointApprovalRequired);
+            }
+            self.not_blacklisted(owner)?;
+            self.not_blacklisted(transfer.joint)?;
+            self.not_blacklisted(transfer.to)?;
+
+            self._transfer(transfer.joint, transfer.to, transfer.amount, Vec::new())?;
+            self.remove_joint_transfer(id, transfer.joint);
+
+            self.env().emit_event(JointTransferExecuted { id });
+
+            Ok(())
+        }
+
+        /// Withdraws a proposed transfer; only its proposer can
+        #[ink(message)]
+        pub fn cancel_joint_transfer(&mut self, id: u64) -> Result<()> {
+            let transfer = self
+                .joint_transfers
+                .get(id)
+                .ok_or(Error::JointTransferNotFound)?;
+            if transfer.proposer != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+
+            self.remove_joint_transfer(id, transfer.joint);
+
+            self.env().emit_event(JointTransferCancelled { id });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn joint_account(&self, joint: AccountId) -> Option<JointAccount> {
+            self.joint_accounts.get(joint)
+        }
+
+        #[ink(message)]
+        pub fn joint_transfer(&self, id: u64) -> Option<JointTransfer> {
+            self.joint_transfers.get(id)
+        }
+
+        /// IDs of the transfers out of `joint` still waiting to be executed
+        #[ink(message)]
+        pub fn pending_joint_transfers(&self, joint: AccountId) -> Vec<u64> {
+            self.pending_joint_transfers.get(joint).unwrap_or_default()
+        }
+
+        fn joint_owner(&self, joint: AccountId, owner: AccountId) -> Result<JointAccount> {
+            let account = self
+                .joint_accounts
+                .get(joint)
+                .ok_or(Error::JointAccountNotFound)?;
+            if !account.owners.contains(&owner) {
+                return Err(Error::NotJointOwner);
+            }
+            Ok(account)
+        }
+
+        fn remove_joint_transfer(&mut self, id: u64, joint: AccountId) {
+            self.joint_transfers.remove(id);
+            let mut pending = self.pending_joint_transfers.get(joint).unwrap_or_default();
+            pending.retain(|transfer| *transfer != id);
+            self.pending_joint_transfers.insert(joint, &pending);
+        }
+
+        /// Sets aside `amount` of the caller's spendable balance in envelope
+        /// `name`, creating the envelope if needed
+        #[ink(message)]
+        pub fn fund_envelope(&mut self, name: String, amount: u128) -> Result<()> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(account) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            let mut envelope = self.open_envelope(account, &name)?;
+            envelope.balance = envelope
+                .balance
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.move_balance(account, self.env().account_id(), amount)?;
+            self.envelopes.insert((account, name.clone()), &envelope);
+
+            self.env().emit_event(EnvelopeFunded {
+                account,
+                name,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Returns `amount` from envelope `name` to the caller's balance
+        #[ink(message)]
+        pub fn release_envelope(&mut self, name: String, amount: u128) -> Result<()> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            let new_balance = self
+                .balance_of(account)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(account, new_balance)?;
+
+            self.take_from_envelope(account, &name, amount)?;
+            self.move_balance(self.env().account_id(), account, amount)?;
+
+            self.env().emit_event(EnvelopeReleased {
+                account,
+                name,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Pays `amount` from envelope `name` to `to`, as a transfer from the caller
+        #[ink(message)]
+        pub fn spend_from_envelope(
+            &mut self,
+            name: String,
+            to: AccountId,
+            amount: u128,
+        ) -> Result<()> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            self.not_blacklisted(to)?;
+
+            self.take_from_envelope(account, &name, amount)?;
+            self.move_balance(self.env().account_id(), account, amount)?;
+            self._transfer(account, to, amount, Vec::new())?;
+
+            self.env().emit_event(EnvelopeReleased {
+                account,
+                name,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Moves `amount` from the caller's envelope `from` to its envelope `to`
+        #[ink(message)]
+        pub fn move_between_envelopes(
+            &mut self,
+            from: String,
+            to: String,
+            amount: u128,
+        ) -> Result<()> {
+            let account = self.env().caller();
+            if from == to {
+                return Err(Error::InvalidEnvelopeName);
+            }
+
+            self.take_from_envelope(account, &from, amount)?;
+            let mut envelope = self.open_envelope(account, &to)?;
+            envelope.balance = envelope
+                .balance
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.envelopes.insert((account, to.clone()), &envelope);
+
+            self.env().emit_event(EnvelopeMoved {
+                account,
+                from,
+                to,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Keeps anything from leaving envelope `name` before `locked_until`. A
+        /// lock can only be extended.
+        #[ink(message)]
+        pub fn lock_envelope(&mut self, name: String, locked_until: Timestamp) -> Result<()> {
+            let account = self.env().caller();
+            let mut envelope = self
+                .envelopes
+                .get((account, name.clone()))
+                .ok_or(Error::EnvelopeNotFound)?;
+            if locked_until <= envelope.locked_until {
+                return Err(Error::EnvelopeLocked);
+            }
+
+            envelope.locked_until = locked_until;
+            self.envelopes.insert((account, name.clone()), &envelope);
+
+            self.env().emit_event(EnvelopeLocked {
+                account,
+                name,
+                locked_until,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn envelope(&self, account: AccountId, name: String) -> Option<Envelope> {
+            self.envelopes.get((account, name))
+        }
+
+        /// Every envelope of `account` with its name
+        #[ink(message)]
+        pub fn envelopes_of(&self, account: AccountId) -> Vec<(String, Envelope)> {
+            self.envelope_names
+                .get(account)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|name| {
+                    let envelope = self.envelopes.get((account, name.clone()))?;
+                    Some((name, envelope))
+                })
+                .collect()
+        }
+
+        /// Envelope `name` of `account`, registered under its name if it is new
+        fn open_envelope(&mut self, account: AccountId, name: &str) -> Result<Envelope> {
+            if let Some(envelope) = self.envelopes.get((account, String::from(name))) {
+                return Ok(envelope);
+            }
+            if name.is_empty() || name.len() > MAX_ENVELOPE_NAME_LEN {
+                return Err(Error::InvalidEnvelopeName);
+            }
+            let mut names = self.envelope_names.get(account).unwrap_or_default();
+            if names.len() >= MAX_ENVELOPES {
+                return Err(Error::TooManyEnvelopes);
+            }
+            names.push(String::from(name));
+            self.envelope_names.insert(account, &names);
+
+            Ok(Envelope::default())
+        }
+
+        /// Deducts `amount` from an unlocked envelope, closing it once it is empty
+        fn take_from_envelope(
+            &mut self,
+            account: AccountId,
+            name: &str,
+            amount: u128,
+        ) -> Result<()> {
+            let mut envelope = self
+                .envelopes
+                .get((account, String::from(name)))
+                .ok_or(Error::EnvelopeNotFound)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.env().block_timestamp() < envelope.locked_until {
+                return Err(Error::EnvelopeLocked);
+            }
+            envelope.balance = envelope
+                .balance
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientBalance)?;
+
+            if envelope.balance == 0 {
+                self.envelopes.remove((account, String::from(name)));
+                let mut names = self.envelope_names.get(account).unwrap_or_default();
+                names.retain(|other| other != name);
+                self.envelope_names.insert(account, &names);
+            } else {
+                self.envelopes
+                    .insert((account, String::from(name)), &envelope);
+            }
+
+            Ok(())
+        }
+
+        /// Moves tokens between balances without transfer policy checks, for
+        /// tokens entering or leaving the contract's custody
+        fn move_balance(&mut self, from: AccountId, to: AccountId, amount: u128) -> Result<()> {
+            self.settle_demurrage(from);
+            self.settle_demurrage(to);
+            let new_from_balance = self
+                .balance_of(from)
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientBalance)?;
+            let new_to_balance = self
+                .balance_of(to)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+
+            self.write_balance(from, new_from_balance);
+            self.write_balance(to, new_to_balance);
+
+            self.env().emit_event(Transfer {
+                from: Some(from),
+                to: Some(to),
+                value: amount,
+                data: Vec::new(),
+            });
+
+            Ok(())
+        }
+
+        /// Stores `balance` for `account`, checkpointing its previous balance if
+        /// this is its first change since the current snapshot
+        fn write_balance(&mut self, account: AccountId, balance: u128) {
+            self.checkpoint_balance(account);
+            self.balances.insert(account, &balance);
+        }
+
+        /// Records `account`'s stored balance against the current snapshot, unless
+        /// it already has been
+        fn checkpoint_balance(&mut self, account: AccountId) {
+            let snapshot = self.current_snapshot;
+            if snapshot == 0 {
+                return;
+            }
+            let count = self.checkpoint_counts.get(account).unwrap_or(0);
+            if count > 0
+                && self
+                    .balance_checkpoints
+                    .get((account, count - 1))
+                    .is_some_and(|(id, _)| id == snapshot)
+            {
+                return;
+            }
+
+            let balance = self.balances.get(account).unwrap_or(0);
+            self.balance_checkpoints
+                .insert((account, count), &(snapshot, balance));
+            self.checkpoint_counts.insert(account, &(count + 1));
+        }
+
+        /// ID of the latest balance snapshot, 0 before the first distribution
+        #[ink(message)]
+        pub fn current_snapshot(&self) -> u32 {
+            self.current_snapshot
+        }
+
+        /// Stored balance of `account` when `snapshot` was taken
+        #[ink(message)]
+        pub fn balance_at(&self, account: AccountId, snapshot: u32) -> u128 {
+            // The first checkpoint recorded at or after `snapshot` holds the
+            // balance from then; without one, it hasn't changed since
+            let mut low = 0;
+            let mut high = self.checkpoint_counts.get(account).unwrap_or(0);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                match self.balance_checkpoints.get((account, mid)) {
+                    Some((id, _)) if id < snapshot => low = mid + 1,
+                    _ => high = mid,
+                }
+            }
+            match self.balance_checkpoints.get((account, low)) {
+                Some((_, balance)) => balance,
+                None => self.balances.get(account).unwrap_or(0),
+            }
+        }
+
+        /// Moves `amount` of the owner's balance into custody as a dividend, takes
+        /// a balance snapshot and returns the distribution ID. Holders claim
+        /// their share with `claim_dividend`
+        #[ink(message)]
+        pub fn create_distribution(&mut self, amount: u128) -> Result<u64> {
+            self.only_owner()?;
+            self.when_not_paused()?;
+
+            let owner = self.env().caller();
+            let custody = self.env().account_id();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(owner) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+            self.move_balance(owner, custody, amount)?;
+
+            let eligible_supply = self
+                .total_supply
+                .saturating_sub(self.balances.get(custody).unwrap_or(0));
+            if eligible_supply == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            let snapshot = self
+                .current_snapshot
+                .checked_add(1)
+                .ok_or(Error::Overflow)?;
+            let id = self.next_distribution_id;
+            self.next_distribution_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.current_snapshot = snapshot;
+            self.distributions.insert(
+                id,
+                &Distribution {
+                    snapshot,
+                    amount,
+                    eligible_supply,
+                    claimed: 0,
+                },
+            );
+
+            self.env().emit_event(DistributionCreated {
+                id,
+                snapshot,
+                amount,
+            });
+
+            Ok(id)
+        }
+
+        /// Pays the caller its share of distribution `id`, pro rata to its balance
+        /// at the distribution's snapshot
+        #[ink(message)]
+        pub fn claim_dividend(&mut self, id: u64) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            let mut distribution = self
+                .distributions
+                .get(id)
+                .ok_or(Error::DistributionNotFound)?;
+            if self.dividend_claims.contains((id, account)) {
+                return Err(Error::DividendAlreadyClaimed);
+            }
+            let amount = self.dividend_share(&distribution, account)?;
+            if amount == 0 {
+                return Err(Error::NothingToClaim);
+            }
+
+            let new_balance = self
+                .balance_of(account)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(account, new_balance)?;
+            self.move_balance(self.env().account_id(), account, amount)?;
+            distribution.claimed = distribution
+                .claimed
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.distributions.insert(id, &distribution);
+            self.dividend_claims.insert((id, account), &());
+
+            self.env().emit_event(DividendClaimed {
+                id,
+                account,
+                amount,
+            });
+
+            Ok(amount)
+        }
+
+        /// Distribution `id`
+        #[ink(message)]
+        pub fn distribution(&self, id: u64) -> Option<Distribution> {
+            self.distributions.get(id)
+        }
+
+        /// What `account` can still claim from distribution `id`
+        #[ink(message)]
+        pub fn dividend_of(&self, id: u64, account: AccountId) -> u128 {
+            match self.distributions.get(id) {
+                Some(distribution) if !self.dividend_claims.contains((id, account)) => {
+                    self.dividend_share(&distribution, account).unwrap_or(0)
+                }
+                _ => 0,
+            }
+        }
+
+        /// Moves `total` of the owner's balance into custody for the `(account,
+        /// amount)` pairs under the Merkle `root`, claimable until `expires_at`.
+        /// Returns the airdrop ID
+        #[ink(message)]
+        pub fn create_airdrop(
+            &mut self,
+            root: Hash,
+            total: u128,
+            expires_at: Timestamp,
+        ) -> Result<u64> {
+            self.only_owner()?;
+            self.when_not_paused()?;
+
+            let owner = self.env().caller();
+            if total == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if expires_at <= self.env().block_timestamp() {
+                return Err(Error::InvalidExpiry);
+            }
+            if self.spendable_balance_of(owner) < total {
+                return Err(Error::InsufficientBalance);
+            }
+
+            let id = self.next_airdrop_id;
+            self.next_airdrop_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.move_balance(owner, self.env().account_id(), total)?;
+            self.airdrops.insert(
+                id,
+                &Airdrop {
+                    root,
+                    total,
+                    claimed: 0,
+                    expires_at,
+                    reclaimed: false,
+                },
+            );
+
+            self.env().emit_event(AirdropCreated {
+                id,
+                root,
+                total,
+                expires_at,
+            });
+
+            Ok(id)
+        }
+
+        /// Pays the caller `amount` from airdrop `id`, given a `proof` that the
+        /// `(caller, amount)` pair is under the airdrop's root
+        #[ink(message)]
+        pub fn claim_airdrop(&mut self, id: u64, amount: u128, proof: Vec<Hash>) -> Result<()> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            let mut airdrop = self.airdrops.get(id).ok_or(Error::AirdropNotFound)?;
+            if self.env().block_timestamp() >= airdrop.expires_at {
+                return Err(Error::AirdropExpired);
+            }
+            if self.airdrop_claims.contains((id, account)) {
+                return Err(Error::AirdropAlreadyClaimed);
+            }
+            if proof.len() > MAX_MERKLE_PROOF_LEN
+                || self.merkle_root(self.merkle_leaf(account, amount), &proof) != airdrop.root
+            {
+                return Err(Error::InvalidMerkleProof);
+            }
+
+            let claimed = airdrop
+                .claimed
+                .checked_add(amount)
+                .filter(|claimed| *claimed <= airdrop.total)
+                .ok_or(Error::InsufficientBalance)?;
+            let new_balance = self
+                .balance_of(account)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(account, new_balance)?;
+            self.move_balance(self.env().account_id(), account, amount)?;
+            airdrop.claimed = claimed;
+            self.airdrops.insert(id, &airdrop);
+            self.airdrop_claims.insert((id, account), &());
+
+            self.env().emit_event(AirdropClaimed {
+                id,
+                account,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Once airdrop `id` has expired, adds what is left of it to the treasury
+        /// and returns that amount
+        #[ink(message)]
+        pub fn reclaim_airdrop(&mut self, id: u64) -> Result<u128> {
+            let mut airdrop = self
+                .airdrops
+                .get(id)
+                .filter(|airdrop| !airdrop.reclaimed)
+                .ok_or(Error::AirdropNotFound)?;
+            if self.env().block_timestamp() < airdrop.expires_at {
+                return Err(Error::AirdropNotExpired);
+            }
+
+            let amount = airdrop.total - airdrop.claimed;
+            self.treasury_balance = self
+                .treasury_balance
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            airdrop.reclaimed = true;
+            self.airdrops.insert(id, &airdrop);
+
+            self.env().emit_event(AirdropReclaimed { id, amount });
+
+            Ok(amount)
+        }
+
+        /// Airdrop `id`
+        #[ink(message)]
+        pub fn airdrop(&self, id: u64) -> Option<Airdrop> {
+            self.airdrops.get(id)
+        }
+
+        /// Whether `account` has claimed from airdrop `id`
+        #[ink(message)]
+        pub fn airdrop_claimed(&self, id: u64, account: AccountId) -> bool {
+            self.airdrop_claims.contains((id, account))
+        }
+
+        /// Leaf committing to `account` receiving `amount`: the Blake2x256 hash of
+        /// the SCALE-encoded pair
+        fn merkle_leaf(&self, account: AccountId, amount: u128) -> Hash {
+            Hash::from(self.env().hash_encoded::<Blake2x256, _>(&(account, amount)))
+        }
+
+        /// Root reached from `leaf` by hashing it with each proof node in turn,
+        /// smaller hash first
+        fn merkle_root(&self, leaf: Hash, proof: &[Hash]) -> Hash {
+            proof.iter().fold(leaf, |node, sibling| {
+                let pair = if node <= *sibling {
+                    (node, *sibling)
+                } else {
+                    (*sibling, node)
+                };
+                Hash::from(self.env().hash_encoded::<Blake2x256, _>(&pair))
+            })
+        }
+
+        /// `account`'s pro-rata share of `distribution`
+        fn dividend_share(&self, distribution: &Distribution, account: AccountId) -> Result<u128> {
+            if account == self.env().account_id() {
+                return Ok(0);
+            }
+            Self::mul_div(
+                distribution.amount,
+                self.balance_at(account, distribution.snapshot),
+                distribution.eligible_supply,
+                false,
+            )
+        }
+
+        /// Burns tokens and returns the same amount of native currency
+        #[ink(message)]
+        pub fn withdraw(&mut self, amount: Balance) -> Result<()> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+
+            self._burn(account, amount)?;
+            self.env()
+                .transfer(account, amount)
+                .map_err(|_| Error::NativeTransferFailed)?;
+
+            self.env().emit_event(Withdrawal { account, amount });
+
+            Ok(())
+        }
+
+        /// Sets or switches off the bonding curve. It can't change while tokens
+        /// bought from it are outstanding, as their reserve was priced on it
+        #[ink(message)]
+        pub fn set_bonding_curve(&mut self, curve: Option<BondingCurve>) -> Result<()> {
+            self.only_owner()?;
+            if self.curve_supply > 0 {
+                return Err(Error::BondingCurveInUse);
+            }
+            if curve
+                .as_ref()
+                .is_some_and(|curve| curve.base_price == 0 && curve.slope == 0)
+            {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.bonding_curve = curve.clone();
+
+            self.audit(AuditOperation::Config, &curve);
+            self.env().emit_event(BondingCurveUpdated { curve });
+
+            Ok(())
+        }
+
+        /// The active bonding curve
+        #[ink(message)]
+        pub fn bonding_curve(&self) -> Option<BondingCurve> {
+            self.bonding_curve.clone()
+        }
+
+        /// Tokens bought from the bonding curve and not yet sold back
+        #[ink(message)]
+        pub fn curve_supply(&self) -> u128 {
+            self.curve_supply
+        }
+
+        /// Native currency held to buy back the curve supply
+        #[ink(message)]
+        pub fn curve_reserve(&self) -> Balance {
+            self.curve_reserve
+        }
+
+        /// Native cost of buying `amount` from the bonding curve now
+        #[ink(message)]
+        pub fn quote_curve_buy(&self, amount: u128) -> Result<Balance> {
+            let curve = self
+                .bonding_curve
+                .as_ref()
+                .ok_or(Error::BondingCurveDisabled)?;
+            Self::curve_price(curve, self.curve_supply, amount, true)
+        }
+
+        /// Native proceeds of selling `amount` back to the bonding curve now
+        #[ink(message)]
+        pub fn quote_curve_sell(&self, amount: u128) -> Result<Balance> {
+            let curve = self
+                .bonding_curve
+                .as_ref()
+                .ok_or(Error::BondingCurveDisabled)?;
+            let start = self
+                .curve_supply
+                .checked_sub(amount)
+                .ok_or(Error::InvalidAmount)?;
+            Self::curve_price(curve, start, amount, false)
+        }
+
+        /// Mints `amount` to the caller at the bonding curve price, paid from the
+        /// native value sent with the call. Anything sent above the price is
+        /// returned
+        #[ink(message, payable)]
+        pub fn buy_from_curve(&mut self, amount: u128) -> Result<Balance> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            let cost = self.quote_curve_buy(amount)?;
+            let paid = self.env().transferred_value();
+            if paid < cost {
+                return Err(Error::InsufficientPayment);
+            }
+
+            self._mint(account, amount)?;
+            self.curve_supply = self
+                .curve_supply
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.curve_reserve = self
+                .curve_reserve
+                .checked_add(cost)
+                .ok_or(Error::Overflow)?;
+            if paid > cost {
+                self.env()
+                    .transfer(account, paid - cost)
+                    .map_err(|_| Error::NativeTransferFailed)?;
+            }
+
+            self.env().emit_event(CurveBought {
+                account,
+                amount,
+                cost,
+            });
+
+            Ok(cost)
+        }
+
+        /// Burns `amount` of the caller's tokens and pays out the bonding curve
+        /// price from the reserve, provided it is at least `min_proceeds`
+        #[ink(message)]
+        pub fn sell_to_curve(&mut self, amount: u128, min_proceeds: Balance) -> Result<Balance> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            let proceeds = self.quote_curve_sell(amount)?;
+            if proceeds < min_proceeds {
+                return Err(Error::SlippageExceeded);
+            }
+
+            self._burn(account, amount)?;
+            self.curve_supply -= amount;
+            self.curve_reserve = self.curve_reserve.saturating_sub(proceeds);
+            self.env()
+                .transfer(account, proceeds)
+                .map_err(|_| Error::NativeTransferFailed)?;
+
+            self.env().emit_event(CurveSold {
+                account,
+                amount,
+                proceeds,
+            });
+
+            Ok(proceeds)
+        }
+
+        /// Moves `supply` of the owner's balance into custody and sells it at
+        /// `rate` tokens per native unit between `start` and `end`. Any earlier sale
+        /// must have been wound up with `reclaim_unsold` and
+        /// `withdraw_sale_proceeds`
+        #[ink(message)]
+        pub fn create_sale(
+            &mut self,
+            rate: u128,
+            start: Timestamp,
+            end: Timestamp,
+            supply: u128,
+            account_cap: u128,
+            whitelist_only: bool,
+        ) -> Result<u32> {
+            self.only_owner()?;
+            self.when_not_paused()?;
+
+            let owner = self.env().caller();
+            if rate == 0 || supply == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if start >= end || end <= self.env().block_timestamp() {
+                return Err(Error::InvalidExpiry);
+            }
+            if self
+                .sale
+                .as_ref()
+                .is_some_and(|sale| !sale.unsold_reclaimed || !sale.proceeds_withdrawn)
+            {
+                return Err(Error::SaleInProgress);
+            }
+            if self.spendable_balance_of(owner) < supply {
+                return Err(Error::InsufficientBalance);
+            }
+
+            let round = self.sale_round.checked_add(1).ok_or(Error::Overflow)?;
+            self.move_balance(owner, self.env().account_id(), supply)?;
+            self.sale_round = round;
+            self.sale = Some(TokenSale {
+                rate,
+                start,
+                end,
+                supply,
+                sold: 0,
+                account_cap,
+                whitelist_only,
+                raised: 0,
+                unsold_reclaimed: false,
+                proceeds_withdrawn: false,
+            });
+
+            self.env().emit_event(SaleCreated {
+                round,
+                rate,
+                start,
+                end,
+                supply,
+            });
+
+            Ok(round)
+        }
+
+        /// The current or last token sale
+        #[ink(message)]
+        pub fn sale(&self) -> Option<TokenSale> {
+            self.sale.clone()
+        }
+
+        /// Tokens `account` has bought in the current sale
+        #[ink(message)]
+        pub fn sale_purchased(&self, account: AccountId) -> u128 {
+            self.sale_purchases
+                .get((self.sale_round, account))
+                .unwrap_or(0)
+        }
+
+        /// Sells the caller tokens for the native value sent with the call, at the
+        /// sale rate. Returns the tokens bought
+        #[ink(message, payable)]
+        pub fn buy(&mut self) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let buyer = self.env().caller();
+            self.not_blacklisted(buyer)?;
+            let mut sale = self.sale.clone().ok_or(Error::SaleNotFound)?;
+            let now = self.env().block_timestamp();
+            if now < sale.start || now >= sale.end {
+                return Err(Error::SaleNotActive);
+            }
+            if sale.whitelist_only && !self.is_whitelisted(buyer) {
+                return Err(Error::NotWhitelisted);
+            }
+
+            let paid = self.env().transferred_value();
+            let amount = paid.checked_mul(sale.rate).ok_or(Error::Overflow)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            let sold = sale
+                .sold
+                .checked_add(amount)
+                .filter(|sold| *sold <= sale.supply)
+                .ok_or(Error::SaleCapExceeded)?;
+            let purchased = self
+                .sale_purchased(buyer)
+                .checked_add(amount)
+                .filter(|purchased| sale.account_cap == 0 || *purchased <= sale.account_cap)
+                .ok_or(Error::SaleCapExceeded)?;
+            let new_balance = self
+                .balance_of(buyer)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(buyer, new_balance)?;
+
+            self.move_balance(self.env().account_id(), buyer, amount)?;
+            sale.sold = sold;
+            sale.raised = sale.raised.checked_add(paid).ok_or(Error::Overflow)?;
+            self.sale = Some(sale);
+            self.sale_purchases
+                .insert((self.sale_round, buyer), &purchased);
+
+            self.env().emit_event(TokensPurchased {
+                buyer,
+                amount,
+                paid,
+            });
+
+            Ok(amount)
+        }
+
+        /// Returns the tokens left unsold to the owner once the sale has closed
+        #[ink(message)]
+        pub fn reclaim_unsold(&mut self) -> Result<u128> {
+            self.only_owner()?;
+
+            let mut sale = self.ended_sale()?;
+            if sale.unsold_reclaimed {
+                return Err(Error::NothingToClaim);
+            }
+
+            let amount = sale.supply - sale.sold;
+            if amount > 0 {
+                self.move_balance(self.env().account_id(), self.owner, amount)?;
+            }
+            sale.unsold_reclaimed = true;
+            self.sale = Some(sale);
+
+            self.env().emit_event(SaleUnsoldReclaimed {
+                round: self.sale_round,
+                amount,
+            });
+
+            Ok(amount)
+        }
+
+        /// Pays the native currency raised to the owner once the sale has closed
+        #[ink(message)]
+        pub fn withdraw_sale_proceeds(&mut self) -> Result<Balance> {
+            self.only_owner()?;
+
+            let mut sale = self.ended_sale()?;
+            if sale.proceeds_withdrawn {
+                return Err(Error::NothingToClaim);
+            }
+
+            let amount = sale.raised;
+            sale.proceeds_withdrawn = true;
+            self.sale = Some(sale);
+            if amount > 0 {
+                self.env()
+                    .transfer(self.owner, amount)
+                    .map_err(|_| Error::NativeTransferFailed)?;
+            }
+
+            self.env().emit_event(SaleProceedsWithdrawn {
+                round: self.sale_round,
+                amount,
+            });
+
+            Ok(amount)
+        }
+
+        /// The current sale, provided it has closed
+        fn ended_sale(&self) -> Result<TokenSale> {
+            let sale = self.sale.clone().ok_or(Error::SaleNotFound)?;
+            if self.env().block_timestamp() < sale.end {
+                return Err(Error::SaleNotEnded);
+            }
+            Ok(sale)
+        }
+
+        /// Sets or removes the PSP22 token this one swaps against at a fixed rate
+        #[ink(message)]
+        pub fn set_swap_pair(&mut self, pair: Option<SwapPair>) -> Result<()> {
+            self.only_owner()?;
+            if let Some(pair) = &pair {
+                if pair.rate == 0 {
+                    return Err(Error::InvalidAmount);
+                }
+                if pair.token == self.env().account_id() || !self.env().is_contract(&pair.token) {
+                    return Err(Error::NotAContract);
+                }
+            }
+
+            self.swap_pair = pair.clone();
+
+            self.audit(AuditOperation::Config, &pair);
+            self.env().emit_event(SwapPairUpdated { pair });
+
+            Ok(())
+        }
+
+        /// The PSP22 token this one swaps against
+        #[ink(message)]
+        pub fn swap_pair(&self) -> Option<SwapPair> {
+            self.swap_pair.clone()
+        }
+
+        /// Tokens held in custody to pay out swaps from the pair token
+        #[ink(message)]
+        pub fn swap_reserve(&self) -> u128 {
+            self.swap_reserve
+        }
+
+        /// Moves `amount` of the caller's balance into the swap reserve
+        #[ink(message)]
+        pub fn fund_swap_reserve(&mut self, amount: u128) -> Result<()> {
+            self.when_not_paused()?;
+
+            let from = self.env().caller();
+            self.not_blacklisted(from)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(from) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.swap_reserve = self
+                .swap_reserve
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.move_balance(from, self.env().account_id(), amount)
+        }
+
+        /// Returns `amount` of the swap reserve to the owner
+        #[ink(message)]
+        pub fn withdraw_swap_reserve(&mut self, amount: u128) -> Result<()> {
+            self.only_owner()?;
+
+            self.swap_reserve = self
+                .swap_reserve
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientBalance)?;
+            self.move_balance(self.env().account_id(), self.owner, amount)
+        }
+
+        /// Swaps `amount` of the caller's tokens for `amount * rate` of the pair
+        /// token, paid from the pair tokens this contract holds. The tokens join
+        /// the swap reserve
+        #[ink(message)]
+        pub fn swap_to_pair(&mut self, amount: u128) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            let pair = self.swap_pair.clone().ok_or(Error::SwapNotConfigured)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.spendable_balance_of(account) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+            let pair_amount = amount.checked_mul(pair.rate).ok_or(Error::Overflow)?;
+
+            self.move_balance(account, self.env().account_id(), amount)?;
+            self.swap_reserve = self
+                .swap_reserve
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.call_psp22(
+                pair.token,
+                ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
+                    .push_arg(account)
+                    .push_arg(pair_amount)
+                    .push_arg(Vec::<u8>::new()),
+            )?;
+
+            self.env().emit_event(Swapped {
+                account,
+                to_pair: true,
+                amount,
+                pair_amount,
+            });
+
+            Ok(pair_amount)
+        }
+
+        /// Pulls `pair_amount` of the pair token from the caller, which must have
+        /// approved this contract, and pays `pair_amount / rate` from the swap
+        /// reserve. `pair_amount` must be a multiple of the rate
+        #[ink(message)]
+        pub fn swap_from_pair(&mut self, pair_amount: u128) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let account = self.env().caller();
+            self.not_blacklisted(account)?;
+            let pair = self.swap_pair.clone().ok_or(Error::SwapNotConfigured)?;
+            let amount = pair_amount / pair.rate;
+            if amount == 0 || pair_amount % pair.rate != 0 {
+                return Err(Error::InvalidAmount);
+            }
+            self.swap_reserve = self
+                .swap_reserve
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientBalance)?;
+            let new_balance = self
+                .balance_of(account)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(account, new_balance)?;
+
+            self.move_balance(self.env().account_id(), account, amount)?;
+            self.call_psp22(
+                pair.token,
+                ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer_from")))
+                    .push_arg(account)
+                    .push_arg(self.env().account_id())
+                    .push_arg(pair_amount)
+                    .push_arg(Vec::<u8>::new()),
+            )?;
+
+            self.env().emit_event(Swapped {
+                account,
+                to_pair: false,
+                amount,
+                pair_amount,
+            });
+
+            Ok(amount)
+        }
+
+        /// Calls a PSP22 `transfer` or `transfer_from` on another token contract.
+        /// Any failure reverts the whole message, so state changed before the
+        /// call is rolled back with it
+        fn call_psp22<Args: ink::scale::Encode>(
+            &self,
+            token: AccountId,
+            input: ExecutionInput<Args>,
+        ) -> Result<()> {
+            let result = build_call::<ink::env::DefaultEnvironment>()
+                .call(token)
+                .exec_input(input)
+                .returns::<core::result::Result<(), PSP22Error>>()
+                .try_invoke();
+
+            match result {
+                Ok(Ok(Ok(()))) => Ok(()),
+                _ => Err(Error::ExternalTransferFailed),
+            }
+        }
+
+        /// Proposes a trade of `amount` of this token from `seller` to `buyer`
+        /// against `asset_amount` of `asset`, and returns its ID. The caller must
+        /// be one of the two parties; neither leg is locked yet.
+        #[ink(message)]
+        pub fn create_dvp(
+            &mut self,
+            seller: AccountId,
+            buyer: AccountId,
+            amount: u128,
+            asset: AccountId,
+            asset_amount: u128,
+            deadline: Timestamp,
+        ) -> Result<u64> {
+            self.when_not_paused()?;
+
+            let caller = self.env().caller();
+            if caller != seller && caller != buyer {
+                return Err(Error::Unauthorized);
+            }
+            self.not_blacklisted(seller)?;
+            self.not_blacklisted(buyer)?;
+            if seller == buyer || amount == 0 || asset_amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if deadline <= self.env().block_timestamp() {
+                return Err(Error::InvalidExpiry);
+            }
+            if asset == self.env().account_id() || !self.env().is_contract(&asset) {
+                return Err(Error::NotAContract);
+            }
+
+            let id = self.next_dvp_id;
+            self.next_dvp_id = id.checked_add(1).ok_or(Error::Overflow)?;
+            self.dvp_trades.insert(
+                id,
+                &DvpTrade {
+                    seller,
+                    buyer,
+                    amount,
+                    asset,
+                    asset_amount,
+                    deadline,
+                    seller_funded: false,
+                    buyer_funded: false,
+                },
+            );
+
+            self.env().emit_event(DvpCreated {
+                id,
+                seller,
+                buyer,
+                amount,
+                asset,
+                asset_amount,
+                deadline,
+            });
+
+            Ok(id)
+        }
+
+        /// Locks the caller's leg of trade `id`: the seller's tokens, or the
+        /// buyer's asset pulled with `transfer_from` after an approval. Locking the
+        /// second leg settles the trade in the same call.
+        #[ink(message)]
+        pub fn fund_dvp(&mut self, id: u64) -> Result<()> {
+            self.when_not_paused()?;
+
+            let caller = self.env().caller();
+            self.not_blacklisted(caller)?;
+            let mut trade = self
+                .dvp_trades
+                .get(id)
+                .filter(|trade| {
+                    (caller == trade.seller && !trade.seller_funded)
+                        || (caller == trade.buyer && !trade.buyer_funded)
+                })
+                .ok_or(Error::DvpNotFound)?;
+            if self.env().block_timestamp() >= trade.deadline {
+                return Err(Error::DvpExpired);
+            }
+
+            if caller == trade.seller {
+                if self.spendable_balance_of(caller) < trade.amount {
+                    return Err(Error::InsufficientBalance);
+                }
+                trade.seller_funded = true;
+                self.move_balance(caller, self.env().account_id(), trade.amount)?;
+            } else {
+                trade.buyer_funded = true;
+                self.call_psp22(
+                    trade.asset,
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                        "PSP22::transfer_from"
+                    )))
+                    .push_arg(caller)
+                    .push_arg(self.env().account_id())
+                    .push_arg(trade.asset_amount)
+                    .push_arg(Vec::<u8>::new()),
+                )?;
+            }
+            self.env().emit_event(DvpFunded {
+                id,
+                account: caller,
+            });
+
+            if !(trade.seller_funded && trade.buyer_funded) {
+                self.dvp_trades.insert(id, &trade);
+                return Ok(());
+            }
+
+            let new_balance = self
+                .balance_of(trade.buyer)
+                .checked_add(trade.amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(trade.buyer, new_balance)?;
+
+            self.dvp_trades.remove(id);
+            self.move_balance(self.env().account_id(), trade.buyer, trade.amount)?;
+            self.call_psp22(
+                trade.asset,
+                ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
+                    .push_arg(trade.seller)
+                    .push_arg(trade.asset_amount)
+                    .push_arg(Vec::<u8>::new()),
+            )?;
+
+            self.env().emit_event(DvpSettled { id });
+
+            Ok(())
+        }
+
+        /// Returns the locked legs of trade `id` to their parties once the
+        /// deadline has passed without settlement. Anyone may call it.
+        #[ink(message)]
+        pub fn refund_dvp(&mut self, id: u64) -> Result<()> {
+            self.when_not_paused()?;
+
+            let trade = self.dvp_trades.get(id).ok_or(Error::DvpNotFound)?;
+            if self.env().block_timestamp() < trade.deadline {
+                return Err(Error::DvpNotExpired);
+            }
+
+            self.dvp_trades.remove(id);
+            if trade.seller_funded {
+                self.move_balance(self.env().account_id(), trade.seller, trade.amount)?;
+            }
+            if trade.buyer_funded {
+                self.call_psp22(
+                    trade.asset,
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
+                        .push_arg(trade.buyer)
+                        .push_arg(trade.asset_amount)
+                        .push_arg(Vec::<u8>::new()),
+                )?;
+            }
+
+            self.env().emit_event(DvpRefunded { id });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn dvp_trade(&self, id: u64) -> Option<DvpTrade> {
+            self.dvp_trades.get(id)
+        }
+
+        /// Locks `amount` of the caller's tokens for `to` behind `hashlock`, the
+        /// SHA-256 hash of a secret, so the lock pairs with HTLCs on other chains.
+        /// `to` receives the tokens when anyone presents the secret before
+        /// `timelock`; after that the caller can take them back.
+        #[ink(message)]
+        pub fn lock_with_hash(
+            &mut self,
+            to: AccountId,
+            amount: u128,
+            hashlock: Hash,
+            timelock: Timestamp,
+        ) -> Result<()> {
+            self.when_not_paused()?;
+
+            let sender = self.env().caller();
+            self.not_blacklisted(sender)?;
+            self.not_blacklisted(to)?;
+            if amount == 0 || sender == to {
+                return Err(Error::InvalidAmount);
+            }
+            if timelock <= self.env().block_timestamp() {
+                return Err(Error::InvalidExpiry);
+            }
+            if self.htlcs.contains(hashlock) {
+                return Err(Error::HtlcExists);
+            }
+            if self.spendable_balance_of(sender) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.move_balance(sender, self.env().account_id(), amount)?;
+            self.htlcs.insert(
+                hashlock,
+                &Htlc {
+                    sender,
+                    to,
+                    amount,
+                    timelock,
+                },
+            );
+
+            self.env().emit_event(HtlcLocked {
+                hashlock,
+                sender,
+                to,
+                amount,
+                timelock,
+            });
+
+            Ok(())
+        }
+
+        /// Pays the lock whose hashlock is the SHA-256 hash of `preimage` to its
+        /// recipient. Anyone may call it.
+        #[ink(message)]
+        pub fn claim(&mut self, preimage: Vec<u8>) -> Result<()> {
+            self.when_not_paused()?;
+
+            let hashlock = Hash::from(self.env().hash_bytes::<Sha2x256>(&preimage));
+            let htlc = self.htlcs.get(hashlock).ok_or(Error::HtlcNotFound)?;
+            if self.env().block_timestamp() >= htlc.timelock {
+                return Err(Error::HtlcExpired);
+            }
+            self.not_blacklisted(htlc.to)?;
+            let new_balance = self
+                .balance_of(htlc.to)
+                .checked_add(htlc.amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(htlc.to, new_balance)?;
+
+            self.htlcs.remove(hashlock);
+            self.move_balance(self.env().account_id(), htlc.to, htlc.amount)?;
+
+            self.env().emit_event(HtlcClaimed {
+                hashlock,
+                to: htlc.to,
+                amount: htlc.amount,
+                preimage,
+            });
+
+            Ok(())
+        }
+
+        /// Returns an expired, unclaimed lock to its sender. `refund` already
+        /// names payment refunds, hence the suffix.
+        #[ink(message)]
+        pub fn refund_htlc(&mut self, hashlock: Hash) -> Result<()> {
+            let htlc = self.htlcs.get(hashlock).ok_or(Error::HtlcNotFound)?;
+            if self.env().caller() != htlc.sender {
+                return Err(Error::Unauthorized);
+            }
+            if self.env().block_timestamp() < htlc.timelock {
+                return Err(Error::HtlcNotExpired);
+            }
+
+            self.htlcs.remove(hashlock);
+            self.move_balance(self.env().account_id(), htlc.sender, htlc.amount)?;
+
+            self.env().emit_event(HtlcRefunded {
+                hashlock,
+                sender: htlc.sender,
+                amount: htlc.amount,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn htlc(&self, hashlock: Hash) -> Option<Htlc> {
+            self.htlcs.get(hashlock)
+        }
+
+        /// Area under `curve` between `start` and `start + amount` units, i.e. the
+        /// native price of those units
+        fn curve_price(
+            curve: &BondingCurve,
+            start: u128,
+            amount: u128,
+            round_up: bool,
+        ) -> Result<Balance> {
+            let end = start
+                .checked_mul(2)
+                .and_then(|doubled| doubled.checked_add(amount))
+                .ok_or(Error::Overflow)?;
+            let area = Self::mul_div(amount, end, 2, round_up)?;
+            let scaled = curve
+                .base_price
+                .checked_mul(amount)
+                .zip(curve.slope.checked_mul(area))
+                .and_then(|(base, rise)| base.checked_add(rise))
+                .ok_or(Error::Overflow)?;
+            Self::mul_div(scaled, 1, CURVE_PRICE_SCALE, round_up)
+        }
+
+        #[ink(message)]
+        pub fn burn_from(&mut self, from: AccountId, amount: u128) -> Result<()> {
+            self.when_not_paused()?;
+
+            let caller = self.env().caller();
+            self.not_blacklisted(caller)?;
+            self.not_blacklisted(from)?;
+
+            // A spender held to certain destinations can't burn either
+            if !self.spender_destinations(from, caller).is_empty() {
+                return Err(Error::DestinationNotAllowed);
+            }
+            let debit = self.allowance_debit(from, caller, amount)?;
+
+            self._burn(from, amount)?;
+            self.spend_allowance(from, caller, debit);
+
+            Ok(())
+        }
+
+        fn _approve(&mut self, owner: AccountId, spender: AccountId, value: u128) {
+            self._approve_until(owner, spender, value, None);
+        }
+
+        fn _approve_until(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            value: u128,
+            expires_at: Option<Timestamp>,
+        ) {
+            self.allowances
+                .insert((owner, spender), &Allowance { value, expires_at });
+            self.track_allowance(owner, spender);
+
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value,
+            });
+        }
+
+        /// Checks that `spender` may move `amount` of `owner`'s tokens. The regular
+        /// allowance is used first, the periodic one when it doesn't cover `amount`.
+        fn allowance_debit(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+            amount: u128,
+        ) -> Result<AllowanceDebit> {
+            if let Some(left) = self.allowance(owner, spender).checked_sub(amount) {
+                return Ok(AllowanceDebit::Standard(left));
+            }
+            let mut periodic = self
+                .periodic_allowance(owner, spender)
+                .ok_or(Error::InsufficientAllowance)?;
+            periodic.spent = periodic
+                .spent
+                .checked_add(amount)
+                .filter(|spent| *spent <= periodic.limit)
+                .ok_or(Error::InsufficientAllowance)?;
+            Ok(AllowanceDebit::Periodic(periodic))
+        }
+
+        /// Records a debit from `allowance_debit`. A regular allowance keeps its expiry.
+        fn spend_allowance(&mut self, owner: AccountId, spender: AccountId, debit: AllowanceDebit) {
+            match debit {
+                AllowanceDebit::Standard(value) => {
+                    let mut allowance = self.allowances.get((owner, spender)).unwrap_or_default();
+                    allowance.value = value;
+                    self.allowances.insert((owner, spender), &allowance);
+                }
+                AllowanceDebit::Periodic(periodic) => {
+                    self.periodic_allowances.insert((owner, spender), &periodic);
+                }
+            }
+        }
+
+        /// Lets `spender` move up to `limit` of the caller's tokens every `period`
+        /// ms, on top of any regular allowance. The budget renews at the start of
+        /// each period without a new approval. A limit of 0 revokes it.
+        #[ink(message)]
+        pub fn approve_periodic(
+            &mut self,
+            spender: AccountId,
+            limit: u128,
+            period: Timestamp,
+        ) -> Result<()> {
+            let owner = self.env().caller();
+            if owner == spender {
+                return Err(Error::SelfApproval);
+            }
+            self.when_scope_active(PauseScope::Approvals)?;
+
+            if limit == 0 {
+                self.periodic_allowances.remove((owner, spender));
+            } else {
+                if period == 0 {
+                    return Err(Error::InvalidAmount);
+                }
+                self.not_blacklisted(owner)?;
+                self.not_blacklisted(spender)?;
+                self.periodic_allowances.insert(
+                    (owner, spender),
+                    &PeriodicAllowance {
+                        limit,
+                        period,
+                        period_start: self.env().block_timestamp(),
+                        spent: 0,
+                    },
+                );
+            }
+
+            self.env().emit_event(PeriodicApproval {
+                owner,
+                spender,
+                limit,
+                period,
+            });
+
+            Ok(())
+        }
+
+        /// Restricts `spender` to moving the caller's tokens to `destinations`
+        /// only, e.g. the landlord and the utility company. `burn_from` is refused
+        /// while a restriction is in place. An empty list lifts it.
+        #[ink(message)]
+        pub fn set_spender_destinations(
+            &mut self,
+            spender: AccountId,
+            destinations: Vec<AccountId>,
+        ) -> Result<()> {
+            let owner = self.env().caller();
+            if destinations.len() > MAX_SPENDER_DESTINATIONS {
+                return Err(Error::InvalidAmount);
+            }
+            if destinations.is_empty() {
+                self.spender_destinations.remove((owner, spender));
+            } else {
+                self.spender_destinations
+                    .insert((owner, spender), &destinations);
+            }
+
+            self.env().emit_event(SpenderDestinationsUpdated {
+                owner,
+                spender,
+                destinations,
+            });
+
+            Ok(())
+        }
+
+        /// Where `spender` may send `owner`'s tokens; empty means anywhere
+        #[ink(message)]
+        pub fn spender_destinations(&self, owner: AccountId, spender: AccountId) -> Vec<AccountId> {
+            self.spender_destinations
+                .get((owner, spender))
+                .unwrap_or_default()
+        }
+
+        /// Registers `delegate` as a session key of the caller: it can send up to
+        /// `cap` through `delegated_transfer`, only to `destinations` (anywhere if
+        /// empty), until `expires_at`. Registering again replaces the key and
+        /// resets what it spent.
+        #[ink(message)]
+        pub fn register_session_key(
+            &mut self,
+            delegate: AccountId,
+            cap: u128,
+            destinations: Vec<AccountId>,
+            expires_at: Timestamp,
+        ) -> Result<()> {
+            let holder = self.env().caller();
+            if holder == delegate {
+                return Err(Error::SelfApproval);
+            }
+            if cap == 0 || destinations.len() > MAX_SPENDER_DESTINATIONS {
+                return Err(Error::InvalidAmount);
+            }
+            if expires_at <= self.env().block_timestamp() {
+                return Err(Error::InvalidExpiry);
+            }
+            self.not_blacklisted(holder)?;
+            self.not_blacklisted(delegate)?;
+
+            self.session_keys.insert(
+                (holder, delegate),
+                &SessionKey {
+                    cap,
+                    spent: 0,
+                    destinations,
+                    expires_at,
+                },
+            );
+
+            self.env().emit_event(SessionKeyRegistered {
+                holder,
+                delegate,
+                cap,
+                expires_at,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn revoke_session_key(&mut self, delegate: AccountId) -> Result<()> {
+            let holder = self.env().caller();
+            if self.session_keys.take((holder, delegate)).is_none() {
+                return Err(Error::SessionKeyNotFound);
+            }
+
+            self.env()
+                .emit_event(SessionKeyRevoked { holder, delegate });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn session_key(&self, holder: AccountId, delegate: AccountId) -> Option<SessionKey> {
+            self.session_keys.get((holder, delegate))
+        }
+
+        /// Sends `amount` of `holder`'s tokens to `to` with the caller's session
+        /// key, within its cap, destinations and expiry
+        #[ink(message)]
+        pub fn delegated_transfer(
+            &mut self,
+            holder: AccountId,
+            to: AccountId,
+            amount: u128,
+        ) -> Result<()> {
+            self.when_not_paused()?;
+
+            let delegate = self.env().caller();
+            self.not_blacklisted(delegate)?;
+            let mut key = self
+                .session_keys
+                .get((holder, delegate))
+                .ok_or(Error::SessionKeyNotFound)?;
+            if self.env().block_timestamp() >= key.expires_at {
+                return Err(Error::SessionKeyExpired);
+            }
+            if !key.destinations.is_empty() && !key.destinations.contains(&to) {
+                return Err(Error::DestinationNotAllowed);
+            }
+            key.spent = key
+                .spent
+                .checked_add(amount)
+                .filter(|spent| *spent <= key.cap)
+                .ok_or(Error::SessionCapExceeded)?;
+
+            self._transfer(holder, to, amount, Vec::new())?;
+            self.session_keys.insert((holder, delegate), &key);
+
+            Ok(())
+        }
+
+        /// The periodic allowance as of now: a period that has run out shows up
+        /// with the current period's start and nothing spent
+        #[ink(message)]
+        pub fn periodic_allowance(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+        ) -> Option<PeriodicAllowance> {
+            let mut periodic = self.periodic_allowances.get((owner, spender))?;
+            let elapsed = self
+                .env()
+                .block_timestamp()
+                .saturating_sub(periodic.period_start);
+            if elapsed >= periodic.period {
+                periodic.period_start = periodic
+                    .period_start
+                    .saturating_add(elapsed - elapsed % periodic.period);
+                periodic.spent = 0;
+            }
+            Some(periodic)
+        }
+
+        /// Like `approve`, but the allowance counts as zero from `expires_at` on
+        #[ink(message)]
+        pub fn approve_with_expiry(
+            &mut self,
+            spender: AccountId,
+            value: u128,
+            expires_at: Timestamp,
+        ) -> Result<()> {
+            if expires_at <= self.env().block_timestamp() {
+                return Err(Error::InvalidExpiry);
+            }
+            self.approve_until(spender, value, Some(expires_at))
+        }
+
+        /// The allowance as stored, with its expiry. Unlike `allowance()` the value
+        /// is shown even once it has expired.
+        #[ink(message)]
+        pub fn allowance_details(&self, owner: AccountId, spender: AccountId) -> Allowance {
+            self.allowances.get((owner, spender)).unwrap_or_default()
+        }
+
+        fn approve_until(
+            &mut self,
+            spender: AccountId,
+            value: u128,
+            expires_at: Option<Timestamp>,
+        ) -> Result<()> {
+            let owner = self.env().caller();
+
+            if owner == spender {
+                return Err(Error::SelfApproval);
+            }
+
+            self.when_scope_active(PauseScope::Approvals)?;
+            self.not_blacklisted(owner)?;
+            self.not_blacklisted(spender)?;
+
+            self._approve_until(owner, spender, value, expires_at);
+
+            Ok(())
+        }
+
+        /// Expiry an allowance change keeps: the current one while it hasn't
+        /// lapsed, none otherwise
+        fn live_expiry(&self, owner: AccountId, spender: AccountId) -> Option<Timestamp> {
+            self.allowance_details(owner, spender)
+                .expires_at
+                .filter(|expires_at| self.env().block_timestamp() < *expires_at)
+        }
+
+        /// Sets an allowance from an ECDSA signature by `owner` over `permit_hash`
+        #[ink(message)]
+        pub fn permit(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            value: u128,
+            deadline: Timestamp,
+            signature: [u8; 65],
+        ) -> Result<()> {
+            if self.env().block_timestamp() > deadline {
+                return Err(Error::SignatureExpired);
+            }
+
+            let message_hash = self.permit_hash(owner, spender, value, deadline);
+            self.verify_ecdsa(owner, &message_hash, &signature)?;
+
+            self.apply_permit(owner, spender, value)?;
+            self.use_nonce(owner)
+        }
+
+        /// Sets an allowance from an sr25519 signature by `owner`.
+        ///
+        /// The signed message is the 32-byte `permit_hash`, either raw or wrapped in
+        /// `<Bytes>...</Bytes>` as produced by polkadot-js `signRaw`.
+        #[ink(message)]
+        pub fn permit_sr25519(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            value: u128,
+            deadline: Timestamp,
+            signature: [u8; 64],
+        ) -> Result<()> {
+            if self.env().block_timestamp() > deadline {
+                return Err(Error::SignatureExpired);
+            }
+
+            let message_hash = self.permit_hash(owner, spender, value, deadline);
+            self.verify_sr25519(owner, &message_hash, &signature)?;
+
+            self.apply_permit(owner, spender, value)?;
+            self.use_nonce(owner)
+        }
+
+        fn apply_permit(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            value: u128,
+        ) -> Result<()> {
+            if owner == spender {
+                return Err(Error::SelfApproval);
+            }
+
+            self.when_scope_active(PauseScope::Approvals)?;
+            self.not_blacklisted(owner)?;
+            self.not_blacklisted(spender)?;
+
+            self._approve(owner, spender, value);
+
+            Ok(())
+        }
+
+        fn permit_hash(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+            value: u128,
+            deadline: Timestamp,
+        ) -> [u8; 32] {
+            self.env().hash_encoded::<Blake2x256, _>(&(
+                b"PSP22Permit",
+                self.env().account_id(),
+                owner,
+                spender,
+                value,
+                self.nonce_of(owner),
+                deadline,
+            ))
+        }
+
+        /// Next nonce expected in `account`'s signed operations
+        #[ink(message)]
+        pub fn nonce_of(&self, account: AccountId) -> u64 {
+            self.nonces.get(account).unwrap_or(0)
+        }
+
+        fn use_nonce(&mut self, account: AccountId) -> Result<()> {
+            let next = self
+                .nonce_of(account)
+                .checked_add(1)
+                .ok_or(Error::Overflow)?;
+            self.nonces.insert(account, &next);
+            Ok(())
+        }
+
+        fn verify_signature(
+            &self,
+            signer: AccountId,
+            message_hash: &[u8; 32],
+            signature: &Signature,
+        ) -> Result<()> {
+            match signature {
+                Signature::Ecdsa(signature) => self.verify_ecdsa(signer, message_hash, signature),
+                Signature::Sr25519(signature) => {
+                    self.verify_sr25519(signer, message_hash, signature)
+                }
+            }
+        }
+
+        /// EIP-3009 style transfer signed by `from` and submitted by any relayer
+        #[ink(message)]
+        #[allow(clippy::too_many_arguments)]
+        pub fn transfer_with_authorization(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: u128,
+            valid_after: Timestamp,
+            valid_before: Timestamp,
+            nonce: u64,
+            signature: Signature,
+        ) -> Result<()> {
+            self.when_not_paused()?;
+
+            let now = self.env().block_timestamp();
+            if now <= valid_after {
+                return Err(Error::AuthorizationNotYetValid);
+            }
+            if now >= valid_before {
+                return Err(Error::SignatureExpired);
+            }
+            if nonce != self.nonce_of(from) {
+                return Err(Error::InvalidNonce);
+            }
+
+            let message_hash =
+                self.transfer_authorization_hash(from, to, value, valid_after, valid_before);
+            self.verify_signature(from, &message_hash, &signature)?;
+
+            self.not_blacklisted(from)?;
+            self.not_blacklisted(to)?;
+
+            let relayer = self.env().caller();
+            self.before_received(relayer, from, to, value, &Vec::new())?;
+            self._transfer(from, to, value, Vec::new())?;
+            self.use_nonce(from)?;
+
+            self.env().emit_event(AuthorizationUsed {
+                authorizer: from,
+                nonce,
+            });
+
+            Ok(())
+        }
+
+        /// Cashes a cheque signed by `payer` over `cheque_hash`. A cheque made out
+        /// to a `payee` can only be cashed by that account; a bearer cheque
+        /// (`None`) pays whoever presents it. Cheque nonces are chosen by the
+        /// payer, so any number can be outstanding at once.
+        #[ink(message)]
+        pub fn cash_cheque(
+            &mut self,
+            payer: AccountId,
+            payee: Option<AccountId>,
+            amount: u128,
+            expiry: Timestamp,
+            nonce: u64,
+            signature: Signature,
+        ) -> Result<()> {
+            self.when_not_paused()?;
+
+            let presenter = self.env().caller();
+            if payee.is_some_and(|payee| payee != presenter) {
+                return Err(Error::Unauthorized);
+            }
+            if self.env().block_timestamp() >= expiry {
+                return Err(Error::SignatureExpired);
+            }
+            if self.is_cheque_spent(payer, nonce) {
+                return Err(Error::ChequeSpent);
+            }
+
+            let message_hash = self.cheque_hash(payer, payee, amount, expiry, nonce);
+            self.verify_signature(payer, &message_hash, &signature)?;
+
+            self.not_blacklisted(payer)?;
+            self.not_blacklisted(presenter)?;
+
+            self.before_received(presenter, payer, presenter, amount, &Vec::new())?;
+            self._transfer(payer, presenter, amount, Vec::new())?;
+            self.spent_cheques.insert((payer, nonce), &true);
+
+            self.env().emit_event(ChequeCashed {
+                payer,
+                payee: presenter,
+                nonce,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Stops payment on one of the caller's cheques before it is cashed
+        #[ink(message)]
+        pub fn cancel_cheque(&mut self, nonce: u64) -> Result<()> {
+            let payer = self.env().caller();
+            if self.is_cheque_spent(payer, nonce) {
+                return Err(Error::ChequeSpent);
+            }
+
+            self.spent_cheques.insert((payer, nonce), &true);
+
+            self.env().emit_event(ChequeCancelled { payer, nonce });
+
+            Ok(())
+        }
+
+        /// Whether cheque `nonce` of `payer` has been cashed or cancelled
+        #[ink(message)]
+        pub fn is_cheque_spent(&self, payer: AccountId, nonce: u64) -> bool {
+            self.spent_cheques.contains((payer, nonce))
+        }
+
+        fn cheque_hash(
+            &self,
+            payer: AccountId,
+            payee: Option<AccountId>,
+            amount: u128,
+            expiry: Timestamp,
+            nonce: u64,
+        ) -> [u8; 32] {
+            self.env().hash_encoded::<Blake2x256, _>(&(
+                b"PSP22Cheque",
+                self.env().account_id(),
+                payer,
+                payee,
+                amount,
+                expiry,
+                nonce,
+            ))
+        }
+
+        /// Executes `call` as `signer` and pays `fee` tokens from `signer` to the relayer
+        #[ink(message)]
+        pub fn execute_meta_tx(
+            &mut self,
+            signer: AccountId,
+            call: MetaCall,
+            fee: u128,
+            deadline: Timestamp,
+            signature: Signature,
+        ) -> Result<()> {
+            if self.env().block_timestamp() > deadline {
+                return Err(Error::SignatureExpired);
+            }
+
+            let nonce = self.nonce_of(signer);
+            let message_hash = self.meta_tx_hash(signer, &call, fee, deadline);
+            self.verify_signature(signer, &message_hash, &signature)?;
+            self.not_blacklisted(signer)?;
+
+            let relayer = self.env().caller();
+            self.execute_call(signer, relayer, call)?;
+
+            if fee > 0 {
+                self.when_not_paused()?;
+                self.not_blacklisted(relayer)?;
+                self._transfer(signer, relayer, fee, Vec::new())?;
+            }
+
+            self.use_nonce(signer)?;
+
+            self.env().emit_event(MetaTxExecuted {
+                signer,
+                relayer,
+                nonce,
+                fee,
+            });
+
+            Ok(())
+        }
+
+        /// Runs `call` on behalf of `account`, with `operator` as the submitting account
+        fn execute_call(
+            &mut self,
+            account: AccountId,
+            operator: AccountId,
+            call: MetaCall,
+        ) -> Result<()> {
+            match call {
+                MetaCall::Transfer { to, amount } => {
+                    self.when_not_paused()?;
+                    self.not_blacklisted(to)?;
+                    self.before_received(operator, account, to, amount, &Vec::new())?;
+                    self._transfer(account, to, amount, Vec::new())
+                }
+                MetaCall::Approve { spender, amount } => {
+                    self.apply_permit(account, spender, amount)
+                }
+                MetaCall::Burn { amount } => {
+                    self.when_not_paused()?;
+                    self._burn(account, amount)
+                }
+            }
+        }
+
+        /// Executes all `calls` as the caller. Returns `Err` with every call's outcome
+        /// (reverting all of them) if any call fails.
+        #[ink(message)]
+        pub fn multicall(
+            &mut self,
+            calls: Vec<MetaCall>,
+        ) -> core::result::Result<Vec<Result<()>>, Vec<Result<()>>> {
+            let caller = self.env().caller();
+            if let Err(error) = self.not_blacklisted(caller) {
+                return Err(ink::prelude::vec![Err(error)]);
+            }
+
+            let mut failed = false;
+            let mut results = Vec::with_capacity(calls.len());
+            for call in calls {
+                let result = self.execute_call(caller, caller, call);
+                failed |= result.is_err();
+                results.push(result);
+            }
+
+            if failed {
+                return Err(results);
+            }
+            Ok(results)
+        }
+
+        fn meta_tx_hash(
+            &self,
+            signer: AccountId,
+            call: &MetaCall,
+            fee: u128,
+            deadline: Timestamp,
+        ) -> [u8; 32] {
+            self.env().hash_encoded::<Blake2x256, _>(&(
+                b"PSP22MetaTx",
+                self.env().account_id(),
+                signer,
+                call,
+                fee,
+                self.nonce_of(signer),
+                deadline,
+            ))
+        }
+
+        fn transfer_authorization_hash(
+            &self,
+            from: AccountId,
+            to: AccountId,
+            value: u128,
+            valid_after: Timestamp,
+            valid_before: Timestamp,
+        ) -> [u8; 32] {
+            self.env().hash_encoded::<Blake2x256, _>(&(
+                b"PSP22TransferWithAuthorization",
+                self.env().account_id(),
+                from,
+                to,
+                value,
+                valid_after,
+                valid_before,
+                self.nonce_of(from),
+            ))
+        }
+
+        /// ECDSA accounts are the blake2 hash of the compressed public key
+        fn verify_ecdsa(
+            &self,
+            signer: AccountId,
+            message_hash: &[u8; 32],
+            signature: &[u8; 65],
+        ) -> Result<()> {
+            let public_key = self
+                .env()
+                .ecdsa_recover(signature, message_hash)
+                .map_err(|_| Error::InvalidSignature)?;
+            let recovered = AccountId::from(self.env().hash_bytes::<Blake2x256>(&public_key));
+
+            if recovered != signer {
+                return Err(Error::InvalidSignature);
+            }
+            Ok(())
+        }
+
+        /// sr25519 accounts are the public key itself
+        fn verify_sr25519(
+            &self,
+            signer: AccountId,
+            message_hash: &[u8; 32],
+            signature: &[u8; 64],
+        ) -> Result<()> {
+            let public_key: &[u8; 32] = signer.as_ref();
+
+            if self
+                .env()
+                .sr25519_verify(signature, message_hash, public_key)
+                .is_ok()
+            {
+                return Ok(());
+            }
+
+            let mut wrapped = Vec::with_capacity(message_hash.len() + 15);
+            wrapped.extend_from_slice(b"<Bytes>");
+            wrapped.extend_from_slice(message_hash);
+            wrapped.extend_from_slice(b"</Bytes>");
+
+            self.env()
+                .sr25519_verify(signature, &wrapped, public_key)
+                .map_err(|_| Error::InvalidSignature)
+        }
+
+        /// Approves `spender` and notifies it via `PSP22ApprovalReceiver::on_approval_received`,
+        /// which may pull the funds with `transfer_from` in the same transaction
+        #[ink(message)]
+        pub fn approve_and_call(
+            &mut self,
+            spender: AccountId,
+            amount: u128,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            if !self.env().is_contract(&spender) {
+                return Err(Error::NotAContract);
+            }
+
+            let owner = self.env().caller();
+            self.apply_permit(owner, spender, amount)?;
+
+            self.call_with_reentry(
+                spender,
+                ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                    "PSP22ApprovalReceiver::on_approval_received"
+                )))
+                .push_arg(owner)
+                .push_arg(amount)
+                .push_arg(data),
+            )
+        }
+
+        /// Calls a receiver contract that is allowed to re-enter this one. Root storage is
+        /// only written back when a message returns, so flush it before the call and reload
+        /// it afterwards to keep both sides consistent.
+        fn call_with_reentry<Args: ink::scale::Encode>(
+            &mut self,
+            callee: AccountId,
+            input: ExecutionInput<Args>,
+        ) -> Result<()> {
+            let key = <Self as ink::storage::traits::StorageKey>::KEY;
+            ink::env::set_contract_storage(&key, self);
+
+            let result = build_call::<ink::env::DefaultEnvironment>()
+                .call(callee)
+                .call_flags(CallFlags::ALLOW_REENTRY)
+                .exec_input(input)
+                .returns::<core::result::Result<(), PSP22ReceiverError>>()
+                .try_invoke();
+
+            if let Ok(Some(state)) = ink::env::get_contract_storage::<_, Self>(&key) {
+                *self = state;
+            }
+
+            match result {
+                Ok(Ok(Ok(()))) => Ok(()),
+                _ => Err(Error::CallbackFailed),
+            }
+        }
+
+        #[ink(message)]
+        pub fn batch_approve(&mut self, approvals: Vec<(AccountId, u128)>) -> Result<()> {
+            let owner = self.env().caller();
+            self.when_scope_active(PauseScope::Approvals)?;
+            self.not_blacklisted(owner)?;
+
+            for (spender, _) in &approvals {
+                if *spender == owner {
+                    return Err(Error::SelfApproval);
+                }
+                self.not_blacklisted(*spender)?;
+            }
+
+            for (spender, value) in approvals {
+                self._approve(owner, spender, value);
+            }
+
+            Ok(())
+        }
+
+        fn _burn(&mut self, from: AccountId, amount: u128) -> Result<()> {
+            self.when_scope_active(PauseScope::Burning)?;
+            self.settle_demurrage(from);
+            let balance = self.balance_of(from);
+
+            if self.spendable_balance_of(from) < amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            let new_balance = balance.checked_sub(amount).ok_or(Error::Overflow)?;
+            let new_supply = self
+                .total_supply
+                .checked_sub(amount)
+                .ok_or(Error::Overflow)?;
+
+            self.write_balance(from, new_balance);
+            self.total_supply = new_supply;
+
+            self.env().emit_event(Transfer {
+                from: Some(from),
+                to: None,
+                value: amount,
+                data: Vec::new(),
+            });
+
+            Ok(())
+        }
+
+        /// Transfer with an arbitrary payload (e.g. payment reference) emitted in the Transfer event
+        #[ink(message)]
+        pub fn transfer_with_data(
+            &mut self,
+            to: AccountId,
+            amount: u128,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            self.when_not_paused()?;
+
+            let from = self.env().caller();
+            self.not_blacklisted(from)?;
+            self.not_blacklisted(to)?;
+
+            self.before_received(from, from, to, amount, &data)?;
+            self._transfer(from, to, amount, data)
+        }
+
+        /// Pays `to` against `payment_id`. The memo goes into the Transfer event's `data`
+        /// and is kept with the payment, queryable through `payment_of`.
+        #[ink(message)]
+        pub fn transfer_with_memo(
+            &mut self,
+            to: AccountId,
+            amount: u128,
+            payment_id: Hash,
+            memo: Option<Vec<u8>>,
+        ) -> Result<()> {
+            Self::check_memo(&memo)?;
+            let from = self.env().caller();
+            self.transfer_with_data(to, amount, memo.clone().unwrap_or_default())?;
+            self.record_payment(payment_id, from, to, amount, memo);
+            Ok(())
+        }
+
+        /// Allowance-based `transfer_with_memo`
+        #[ink(message)]
+        pub fn transfer_from_with_memo(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            amount: u128,
+            payment_id: Hash,
+            memo: Option<Vec<u8>>,
+        ) -> Result<()> {
+            Self::check_memo(&memo)?;
+            self.transfer_from_with_data(from, to, amount, memo.clone().unwrap_or_default())?;
+            self.record_payment(payment_id, from, to, amount, memo);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn payment_of(&self, payment_id: Hash) -> Option<PaymentRecord> {
+            self.payments.get(payment_id)
+        }
+
+        /// Returns `amount` of payment `payment_id` to its payer. Only the payee
+        /// of the recorded payment may refund it, and refunds can't add up to more
+        /// than the payment.
+        #[ink(message)]
+        pub fn refund(&mut self, payment_id: Hash, amount: u128) -> Result<()> {
+            let payee = self.env().caller();
+            let mut record = self
+                .payments
+                .get(payment_id)
+                .filter(|record| record.to == payee)
+                .ok_or(Error::PaymentNotFound)?;
+            let total_refunded = record
+                .refunded
+                .checked_add(amount)
+                .filter(|refunded| *refunded <= record.amount)
+                .ok_or(Error::RefundExceedsPayment)?;
+
+            self.transfer_with_data(record.from, amount, payment_id.as_ref().to_vec())?;
+            record.refunded = total_refunded;
+            self.payments.insert(payment_id, &record);
+
+            self.env().emit_event(PaymentRefunded {
+                payment_id,
+                from: payee,
+                to: record.from,
+                amount,
+                total_refunded,
+            });
+
+            Ok(())
+        }
+
+        fn check_memo(memo: &Option<Vec<u8>>) -> Result<()> {
+            if memo.as_ref().is_some_and(|memo| memo.len() > MAX_MEMO_LEN) {
+                return Err(Error::MemoTooLong);
+            }
+            Ok(())
+        }
+
+        fn record_payment(
+            &mut self,
+            payment_id: Hash,
+            from: AccountId,
+            to: AccountId,
+            amount: u128,
+            memo: Option<Vec<u8>>,
+        ) {
+            self.payments.insert(
+                payment_id,
+                &PaymentRecord {
+                    from,
+                    to,
+                    amount,
+                    memo: memo.clone(),
+                    timestamp: self.env().block_timestamp(),
+                    refunded: 0,
+                },
+            );
+
+            self.env().emit_event(PaymentRecorded {
+                payment_id,
+                from,
+                to,
+                amount,
+                memo,
+            });
+        }
+
+        /// Transfers to a contract and then calls `selector` on it with `(from, amount, data)`.
+        /// The called message must return `Result<(), PSP22ReceiverError>`; on error the
+        /// transfer is reverted. It replaces the `before_received` hook for this transfer.
+        #[ink(message)]
+        pub fn transfer_and_call(
+            &mut self,
+            to: AccountId,
+            amount: u128,
+            selector: [u8; 4],
+            data: Vec<u8>,
+        ) -> Result<()> {
+            self.when_not_paused()?;
+
+            if !self.env().is_contract(&to) {
+                return Err(Error::NotAContract);
+            }
+
+            let from = self.env().caller();
+            self.not_blacklisted(from)?;
+            self.not_blacklisted(to)?;
+
+            self._transfer(from, to, amount, Vec::new())?;
+
+            self.call_with_reentry(
+                to,
+                ExecutionInput::new(Selector::new(selector))
+                    .push_arg(from)
+                    .push_arg(amount)
+                    .push_arg(data),
+            )
+        }
+
+        /// Moves the caller's entire balance, read within the same call
+        #[ink(message)]
+        pub fn transfer_all(&mut self, to: AccountId) -> Result<()> {
+            let amount = self.spendable_balance_of(self.env().caller());
+            self.transfer_with_data(to, amount, Vec::new())
+        }
+
+        fn _transfer(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            amount: u128,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            self.settle_demurrage(from);
+            self.settle_demurrage(to);
+            if let Some(collector) = self.fee_collector {
+                self.settle_demurrage(collector);
+            }
+            let amount = self.with_swept_dust(from, amount);
+            let fee = self.check_transfer(from, to, amount, &data)?;
+            let levy = self.insurance_levy_for(from, to, amount);
+            let net = amount
+                .checked_sub(fee)
+                .and_then(|net| net.checked_sub(levy))
+                .ok_or(Error::Overflow)?;
+            let overdraft = self.overdraft_needed(from, amount)?;
+            if overdraft > 0 {
+                self.draw_overdraft(from, overdraft)?;
+            }
+
+            let new_from_balance = self
+                .balance_of(from)
+                .checked_sub(amount)
+                .ok_or(Error::Overflow)?;
+            let new_to_balance = self
+                .balance_of(to)
+                .checked_add(net)
+                .ok_or(Error::Overflow)?;
+            let collector_balance = match self.fee_collector {
+                Some(collector) if fee > 0 => Some((
+                    collector,
+                    self.balance_of(collector)
+                        .checked_add(fee)
+                        .ok_or(Error::Overflow)?,
+                )),
+                _ => None,
+            };
+
+            self.write_balance(from, new_from_balance);
+            self.write_balance(to, new_to_balance);
+
+            self.env().emit_event(Transfer {
+                from: Some(from),
+                to: Some(to),
+                value: net,
+                data,
+            });
+
+            if let Some((collector, new_collector_balance)) = collector_balance {
+                self.write_balance(collector, new_collector_balance);
+
+                self.env().emit_event(Transfer {
+                    from: Some(from),
+                    to: Some(collector),
+                    value: fee,
+                    data: Vec::new(),
+                });
+                self.env().emit_event(FeeCharged {
+                    from,
+                    to,
+                    gross: amount,
+                    net,
+                    fee,
+                });
+            }
+            if levy > 0 {
+                self.collect_insurance_levy(from, levy)?;
+            }
+
+            self.record_credit_event(from, |history| {
+                history.transfers = history.transfers.saturating_add(1)
+            });
+            self.open_credit_history(to);
+            self.record_activity(from);
+            self.record_volume(0, amount);
+            self.accrue_cashback(from, to, amount);
+            self.record_referral(from, amount)?;
+            self.record_loyalty_volume(from, amount);
+
+            Ok(())
+        }
+
+        /// Policy and balance checks for moving `amount` from `from` to `to`; returns the fee
+        fn check_transfer(
+            &self,
+            from: AccountId,
+            to: AccountId,
+            amount: u128,
+            data: &[u8],
+        ) -> Result<u128> {
+            self.when_scope_active(PauseScope::Transfers)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if self.max_transfer_amount.is_some_and(|max| amount > max) {
+                return Err(Error::TransferAmountExceeded);
+            }
+            if self.whitelist_mode && !(self.is_whitelisted(from) && self.is_whitelisted(to)) {
+                return Err(Error::NotWhitelisted);
+            }
+            if self.is_account_locked(from) {
+                return Err(Error::AccountLocked);
+            }
+            if data.is_empty() && self.travel_rule_threshold.is_some_and(|min| amount > min) {
+                return Err(Error::TravelRuleMemoRequired);
+            }
+
+            self.overdraft_needed(from, amount)?;
+
+            let fee = self.transfer_fee(from, to, amount);
+            let net = amount.checked_sub(fee).ok_or(Error::Overflow)?;
+            let new_to_balance = self
+                .balance_of(to)
+                .checked_add(net)
+                .ok_or(Error::Overflow)?;
+
+            if self
+                .limits_of(from)
+                .max_transfer
+                .is_some_and(|max| amount > max)
+            {
+                return Err(Error::TierLimitExceeded);
+            }
+            self.within_balance_limit(to, new_to_balance)?;
+            let custody = self.env().account_id();
+            if self.min_balance > 0 && from != custody && to != custody {
+                let remainder = self.balance_of(from).saturating_sub(amount);
+                if new_to_balance < self.min_balance
+                    || (!self.sweep_dust && remainder > 0 && remainder < self.min_balance)
+                {
+                    return Err(Error::BelowMinimumBalance);
+                }
+            }
+
+            Ok(fee)
+        }
+
+        /// What `transfer(to, amount)` from `from` would do, without executing it.
+        /// Quotes a transfer without `data`, so the travel rule applies
+        #[ink(message)]
+        pub fn quote_transfer(
+            &self,
+            from: AccountId,
+            to: AccountId,
+            amount: u128,
+        ) -> TransferQuote {
+            let fee = self.transfer_fee(from, to, amount);
+            let outcome = self
+                .when_not_paused()
+                .and_then(|_| self.not_blacklisted(from))
+                .and_then(|_| self.not_blacklisted(to))
+                .and_then(|_| self.check_transfer(from, to, amount, &[]))
+                .map(|_| ());
+
+            TransferQuote {
+                fee,
+                net: amount
+                    .saturating_sub(fee)
+                    .saturating_sub(self.insurance_levy_for(from, to, amount)),
+                outcome,
+            }
+        }
+
+        /// Allowance-based transfer carrying a payload in the Transfer event
+        #[ink(message)]
+        pub fn transfer_from_with_data(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            amount: u128,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            self.when_not_paused()?;
+
+            let caller = self.env().caller();
+            self.not_blacklisted(from)?;
+            self.not_blacklisted(to)?;
+            self.not_blacklisted(caller)?;
+
+            let destinations = self.spender_destinations(from, caller);
+            if !destinations.is_empty() && !destinations.contains(&to) {
+                return Err(Error::DestinationNotAllowed);
+            }
+            let debit = self.allowance_debit(from, caller, amount)?;
+
+            self.before_received(caller, from, to, amount, &data)?;
+            self._transfer(from, to, amount, data)?;
+            self.spend_allowance(from, caller, debit);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn batch_transfer(&mut self, recipients: Vec<(AccountId, u128)>) -> Result<()> {
+            self.when_not_paused()?;
+
+            let from = self.env().caller();
+            self.not_blacklisted(from)?;
+
+            let mut total_amount: u128 = 0;
+            for (to, amount) in &recipients {
+                self.not_blacklisted(*to)?;
+                total_amount = total_amount.checked_add(*amount).ok_or(Error::Overflow)?;
+            }
+
+            let from_balance = self.balance_of(from);
+            if from_balance < total_amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            for (to, amount) in recipients {
+                if amount > 0 {
+                    self._transfer(from, to, amount, Vec::new())?;
+                }
+            }
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn batch_mint(&mut self, recipients: Vec<(AccountId, u128)>) -> Result<()> {
+            self.only_owner()?;
+
+            let mut total_amount: u128 = 0;
+            for (to, amount) in &recipients {
+                self.not_blacklisted(*to)?;
+                total_amount = total_amount.checked_add(*amount).ok_or(Error::Overflow)?;
+            }
+
+            // Every recipient balance is bounded by total supply, so this covers them all
+            let new_supply = self
+                .total_supply
+                .checked_add(total_amount)
+                .ok_or(Error::Overflow)?;
+            self.within_cap(new_supply)?;
+
+            let count = recipients.len() as u32;
+            for (to, amount) in recipients {
+                if amount > 0 {
+                    self._mint(to, amount)?;
+                }
+            }
+
+            self.audit(AuditOperation::Mint, &(count, total_amount));
+            self.env().emit_event(BatchMinted {
+                recipients: count,
+                total_amount,
+            });
+
+            Ok(())
+        }
+
+        /// ERC-20 style `transfer` returning `false` instead of an error
+        #[ink(message)]
+        pub fn erc20_transfer(&mut self, to: AccountId, value: u128) -> bool {
+            self.transfer_with_data(to, value, Vec::new()).is_ok()
+        }
+
+        /// ERC-20 style `transferFrom` returning `false` instead of an error
+        #[ink(message)]
+        pub fn erc20_transfer_from(&mut self, from: AccountId, to: AccountId, value: u128) -> bool {
+            self.transfer_from_with_data(from, to, value, Vec::new())
+                .is_ok()
+        }
+
+        /// ERC-20 style `approve` returning `false` instead of an error
+        #[ink(message)]
+        pub fn erc20_approve(&mut self, spender: AccountId, value: u128) -> bool {
+            self.approve(spender, value).is_ok()
+        }
+
+        /// Callable by the owner, or by the guardian even while the multisig is enabled
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            if self.guardian == Some(caller) && !self.renounced {
+                self.set_paused(true);
+                return Ok(());
+            }
+
+            self.only_owner_direct()?;
+            self.set_paused(true);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<()> {
+            self.only_owner_direct()?;
+            self.set_paused(false);
+            Ok(())
+        }
+
+        fn set_paused(&mut self, paused: bool) {
+            if self.paused == paused {
+                return;
+            }
+
+            self.paused = paused;
+            if !paused {
+                // Start a fresh breaker window so the next operation doesn't trip it again
+                self.window_start = self.env().block_timestamp();
+                self.window_minted = 0;
+                self.window_transferred = 0;
+            }
+
+            let operation = if paused {
+                AuditOperation::Pause
+            } else {
+                AuditOperation::Unpause
+            };
+            self.audit(operation, &());
+            self.env().emit_event(Paused { paused });
+        }
+
+        /// Configures the circuit breaker: once more than `mint_limit` is minted or
+        /// `transfer_limit` transferred within `window` milliseconds, the contract pauses
+        /// itself. A limit of 0 disables that check, a window of 0 the breaker.
+        #[ink(message)]
+        pub fn set_circuit_breaker(
+            &mut self,
+            window: Timestamp,
+            mint_limit: u128,
+            transfer_limit: u128,
+        ) -> Result<()> {
+            self.only_owner()?;
+
+            self.breaker_window = window;
+            self.breaker_mint_limit = mint_limit;
+            self.breaker_transfer_limit = transfer_limit;
+            self.window_start = self.env().block_timestamp();
+            self.window_minted = 0;
+            self.window_transferred = 0;
+
+            self.audit(
+                AuditOperation::Config,
+                &(window, mint_limit, transfer_limit),
+            );
+            self.env().emit_event(CircuitBreakerUpdated {
+                window,
+                mint_limit,
+                transfer_limit,
+            });
+
+            Ok(())
+        }
+
+        /// `(window, mint_limit, transfer_limit)`
+        #[ink(message)]
+        pub fn circuit_breaker(&self) -> (Timestamp, u128, u128) {
+            (
+                self.breaker_window,
+                self.breaker_mint_limit,
+                self.breaker_transfer_limit,
+            )
+        }
+
+        /// `(minted, transferred)` so far in the current breaker window
+        #[ink(message)]
+        pub fn window_volume(&self) -> (u128, u128) {
+            (self.window_minted, self.window_transferred)
+        }
+
+        /// Adds to the current window's volume and trips the breaker when a threshold
+        /// is crossed. The operation that crosses it still completes: returning an
+        /// error would revert the pause along with it.
+        fn record_volume(&mut self, minted: u128, transferred: u128) {
+            if self.breaker_window == 0 {
+                return;
+            }
+
+            let now = self.env().block_timestamp();
+            if now >= self.window_start.saturating_add(self.breaker_window) {
+                self.window_start = now;
+                self.window_minted = 0;
+                self.window_transferred = 0;
+            }
+
+            self.window_minted = self.window_minted.saturating_add(minted);
+            self.window_transferred = self.window_transferred.saturating_add(transferred);
+
+            let mint_tripped =
+                self.breaker_mint_limit > 0 && self.window_minted > self.breaker_mint_limit;
+            let transfer_tripped = self.breaker_transfer_limit > 0
+                && self.window_transferred > self.breaker_transfer_limit;
+            if !(mint_tripped || transfer_tripped) || self.paused {
+                return;
+            }
+
+            self.set_paused(true);
+            self.set_scope_paused(PauseScope::Minting, true);
+
+            self.env().emit_event(CircuitBreaker {
+                window_start: self.window_start,
+                minted: self.window_minted,
+                transferred: self.window_transferred,
+            });
+        }
+
+        #[ink(message)]
+        pub fn is_paused(&self) -> bool {
+            self.paused
+        }
+
+        #[ink(message)]
+        pub fn pause_minting(&mut self) -> Result<()> {
+            self.only_owner_direct()?;
+            self.set_scope_paused(PauseScope::Minting, true);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unpause_minting(&mut self) -> Result<()> {
+            self.only_owner_direct()?;
+            self.set_scope_paused(PauseScope::Minting, false);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn pause_burning(&mut self) -> Result<()> {
+            self.only_owner_direct()?;
+            self.set_scope_paused(PauseScope::Burning, true);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unpause_burning(&mut self) -> Result<()> {
+            self.only_owner_direct()?;
+            self.set_scope_paused(PauseScope::Burning, false);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn pause_transfers(&mut self) -> Result<()> {
+            self.only_owner_direct()?;
+            self.set_scope_paused(PauseScope::Transfers, true);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unpause_transfers(&mut self) -> Result<()> {
+            self.only_owner_direct()?;
+            self.set_scope_paused(PauseScope::Transfers, false);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn pause_approvals(&mut self) -> Result<()> {
+            self.only_owner_direct()?;
+            self.set_scope_paused(PauseScope::Approvals, true);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unpause_approvals(&mut self) -> Result<()> {
+            self.only_owner_direct()?;
+            self.set_scope_paused(PauseScope::Approvals, false);
+            Ok(())
+        }
+
+        /// Whether `scope` is switched off on its own; the global `pause()` is
+        /// reported separately by `is_paused()`
+        #[ink(message)]
+        pub fn is_scope_paused(&self, scope: PauseScope) -> bool {
+            match scope {
+                PauseScope::Minting => self.minting_paused,
+                PauseScope::Burning => self.burning_paused,
+                PauseScope::Transfers => self.transfers_paused,
+                PauseScope::Approvals => self.approvals_paused,
+            }
+        }
+
+        fn set_scope_paused(&mut self, scope: PauseScope, paused: bool) {
+            if self.is_scope_paused(scope) == paused {
+                return;
+            }
+
+            match scope {
+                PauseScope::Minting => self.minting_paused = paused,
+                PauseScope::Burning => self.burning_paused = paused,
+                PauseScope::Transfers => self.transfers_paused = paused,
+                PauseScope::Approvals => self.approvals_paused = paused,
+            }
+
+            let operation = if paused {
+                AuditOperation::Pause
+            } else {
+                AuditOperation::Unpause
+            };
+            self.audit(operation, &scope);
+            self.env().emit_event(ScopePaused { scope, paused });
+        }
+
+        /// Limits the size of any single transfer; `None` removes the limit
+        #[ink(message)]
+        pub fn set_max_transfer_amount(&mut self, max_transfer_amount: Option<u128>) -> Result<()> {
+            self.only_owner()?;
+
+            self.max_transfer_amount = max_transfer_amount;
+
+            self.audit(AuditOperation::Config, &max_transfer_amount);
+            self.env().emit_event(MaxTransferAmountUpdated {
+                max_transfer_amount,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn max_transfer_amount(&self) -> Option<u128> {
+            self.max_transfer_amount
+        }
+
+        /// Sets the transfer fee, in basis points of the transferred amount
+        #[ink(message)]
+        pub fn set_fee_bps(&mut self, fee_bps: u16) -> Result<()> {
+            self.only_owner()?;
+            if fee_bps > MAX_FEE_BPS {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.fee_bps = fee_bps;
+            self.fee_config_updated();
+            Ok(())
+        }
+
+        /// Sets the account receiving transfer fees; no fees are charged without one
+        #[ink(message)]
+        pub fn set_fee_collector(&mut self, fee_collector: Option<AccountId>) -> Result<()> {
+            self.only_owner()?;
+
+            self.fee_collector = fee_collector;
+            self.fee_config_updated();
+            Ok(())
+        }
+
+        fn fee_config_updated(&mut self) {
+            let fee_bps = self.fee_bps;
+            let fee_collector = self.fee_collector;
+
+            self.audit(AuditOperation::Config, &(fee_bps, fee_collector));
+            self.env().emit_event(FeeConfigUpdated {
+                fee_bps,
+                fee_collector,
+            });
+        }
+
+        #[ink(message)]
+        pub fn fee_bps(&self) -> u16 {
+            self.fee_bps
+        }
+
+        #[ink(message)]
+        pub fn fee_collector(&self) -> Option<AccountId> {
+            self.fee_collector
+        }
+
+        /// Replaces the flat `fee_bps` with brackets by transfer size. Brackets must be
+        /// sorted by strictly increasing `min_amount`; amounts below the first bracket
+        /// pay no fee. An empty schedule restores the flat fee.
+        #[ink(message)]
+        pub fn set_fee_schedule(&mut self, brackets: Vec<FeeBracket>) -> Result<()> {
+            self.only_owner()?;
+
+            if brackets.len() > MAX_FEE_BRACKETS {
+                return Err(Error::InvalidAmount);
+            }
+            for (i, bracket) in brackets.iter().enumerate() {
+                if bracket.fee_bps > MAX_FEE_BPS {
+                    return Err(Error::InvalidAmount);
+                }
+                if i > 0 && bracket.min_amount <= brackets[i - 1].min_amount {
+                    return Err(Error::InvalidAmount);
+                }
+            }
+
+            self.audit(AuditOperation::Config, &brackets);
+            self.env().emit_event(FeeScheduleUpdated {
+                brackets: brackets.clone(),
+            });
+            self.fee_schedule = brackets;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn fee_schedule(&self) -> Vec<FeeBracket> {
+            self.fee_schedule.clone()
+        }
+
+        /// Fee a transfer of `amount` would pay under the active rate or schedule
+        #[ink(message)]
+        pub fn fee_for(&self, amount: u128) -> u128 {
+            if self.fee_collector.is_none() {
+                return 0;
+            }
+            Self::bps_of(amount, self.fee_bps_for(amount))
+        }
+
+        fn fee_bps_for(&self, amount: u128) -> u16 {
+            if self.fee_schedule.is_empty() {
+                return self.fee_bps;
+            }
+            self.fee_schedule
+                .iter()
+                .rev()
+                .find(|bracket| amount >= bracket.min_amount)
+                .map_or(0, |bracket| bracket.fee_bps)
+        }
+
+        /// Fee taken from `amount`, less the sender's loyalty discount; transfers to
+        /// or from the collector are exempt
+        fn transfer_fee(&self, from: AccountId, to: AccountId, amount: u128) -> u128 {
+            match self.fee_collector {
+                Some(collector) if collector != from && collector != to => {
+                    let fee = self.fee_for(amount);
+                    let discount_bps = self
+                        .loyalty_tier(from)
+                        .map_or(0, |tier| tier.fee_discount_bps);
+                    fee - Self::bps_of(fee, discount_bps)
+                }
+                _ => 0,
+            }
+        }
+
+        /// Replaces the loyalty tiers. Tiers must be sorted by strictly increasing
+        /// `min_volume`; an empty list ends the program.
+        #[ink(message)]
+        pub fn set_loyalty_tiers(&mut self, tiers: Vec<LoyaltyTier>) -> Result<()> {
+            self.only_owner()?;
+
+            if tiers.len() > MAX_LOYALTY_TIERS {
+                return Err(Error::InvalidAmount);
+            }
+            for (i, tier) in tiers.iter().enumerate() {
+                if tier.fee_discount_bps > 10_000 {
+                    return Err(Error::InvalidAmount);
+                }
+                if i > 0 && tier.min_volume <= tiers[i - 1].min_volume {
+                    return Err(Error::InvalidAmount);
+                }
+            }
+
+            self.audit(AuditOperation::Config, &tiers);
+            self.env().emit_event(LoyaltyTiersUpdated {
+                tiers: tiers.clone(),
+            });
+            self.loyalty_tiers = tiers;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn loyalty_tiers(&self) -> Vec<LoyaltyTier> {
+            self.loyalty_tiers.clone()
+        }
+
+        /// What `account` sent in the current and the previous `LOYALTY_WINDOW`
+        #[ink(message)]
+        pub fn rolling_volume(&self, account: AccountId) -> u128 {
+            let volume = self.current_loyalty_volume(account);
+            volume.current.saturating_add(volume.previous)
+        }
+
+        /// `account`'s loyalty tier by rolling volume: 0 below the first tier, 1
+        /// for the first and so on. Not to be confused with the KYC `tier_of`.
+        #[ink(message)]
+        pub fn loyalty_tier_of(&self, account: AccountId) -> u8 {
+            let volume = self.rolling_volume(account);
+            self.loyalty_tiers
+                .iter()
+                .filter(|tier| volume >= tier.min_volume)
+                .count() as u8
+        }
+
+        fn loyalty_tier(&self, account: AccountId) -> Option<&LoyaltyTier> {
+            match self.loyalty_tier_of(account) {
+                0 => None,
+                tier => self.loyalty_tiers.get(tier as usize - 1),
+            }
+        }
+
+        /// `account`'s loyalty volume with windows that ended rolled over
+        fn current_loyalty_volume(&self, account: AccountId) -> LoyaltyVolume {
+            let mut volume = self.loyalty_volume.get(account).unwrap_or_default();
+            let elapsed = self
+                .env()
+                .block_timestamp()
+                .saturating_sub(volume.window_start);
+            if elapsed >= LOYALTY_WINDOW {
+                volume.previous = if elapsed < 2 * LOYALTY_WINDOW {
+                    volume.current
+                } else {
+                    0
+                };
+                volume.current = 0;
+                volume.window_start = volume
+                    .window_start
+                    .saturating_add(elapsed - elapsed % LOYALTY_WINDOW);
+            }
+            volume
+        }
+
+        fn record_loyalty_volume(&mut self, account: AccountId, amount: u128) {
+            if self.loyalty_tiers.is_empty() {
+                return;
+            }
+            let mut volume = self.current_loyalty_volume(account);
+            volume.current = volume.current.saturating_add(amount);
+            self.loyalty_volume.insert(account, &volume);
+        }
+
+        /// `amount * bps / 10_000` without overflowing for large amounts
+        fn bps_of(amount: u128, bps: u16) -> u128 {
+            let bps = bps as u128;
+            amount / 10_000 * bps + amount % 10_000 * bps / 10_000
+        }
+
+        /// Transfers above `threshold` must carry a non-empty `data` reference, e.g. a
+        /// hash of the originator and beneficiary information
+        #[ink(message)]
+        pub fn set_travel_rule_threshold(&mut self, threshold: Option<u128>) -> Result<()> {
+            self.only_owner()?;
+
+            self.travel_rule_threshold = threshold;
+
+            self.audit(AuditOperation::Config, &threshold);
+            self.env()
+                .emit_event(TravelRuleThresholdUpdated { threshold });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn travel_rule_threshold(&self) -> Option<u128> {
+            self.travel_rule_threshold
+        }
+
+        #[ink(message)]
+        pub fn set_compliance_officer(&mut self, compliance: Option<AccountId>) -> Result<()> {
+            self.only_owner()?;
+
+            self.compliance = compliance;
+
+            self.audit(AuditOperation::Config, &compliance);
+            self.env()
+                .emit_event(ComplianceOfficerUpdated { compliance });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn compliance_officer(&self) -> Option<AccountId> {
+            self.compliance
+        }
+
+        #[ink(message)]
+        pub fn set_tier(&mut self, account: AccountId, tier: KycTier) -> Result<()> {
+            self.only_compliance()?;
+
+            self.tiers.insert(account, &tier);
+
+            self.audit(AuditOperation::Config, &(account, tier));
+            self.env().emit_event(TierUpdated { account, tier });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn tier_of(&self, account: AccountId) -> KycTier {
+            self.tiers.get(account).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn set_tier_limits(&mut self, tier: KycTier, limits: TierLimits) -> Result<()> {
+            self.only_compliance()?;
+
+            self.tier_limits.insert(tier, &limits);
+
+            self.audit(AuditOperation::Config, &(tier, &limits));
+            self.env().emit_event(TierLimitsUpdated { tier, limits });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn tier_limits(&self, tier: KycTier) -> TierLimits {
+            self.tier_limits.get(tier).unwrap_or_default()
+        }
+
+        /// `(max_transfer, balance_headroom)` for `account` under its tier; `None` is unlimited
+        #[ink(message)]
+        pub fn remaining_limits(&self, account: AccountId) -> (Option<u128>, Option<u128>) {
+            let limits = self.limits_of(account);
+            let headroom = limits
+                .max_balance
+                .map(|max| max.saturating_sub(self.balance_of(account)));
+            (limits.max_transfer, headroom)
+        }
+
+        fn limits_of(&self, account: AccountId) -> TierLimits {
+            self.tier_limits(self.tier_of(account))
+        }
+
+        #[inline]
+        fn within_balance_limit(&self, account: AccountId, new_balance: u128) -> Result<()> {
+            if self
+                .limits_of(account)
+                .max_balance
+                .is_some_and(|max| new_balance > max)
+            {
+                return Err(Error::TierLimitExceeded);
+            }
+            Ok(())
+        }
+
+        /// Freezes `amount` more of `account`'s balance; frozen tokens can't be
+        /// transferred or burned
+        #[ink(message)]
+        pub fn freeze(&mut self, account: AccountId, amount: u128) -> Result<()> {
+            self.only_compliance()?;
+
+            let frozen = self
+                .frozen_balance_of(account)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            if frozen > self.balance_of(account) {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.set_frozen(account, frozen);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unfreeze(&mut self, account: AccountId, amount: u128) -> Result<()> {
+            self.only_compliance()?;
+
+            let frozen = self
+                .frozen_balance_of(account)
+                .checked_sub(amount)
+                .ok_or(Error::InvalidAmount)?;
+
+            self.set_frozen(account, frozen);
+            Ok(())
+        }
+
+        fn set_frozen(&mut self, account: AccountId, frozen: u128) {
+            if frozen == 0 {
+                self.frozen.remove(account);
+            } else {
+                self.frozen.insert(account, &frozen);
+            }
+
+            self.audit(AuditOperation::Freeze, &(account, frozen));
+            self.env().emit_event(BalanceFrozen { account, frozen });
+        }
+
+        /// Court-ordered seizure by the compliance officer. Ignores pause, blacklist
+        /// and freezes. With the timelock enabled it is queued and the operation id
+        /// returned; otherwise it executes immediately and returns `None`.
+        #[ink(message)]
+        pub fn force_transfer(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            amount: u128,
+        ) -> Result<Option<u64>> {
+            self.only_compliance()?;
+
+            let action = AdminAction::ForceTransfer { from, to, amount };
+            if self.is_timelocked(&action) {
+                return self.schedule(action).map(Some);
+            }
+
+            self.seize(from, to, amount)?;
+            Ok(None)
+        }
+
+        fn seize(&mut self, from: AccountId, to: AccountId, amount: u128) -> Result<()> {
+            if amount == 0 || from == to {
+                return Err(Error::InvalidAmount);
+            }
+            self.settle_demurrage(from);
+            self.settle_demurrage(to);
+
+            let from_balance = self.balance_of(from);
+            let new_from_balance = from_balance
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientBalance)?;
+            let new_to_balance = self
+                .balance_of(to)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+
+            self.write_balance(from, new_from_balance);
+            self.write_balance(to, new_to_balance);
+            if self.frozen_balance_of(from) > new_from_balance {
+                self.set_frozen(from, new_from_balance);
+            }
+
+            self.env().emit_event(Transfer {
+                from: Some(from),
+                to: Some(to),
+                value: amount,
+                data: Vec::new(),
+            });
+            self.audit(AuditOperation::Seize, &(from, to, amount));
+            self.env().emit_event(Seizure { from, to, amount });
+
+            Ok(())
+        }
+
+        /// Burns the whole balance of a blacklisted account, reducing total supply
+        #[ink(message)]
+        pub fn wipe_blacklisted(&mut self, account: AccountId) -> Result<()> {
+            self.only_compliance()?;
+            if !self.is_blacklisted(account) {
+                return Err(Error::NotBlacklisted);
+            }
+            self.settle_demurrage(account);
+
+            let amount = self.balance_of(account);
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.write_balance(account, 0);
+            self.total_supply = self.total_supply.saturating_sub(amount);
+            if self.frozen_balance_of(account) > 0 {
+                self.set_frozen(account, 0);
+            }
+
+            self.env().emit_event(Transfer {
+                from: Some(account),
+                to: None,
+                value: amount,
+                data: Vec::new(),
+            });
+            self.audit(AuditOperation::Wipe, &(account, amount));
+            self.env()
+                .emit_event(BlacklistedFundsWiped { account, amount });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn frozen_balance_of(&self, account: AccountId) -> u128 {
+            self.frozen.get(account).unwrap_or(0)
+        }
+
+        /// Balance minus the frozen amount
+        #[ink(message)]
+        pub fn spendable_balance_of(&self, account: AccountId) -> u128 {
+            self.balance_of(account)
+                .saturating_sub(self.frozen_balance_of(account))
+        }
+
+        /// Sets the account allowed to `pause()` (but not unpause or anything else)
+        #[ink(message)]
+        pub fn set_guardian(&mut self, guardian: Option<AccountId>) -> Result<()> {
+            self.only_owner()?;
+
+            self.guardian = guardian;
+
+            self.audit(AuditOperation::Config, &guardian);
+            self.env().emit_event(GuardianUpdated { guardian });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn guardian(&self) -> Option<AccountId> {
+            self.guardian
+        }
+
+        #[ink(message)]
+        pub fn blacklist(&mut self, account: AccountId) -> Result<()> {
+            self.only_owner_direct()?;
+            self.add_to_blacklist(account, None, BlacklistReason::Unspecified)
+        }
+
+        /// Temporary blacklisting that lapses at `expires_at` without an `unblacklist`
+        #[ink(message)]
+        pub fn blacklist_until(&mut self, account: AccountId, expires_at: Timestamp) -> Result<()> {
+            self.only_owner_direct()?;
+            self.add_to_blacklist(account, Some(expires_at), BlacklistReason::Unspecified)
+        }
+
+        #[ink(message)]
+        pub fn blacklist_with_reason(
+            &mut self,
+            account: AccountId,
+            reason: BlacklistReason,
+            expires_at: Option<Timestamp>,
+        ) -> Result<()> {
+            self.only_owner_direct()?;
+            self.add_to_blacklist(account, expires_at, reason)
+        }
+
+        #[ink(message)]
+        pub fn unblacklist(&mut self, account: AccountId) -> Result<()> {
+            self.only_owner_direct()?;
+            self.remove_from_blacklist(account);
+            Ok(())
+        }
+
+        fn add_to_blacklist(
+            &mut self,
+            account: AccountId,
+            expires_at: Option<Timestamp>,
+            reason: BlacklistReason,
+        ) -> Result<()> {
+            if account == self.owner {
+                return Err(Error::Unauthorized);
+            }
+            if expires_at.is_some_and(|expiry| expiry <= self.env().block_timestamp()) {
+                return Err(Error::InvalidAmount);
+            }
+
+            if !self.blacklist_index.contains(account) {
+                let index = self.blacklist_count;
+                self.blacklist_count = index.checked_add(1).ok_or(Error::Overflow)?;
+                self.blacklisted_accounts.insert(index, &account);
+                self.blacklist_index.insert(account, &index);
+            }
+            self.blacklist
+                .insert(account, &BlacklistEntry { expires_at, reason });
+
+            self.audit(AuditOperation::Blacklist, &(account, expires_at, reason));
+            self.env().emit_event(BlacklistUpdated {
+                account,
+                blacklisted: true,
+                expires_at,
+                reason: Some(reason),
+            });
+
+            Ok(())
+        }
+
+        fn remove_from_blacklist(&mut self, account: AccountId) {
+            self.blacklist.remove(account);
+
+            // Swap-remove from the enumeration index
+            if let Some(index) = self.blacklist_index.take(account) {
+                let last = self.blacklist_count.saturating_sub(1);
+                if index != last {
+                    if let Some(moved) = self.blacklisted_accounts.get(last) {
+                        self.blacklisted_accounts.insert(index, &moved);
+                        self.blacklist_index.insert(moved, &index);
+                    }
+                }
+                self.blacklisted_accounts.remove(last);
+                self.blacklist_count = last;
+            }
+
+            self.audit(AuditOperation::Unblacklist, &account);
+            self.env().emit_event(BlacklistUpdated {
+                account,
+                blacklisted: false,
+                expires_at: None,
+                reason: None,
+            });
+        }
+
+        #[ink(message)]
+        pub fn is_blacklisted(&self, account: AccountId) -> bool {
+            self.blacklist_info(account).is_some()
+        }
+
+        /// Submits an appeal against the caller's blacklisting. A rejected account may
+        /// appeal again.
+        #[ink(message)]
+        pub fn submit_appeal(&mut self, memo_hash: Hash) -> Result<()> {
+            let account = self.env().caller();
+            if !self.is_blacklisted(account) {
+                return Err(Error::NotBlacklisted);
+            }
+            if self
+                .appeals
+                .get(account)
+                .is_some_and(|appeal| appeal.status == AppealStatus::Pending)
+            {
+                return Err(Error::AppealPending);
+            }
+
+            self.appeals.insert(
+                account,
+                &Appeal {
+                    memo_hash,
+                    submitted_at: self.env().block_timestamp(),
+                    status: AppealStatus::Pending,
+                },
+            );
+
+            self.env()
+                .emit_event(AppealSubmitted { account, memo_hash });
+
+            Ok(())
+        }
+
+        /// Approving an appeal lifts the blacklisting
+        #[ink(message)]
+        pub fn resolve_appeal(&mut self, account: AccountId, approve: bool) -> Result<()> {
+            self.only_compliance()?;
+
+            let mut appeal = self
+                .appeals
+                .get(account)
+                .filter(|appeal| appeal.status == AppealStatus::Pending)
+                .ok_or(Error::AppealNotFound)?;
+
+            if approve {
+                appeal.status = AppealStatus::Approved;
+                self.remove_from_blacklist(account);
+            } else {
+                appeal.status = AppealStatus::Rejected;
+            }
+            self.appeals.insert(account, &appeal);
+
+            self.env().emit_event(AppealResolved {
+                account,
+                approved: approve,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn appeal_of(&self, account: AccountId) -> Option<Appeal> {
+            self.appeals.get(account)
+        }
+
+        /// Number of blacklist entries, including lapsed ones not yet purged
+        #[ink(message)]
+        pub fn blacklist_count(&self) -> u32 {
+            self.blacklist_count
+        }
+
+        /// Page through blacklist entries. Order is not stable across removals.
+        /// Lapsed entries are included until purged, check `expires_at`.
+        #[ink(message)]
+        pub fn blacklisted_accounts(
+            &self,
+            offset: u32,
+            limit: u32,
+        ) -> Vec<(AccountId, BlacklistEntry)> {
+            let end = offset.saturating_add(limit).min(self.blacklist_count);
+            (offset..end)
+                .filter_map(|index| self.blacklisted_accounts.get(index))
+                .filter_map(|account| self.blacklist.get(account).map(|entry| (account, entry)))
+                .collect()
+        }
+
+        /// Removes a lapsed entry from the list. Anyone may call it.
+        #[ink(message)]
+        pub fn purge_expired_blacklist(&mut self, account: AccountId) -> Result<()> {
+            if self.is_blacklisted(account) {
+                return Err(Error::BlacklistEntryActive);
+            }
+            if self.blacklist_index.contains(account) {
+                self.remove_from_blacklist(account);
+            }
+            Ok(())
+        }
+
+        /// The active blacklist entry for `account`, with its expiry and reason
+        #[ink(message)]
+        pub fn blacklist_info(&self, account: AccountId) -> Option<BlacklistEntry> {
+            self.blacklist.get(account).filter(|entry| {
+                entry
+                    .expires_at
+                    .is_none_or(|expiry| self.env().block_timestamp() < expiry)
+            })
+        }
+
+        /// While enabled only whitelisted accounts may send or receive tokens
+        #[ink(message)]
+        pub fn set_whitelist_mode(&mut self, enabled: bool) -> Result<()> {
+            self.only_owner()?;
+
+            self.whitelist_mode = enabled;
+
+            self.audit(AuditOperation::Config, &enabled);
+            self.env().emit_event(WhitelistModeUpdated { enabled });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_whitelist_mode(&self) -> bool {
+            self.whitelist_mode
+        }
+
+        #[ink(message)]
+        pub fn add_to_whitelist(&mut self, account: AccountId) -> Result<()> {
+            self.only_compliance()?;
+            self.set_whitelisted(account, true);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn remove_from_whitelist(&mut self, account: AccountId) -> Result<()> {
+            self.only_compliance()?;
+            self.set_whitelisted(account, false);
+            Ok(())
+        }
+
+        fn set_whitelisted(&mut self, account: AccountId, whitelisted: bool) {
+            if whitelisted {
+                self.whitelist.insert(account, &true);
+            } else {
+                self.whitelist.remove(account);
+            }
+
+            self.audit(AuditOperation::Config, &(account, whitelisted));
+            self.env().emit_event(WhitelistUpdated {
+                account,
+                whitelisted,
+            });
+        }
+
+        #[ink(message)]
+        pub fn is_whitelisted(&self, account: AccountId) -> bool {
+            self.whitelist.contains(account)
+        }
+
+        /// Blocks outgoing transfers from the caller until unlocked
+        #[ink(message)]
+        pub fn lock_my_account(&mut self) -> Result<()> {
+            let account = self.env().caller();
+
+            self.self_locks.insert(account, &Timestamp::MAX);
+
+            self.env().emit_event(SelfLockUpdated {
+                account,
+                unlock_at: None,
+            });
+
+            Ok(())
+        }
+
+        /// Lets the caller's lock lapse `delay` ms from now. The delay is at least
+        /// `MIN_SELF_UNLOCK_DELAY` and a scheduled unlock can only be pushed back.
+        #[ink(message)]
+        pub fn unlock_my_account_after(&mut self, delay: Timestamp) -> Result<()> {
+            let account = self.env().caller();
+            let current = self.self_locks.get(account).ok_or(Error::InvalidAmount)?;
+            if delay < MIN_SELF_UNLOCK_DELAY {
+                return Err(Error::InvalidAmount);
+            }
+
+            let unlock_at = self
+                .env()
+                .block_timestamp()
+                .checked_add(delay)
+                .ok_or(Error::Overflow)?;
+            if current != Timestamp::MAX && unlock_at < current {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.self_locks.insert(account, &unlock_at);
+
+            self.env().emit_event(SelfLockUpdated {
+                account,
+                unlock_at: Some(unlock_at),
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_account_locked(&self, account: AccountId) -> bool {
+            self.self_locks
+                .get(account)
+                .is_some_and(|unlock_at| self.env().block_timestamp() < unlock_at)
+        }
+
+        /// Lets `beneficiary` claim the caller's balance after `inactivity_period`
+        /// ms without a transfer or `check_in()` from the caller
+        #[ink(message)]
+        pub fn set_beneficiary(
+            &mut self,
+            beneficiary: AccountId,
+            inactivity_period: Timestamp,
+        ) -> Result<()> {
+            let holder = self.env().caller();
+            if beneficiary == holder || inactivity_period < MIN_INACTIVITY_PERIOD {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.inheritances.insert(
+                holder,
+                &Inheritance {
+                    beneficiary,
+                    inactivity_period,
+                    last_active: self.env().block_timestamp(),
+                },
+            );
+
+            self.env().emit_event(BeneficiaryUpdated {
+                holder,
+                beneficiary: Some(beneficiary),
+                inactivity_period,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn remove_beneficiary(&mut self) -> Result<()> {
+            let holder = self.env().caller();
+            if !self.inheritances.contains(holder) {
+                return Err(Error::NoBeneficiary);
+            }
+
+            self.inheritances.remove(holder);
+
+            self.env().emit_event(BeneficiaryUpdated {
+                holder,
+                beneficiary: None,
+                inactivity_period: 0,
+            });
+
+            Ok(())
+        }
+
+        /// Resets the caller's inactivity timer without making a transfer. This
+        /// also clears a dormancy flag.
+        #[ink(message)]
+        pub fn check_in(&mut self) -> Result<()> {
+            let holder = self.env().caller();
+            if !self.inheritances.contains(holder) && self.dormancy_rules.is_none() {
+                return Err(Error::NoBeneficiary);
+            }
+            self.record_activity(holder);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn inheritance_of(&self, holder: AccountId) -> Option<Inheritance> {
+            self.inheritances.get(holder)
+        }
+
+        /// Moves the spendable balance of a holder inactive for its whole
+        /// inactivity period to the caller, its beneficiary, and returns the amount
+        #[ink(message)]
+        pub fn claim_inheritance(&mut self, holder: AccountId) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let beneficiary = self.env().caller();
+            let inheritance = self
+                .inheritances
+                .get(holder)
+                .filter(|inheritance| inheritance.beneficiary == beneficiary)
+                .ok_or(Error::NoBeneficiary)?;
+            if self.env().block_timestamp()
+                < inheritance
+                    .last_active
+                    .saturating_add(inheritance.inactivity_period)
+            {
+                return Err(Error::HolderStillActive);
+            }
+            self.not_blacklisted(holder)?;
+            self.not_blacklisted(beneficiary)?;
+
+            self.settle_demurrage(holder);
+            let amount = self.spendable_balance_of(holder);
+            let new_balance = self
+                .balance_of(beneficiary)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(beneficiary, new_balance)?;
+
+            self.move_balance(holder, beneficiary, amount)?;
+            self.inheritances.remove(holder);
+
+            self.env().emit_event(InheritanceClaimed {
+                holder,
+                beneficiary,
+                amount,
+            });
+
+            Ok(amount)
+        }
+
+        /// Appoints the guardians who can recover the caller's balance if its key
+        /// is lost; an empty list removes them. Drops any pending recovery.
+        #[ink(message)]
+        pub fn set_recovery_guardians(
+            &mut self,
+            guardians: Vec<AccountId>,
+            quorum: u32,
+        ) -> Result<()> {
+            let holder = self.env().caller();
+            if guardians.is_empty() {
+                self.recovery_configs.remove(holder);
+            } else {
+                if guardians.len() > MAX_RECOVERY_GUARDIANS
+                    || quorum == 0
+                    || quorum as usize > guardians.len()
+                {
+                    return Err(Error::InvalidThreshold);
+                }
+                for (i, guardian) in guardians.iter().enumerate() {
+                    if *guardian == holder || guardians[..i].contains(guardian) {
+                        return Err(Error::DuplicateGuardian);
+                    }
+                }
+                self.recovery_configs.insert(
+                    holder,
+                    &RecoveryConfig {
+                        guardians: guardians.clone(),
+                        quorum,
+                    },
+                );
+            }
+            self.recoveries.remove(holder);
+
+            self.env().emit_event(RecoveryGuardiansUpdated {
+                holder,
+                guardians,
+                quorum,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn recovery_config(&self, holder: AccountId) -> Option<RecoveryConfig> {
+            self.recovery_configs.get(holder)
+        }
+
+        #[ink(message)]
+        pub fn recovery(&self, holder: AccountId) -> Option<Recovery> {
+            self.recoveries.get(holder)
+        }
+
+        /// Backs moving `holder`'s balance to `new_account`. Backing a different
+        /// account than the pending recovery starts over. Once the quorum agrees,
+        /// the recovery can be executed after `RECOVERY_DELAY`.
+        #[ink(message)]
+        pub fn approve_recovery(
+            &mut self,
+            holder: AccountId,
+            new_account: AccountId,
+        ) -> Result<()> {
+            let guardian = self.env().caller();
+            let config = self
+                .recovery_configs
+                .get(holder)
+                .filter(|config| config.guardians.contains(&guardian))
+                .ok_or(Error::NotRecoveryGuardian)?;
+            if new_account == holder {
+                return Err(Error::InvalidAmount);
+            }
+
+            let mut recovery = self
+                .recoveries
+                .get(holder)
+                .filter(|recovery| recovery.new_account == new_account)
+                .unwrap_or(Recovery {
+                    new_account,
+                    approvals: Vec::new(),
+                    executable_at: None,
+                });
+            if recovery.approvals.contains(&guardian) {
+                return Err(Error::AlreadyConfirmed);
+            }
+            recovery.approvals.push(guardian);
+            let approvals = recovery.approvals.len() as u32;
+
+            // Set only when this approval reaches the quorum
+            let scheduled_at = match recovery.executable_at {
+                None if approvals >= config.quorum => Some(
+                    self.env()
+                        .block_timestamp()
+                        .checked_add(RECOVERY_DELAY)
+                        .ok_or(Error::Overflow)?,
+                ),
+                _ => None,
+            };
+            if scheduled_at.is_some() {
+                recovery.executable_at = scheduled_at;
+            }
+            self.recoveries.insert(holder, &recovery);
+
+            self.env().emit_event(RecoveryApproved {
+                holder,
+                guardian,
+                new_account,
+                approvals,
+            });
+            if let Some(executable_at) = scheduled_at {
+                self.env().emit_event(RecoveryScheduled {
+                    holder,
+                    new_account,
+                    executable_at,
+                });
+            }
+
+            Ok(())
+        }
+
+        /// Lets the holder stop a recovery it didn't ask for
+        #[ink(message)]
+        pub fn cancel_recovery(&mut self) -> Result<()> {
+            let holder = self.env().caller();
+            if !self.recoveries.contains(holder) {
+                return Err(Error::RecoveryNotFound);
+            }
+
+            self.recoveries.remove(holder);
+
+            self.env().emit_event(RecoveryCancelled { holder });
+
+            Ok(())
+        }
+
+        /// Moves the spendable balance of `holder` to the new account its guardians
+        /// agreed on, once the delay has passed. Anyone can call it.
+        #[ink(message)]
+        pub fn execute_recovery(&mut self, holder: AccountId) -> Result<u128> {
+            self.when_not_paused()?;
+
+            let recovery = self.recoveries.get(holder).ok_or(Error::RecoveryNotFound)?;
+            if recovery
+                .executable_at
+                .is_none_or(|executable_at| self.env().block_timestamp() < executable_at)
+            {
+                return Err(Error::RecoveryNotReady);
+            }
+            let new_account = recovery.new_account;
+            self.not_blacklisted(holder)?;
+            self.not_blacklisted(new_account)?;
+
+            self.settle_demurrage(holder);
+            let amount = self.spendable_balance_of(holder);
+            let new_balance = self
+                .balance_of(new_account)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.within_balance_limit(new_account, new_balance)?;
+
+            self.move_balance(holder, new_account, amount)?;
+            self.recoveries.remove(holder);
+
+            self.env().emit_event(AccountRecovered {
+                holder,
+                new_account,
+                amount,
+            });
+
+            Ok(amount)
+        }
+
+        /// Resets `holder`'s inactivity timer, if it has a beneficiary
+        fn record_activity(&mut self, holder: AccountId) {
+            let now = self.env().block_timestamp();
+            if let Some(mut inheritance) = self.inheritances.get(holder) {
+                inheritance.last_active = now;
+                self.inheritances.insert(holder, &inheritance);
+            }
+            self.last_activity.insert(holder, &now);
+            self.dormant_since.remove(holder);
+        }
+
+        /// Appends to the audit log, overwriting the oldest entry once it is full
+        fn audit<P: ink::scale::Encode>(&mut self, operation: AuditOperation, params: &P) {
+            let id = self.audit_log_len;
+            self.audit_log.insert(
+                id % AUDIT_LOG_CAPACITY,
+                &AuditEntry {
+                    caller: self.env().caller(),
+                    timestamp: self.env().block_timestamp(),
+                    operation,
+                    params: params.encode(),
+                },
+            );
+            self.audit_log_len = id.saturating_add(1);
+        }
+
+        /// Total number of operations ever logged; only the last
+        /// `AUDIT_LOG_CAPACITY` can still be read
+        #[ink(message)]
+        pub fn audit_log_len(&self) -> u64 {
+            self.audit_log_len
+        }
+
+        /// Retained audit entries with ids in `offset..offset + limit`, oldest first
+        #[ink(message)]
+        pub fn audit_log(&self, offset: u64, limit: u32) -> Vec<(u64, AuditEntry)> {
+            let first = self.audit_log_len.saturating_sub(AUDIT_LOG_CAPACITY);
+            let start = offset.max(first);
+            let end = offset.saturating_add(limit as u64).min(self.audit_log_len);
+            (start..end)
+                .filter_map(|id| {
+                    self.audit_log
+                        .get(id % AUDIT_LOG_CAPACITY)
+                        .map(|entry| (id, entry))
+                })
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<()> {
+            self.only_owner_direct()?;
+            self.not_timelocked(&AdminAction::TransferOwnership { new_owner })?;
+            self._transfer_ownership(new_owner);
+            Ok(())
+        }
+
+        fn _transfer_ownership(&mut self, new_owner: AccountId) {
+            let old_owner = self.owner;
+            self.owner = new_owner;
+
+            self.audit(AuditOperation::Ownership, &(old_owner, new_owner));
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner: old_owner,
+                new_owner,
+            });
+        }
+
+        /// Permanently gives up ownership, disabling every owner-only message.
+        /// `confirm_owner` must repeat the current owner's address.
+        #[ink(message)]
+        pub fn renounce_ownership(&mut self, confirm_owner: AccountId) -> Result<()> {
+            self.only_owner()?;
+
+            if confirm_owner != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            // Nobody could ever unpause afterwards
+            self.when_not_paused()?;
+
+            let previous_owner = self.owner;
+            self.owner = AccountId::from([0u8; 32]);
+            self.renounced = true;
+
+            self.audit(AuditOperation::Ownership, &(previous_owner, self.owner));
+            self.env().emit_event(OwnershipRenounced { previous_owner });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_renounced(&self) -> bool {
+            self.renounced
+        }
+
+        /// Enables the admin multisig: from then on mint, blacklist, pause and
+        /// ownership transfer need `threshold` of `admins` to confirm a proposal.
+        /// Proposals expire `proposal_ttl` milliseconds after creation.
+        #[ink(message)]
+        pub fn set_admins(
+            &mut self,
+            admins: Vec<AccountId>,
+            threshold: u32,
+            proposal_ttl: Timestamp,
+        ) -> Result<()> {
+            self.only_owner_direct()?;
+            if proposal_ttl == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            self.proposal_ttl = proposal_ttl;
+            self._set_admins(admins, threshold)
+        }
+
+        fn _set_admins(&mut self, admins: Vec<AccountId>, threshold: u32) -> Result<()> {
+            if threshold == 0 || threshold as usize > admins.len() {
+                return Err(Error::InvalidThreshold);
+            }
+            for (i, admin) in admins.iter().enumerate() {
+                if admins[..i].contains(admin) {
+                    return Err(Error::InvalidThreshold);
+                }
+            }
+
+            self.audit(AuditOperation::Config, &(&admins, threshold));
+            self.env().emit_event(AdminsUpdated {
+                admins: admins.len() as u32,
+                threshold,
+            });
+
+            self.admins = admins;
+            self.admin_threshold = threshold;
+            self.admin_epoch = self.admin_epoch.wrapping_add(1);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn admins(&self) -> Vec<AccountId> {
+            self.admins.clone()
+        }
+
+        #[ink(message)]
+        pub fn admin_threshold(&self) -> u32 {
+            self.admin_threshold
+        }
+
+        #[ink(message)]
+        pub fn proposal(&self, id: u64) -> Option<Proposal> {
+            self.proposals.get(id)
+        }
+
+        #[ink(message)]
+        pub fn has_confirmed(&self, id: u64, admin: AccountId) -> bool {
+            self.confirmations.get((id, admin)).unwrap_or(false)
+        }
+
+        fn only_admin(&self) -> Result<AccountId> {
+            let caller = self.env().caller();
+            if self.admin_threshold == 0 || !self.admins.contains(&caller) {
+                return Err(Error::Unauthorized);
+            }
+            Ok(caller)
+        }
+
+        /// Proposes an admin action; the proposer's confirmation is counted
+        #[ink(message)]
+        pub fn propose_admin_action(&mut self, action: AdminAction) -> Result<u64> {
+            let proposer = self.only_admin()?;
+            if self.renounced {
+                return Err(Error::OwnershipRenounced);
+            }
+
+            let id = self.next_proposal_id;
+            self.next_proposal_id = id.checked_add(1).ok_or(Error::Overflow)?;
+
+            let expires_at = self
+                .env()
+                .block_timestamp()
+                .checked_add(self.proposal_ttl)
+                .ok_or(Error::Overflow)?;
+
+            self.proposals.insert(
+                id,
+                &Proposal {
+                    action: action.clone(),
+                    proposer,
+                    confirmations: 0,
+                    expires_at,
+                    executed: false,
+                    epoch: self.admin_epoch,
+                },
+            );
+
+            self.env().emit_event(ProposalCreated {
+                id,
+                proposer,
+                action,
+                expires_at,
+            });
+
+            self.confirm_admin_action(id)?;
+
+            Ok(id)
+        }
+
+        /// Confirms a proposal, executing it once the threshold is reached
+        #[ink(message)]
+        pub fn confirm_admin_action(&mut self, id: u64) -> Result<()> {
+            let admin = self.only_admin()?;
+            if self.renounced {
+                return Err(Error::OwnershipRenounced);
+            }
+
+            let mut proposal = self.proposals.get(id).ok_or(Error::ProposalNotFound)?;
+            if proposal.executed || proposal.epoch != self.admin_epoch {
+                return Err(Error::ProposalNotFound);
+            }
+            if self.env().block_timestamp() >= proposal.expires_at {
+                return Err(Error::ProposalExpired);
+            }
+            if self.has_confirmed(id, admin) {
+                return Err(Error::AlreadyConfirmed);
+            }
+
+            proposal.confirmations = proposal.confirmations.saturating_add(1);
+
+            if proposal.confirmations >= self.admin_threshold {
+                self.dispatch_admin_action(proposal.action.clone())?;
+                proposal.executed = true;
+            }
+
+            self.confirmations.insert((id, admin), &true);
+            self.proposals.insert(id, &proposal);
+
+            self.env().emit_event(ProposalConfirmed {
+                id,
+                admin,
+                confirmations: proposal.confirmations,
+            });
+            if proposal.executed {
+                self.env().emit_event(ProposalExecuted { id });
+            }
+
+            Ok(())
+        }
+
+        fn execute_admin_action(&mut self, action: AdminAction) -> Result<()> {
+            match action {
+                AdminAction::Mint { to, amount } => self.privileged_mint(to, amount),
+                AdminAction::Blacklist {
+                    account,
+                    expires_at,
+                    reason,
+                } => self.add_to_blacklist(account, expires_at, reason),
+                AdminAction::Unblacklist { account } => {
+                    self.remove_from_blacklist(account);
+                    Ok(())
+                }
+                AdminAction::Pause => {
+                    self.set_paused(true);
+                    Ok(())
+                }
+                AdminAction::Unpause => {
+                    self.set_paused(false);
+                    Ok(())
+                }
+                AdminAction::TransferOwnership { new_owner } => {
+                    self._transfer_ownership(new_owner);
+                    Ok(())
+                }
+                AdminAction::SetAdmins { admins, threshold } => self._set_admins(admins, threshold),
+                AdminAction::SetCap { cap } => self._set_cap(cap),
+                AdminAction::SetTimelock {
+                    delay,
+                    large_mint_threshold,
+                } => {
+                    self._set_timelock(delay, large_mint_threshold);
+                    Ok(())
+                }
+                AdminAction::CancelOperation { id } => self._cancel_operation(id),
+                AdminAction::ForceTransfer { from, to, amount } => self.seize(from, to, amount),
+                AdminAction::InsurancePayout { to, amount } => self.pay_from_insurance(to, amount),
+                AdminAction::SetScopePaused { scope, paused } => {
+                    self.set_scope_paused(scope, paused);
+                    Ok(())
+                }
+                AdminAction::SetCodeHash { code_hash } => {
+                    self.env()
+                        .set_code_hash(&code_hash)
+                        .map_err(|_| Error::UpgradeFailed)?;
+                    self.audit(AuditOperation::Upgrade, &code_hash);
+                    Ok(())
+                }
+            }
+        }
+
+        /// Runs `action` now, or queues it if it is subject to the timelock
+        fn dispatch_admin_action(&mut self, action: AdminAction) -> Result<()> {
+            if self.is_timelocked(&action) {
+                self.schedule(action).map(|_| ())
+            } else {
+                self.execute_admin_action(action)
+            }
+        }
+
+        /// Large mints, cap changes, seizures, ownership transfer, upgrades and changes
+        /// to the timelock itself must wait out the delay once it is enabled
+        fn is_timelocked(&self, action: &AdminAction) -> bool {
+            if self.timelock_delay == 0 {
+                return false;
+            }
+            match action {
+                AdminAction::Mint { amount, .. } => *amount > self.large_mint_threshold,
+                AdminAction::SetCap { .. }
+                | AdminAction::ForceTransfer { .. }
+                | AdminAction::TransferOwnership { .. }
+                | AdminAction::SetTimelock { .. }
+                | AdminAction::SetCodeHash { .. } => true,
+                _ => false,
+            }
+        }
+
+        #[inline]
+        fn not_timelocked(&self, action: &AdminAction) -> Result<()> {
+            if self.is_timelocked(action) {
+                return Err(Error::TimelockRequired);
+            }
+            Ok(())
+        }
+
+        /// Enables the timelock. Mints above `large_mint_threshold` and the other
+        /// sensitive actions must then be scheduled and wait `delay` milliseconds.
+        #[ink(message)]
+        pub fn set_timelock(&mut self, delay: Timestamp, large_mint_threshold: u128) -> Result<()> {
+            self.only_owner_direct()?;
+            self.not_timelocked(&AdminAction::SetTimelock {
+                delay,
+                large_mint_threshold,
+            })?;
+            self._set_timelock(delay, large_mint_threshold);
+            Ok(())
+        }
+
+        fn _set_timelock(&mut self, delay: Timestamp, large_mint_threshold: u128) {
+            self.timelock_delay = delay;
+            self.large_mint_threshold = large_mint_threshold;
+
+            self.audit(AuditOperation::Config, &(delay, large_mint_threshold));
+            self.env().emit_event(TimelockUpdated {
+                delay,
+                large_mint_threshold,
+            });
+        }
+
+        #[ink(message)]
+        pub fn timelock_delay(&self) -> Timestamp {
+            self.timelock_delay
+        }
+
+        #[ink(message)]
+        pub fn scheduled_operation(&self, id: u64) -> Option<ScheduledOperation> {
+            self.scheduled.get(id)
+        }
+
+        /// Queues an admin action to become executable after the timelock delay
+        #[ink(message)]
+        pub fn schedule_admin_action(&mut self, action: AdminAction) -> Result<u64> {
+            self.only_owner_direct()?;
+            self.schedule(action)
+        }
+
+        fn schedule(&mut self, action: AdminAction) -> Result<u64> {
+            let id = self.next_operation_id;
+            self.next_operation_id = id.checked_add(1).ok_or(Error::Overflow)?;
+
+            let eta = self
+                .env()
+                .block_timestamp()
+                .checked_add(self.timelock_delay)
+                .ok_or(Error::Overflow)?;
+
+            self.scheduled.insert(
+                id,
+                &ScheduledOperation {
+                    action: action.clone(),
+                    eta,
+                },
+            );
+
+            self.env()
+                .emit_event(OperationScheduled { id, action, eta });
+
+            Ok(id)
+        }
+
+        /// Executes a queued action once its delay has passed. Anyone may trigger it.
+        #[ink(message)]
+        pub fn execute_scheduled(&mut self, id: u64) -> Result<()> {
+            if self.renounced {
+                return Err(Error::OwnershipRenounced);
+            }
+
+            let operation = self.scheduled.get(id).ok_or(Error::OperationNotFound)?;
+            if self.env().block_timestamp() < operation.eta {
+                return Err(Error::OperationNotReady);
+            }
+
+            self.execute_admin_action(operation.action)?;
+            self.scheduled.remove(id);
+
+            self.env().emit_event(OperationExecuted { id });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn cancel_scheduled(&mut self, id: u64) -> Result<()> {
+            self.only_owner_direct()?;
+            self._cancel_operation(id)
+        }
+
+        fn _cancel_operation(&mut self, id: u64) -> Result<()> {
+            if !self.scheduled.contains(id) {
+                return Err(Error::OperationNotFound);
+            }
+            self.scheduled.remove(id);
+
+            self.audit(AuditOperation::Config, &id);
+            self.env().emit_event(OperationCancelled { id });
+
+            Ok(())
+        }
+    }
+
+    impl PSP22 for Token {
+        #[ink(message)]
+        fn total_supply(&self) -> u128 {
+            self.total_supply
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u128 {
+            let balance = self.balances.get(owner).unwrap_or(0);
+            balance.saturating_sub(self.demurrage_owed(owner))
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> u128 {
+            let allowance = self.allowance_details(owner, spender);
+            if allowance
+                .expires_at
+                .is_some_and(|expires_at| self.env().block_timestamp() >= expires_at)
+            {
+                return 0;
+            }
+            allowance.value
+        }
+
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            to: AccountId,
+            value: u128,
+            data: Vec<u8>,
+        ) -> core::result::Result<(), PSP22Error> {
+            Ok(self.transfer_with_data(to, value, data)?)
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: u128,
+            data: Vec<u8>,
+        ) -> core::result::Result<(), PSP22Error> {
+            Ok(self.transfer_from_with_data(from, to, value, data)?)
+        }
+
+        #[ink(message)]
+        fn approve(
+            &mut self,
+            spender: AccountId,
+            value: u128,
+        ) -> core::result::Result<(), PSP22Error> {
+            Ok(self.approve_until(spender, value, None)?)
+        }
+
+        #[ink(message)]
+        fn increase_allowance(
+            &mut self,
+            spender: AccountId,
+            delta_value: u128,
+        ) -> core::result::Result<(), PSP22Error> {
+            let owner = self.env().caller();
+            let current_allowance = self.allowance(owner, spender);
+            let new_allowance = current_allowance
+                .checked_add(delta_value)
+                .ok_or(Error::Overflow)?;
+
+            let expires_at = self.live_expiry(owner, spender);
+            Ok(self.approve_until(spender, new_allowance, expires_at)?)
+        }
+
+        #[ink(message)]
+        fn decrease_allowance(
+            &mut self,
+            spender: AccountId,
+            delta_value: u128,
+        ) -> core::result::Result<(), PSP22Error> {
+            let owner = self.env().caller();
+            let current_allowance = self.allowance(owner, spender);
+
+            if current_allowance < delta_value {
+                return Err(PSP22Error::InsufficientAllowance);
+            }
+
+            let new_allowance = current_allowance
+                .checked_sub(delta_value)
+                .ok_or(Error::Overflow)?;
+            let expires_at = self.live_expiry(owner, spender);
+            Ok(self.approve_until(spender, new_allowance, expires_at)?)
+        }
+    }
+
+    impl PSP22Metadata for Token {
+        #[ink(message)]
+        fn token_name(&self) -> Option<String> {
+            self.name.clone()
+        }
+
+        #[ink(message)]
+        fn token_symbol(&self) -> Option<String> {
+            self.symbol.clone()
+        }
+
+        #[ink(message)]
+        fn token_decimals(&self) -> u8 {
+            self.decimals
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn get_accounts() -> ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment> {
+            ink::env::test::default_accounts::<ink::env::DefaultEnvironment>()
+        }
+
+        #[ink::test]
+        fn test_mint() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            assert_eq!(token.balance_of(accounts.bob), 0);
+            assert_eq!(token.total_supply(), 0);
+
+            // Mint tokens
+            token.mint(accounts.bob, 1000).unwrap();
+
+            assert_eq!(token.balance_of(accounts.bob), 1000);
+            assert_eq!(token.total_supply(), 1000);
+
+            // Mint more to same account
+            token.mint(accounts.bob, 500).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 1500);
+            assert_eq!(token.total_supply(), 1500);
+        }
+
+        #[ink::test]
+        fn test_minter_quota() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.set_minter_quota(accounts.bob, 500).unwrap();
+            assert_eq!(token.minter_quota_of(accounts.bob), Some(500));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.mint(accounts.charlie, 300).unwrap();
+            assert_eq!(token.balance_of(accounts.charlie), 300);
+            assert_eq!(token.minter_quota_of(accounts.bob), Some(200));
+
+            // Cannot go beyond the remaining quota
+            assert_eq!(
+                token.mint(accounts.charlie, 201),
+                Err(Error::MinterQuotaExceeded)
+            );
+            token.mint(accounts.charlie, 200).unwrap();
+            assert_eq!(token.minter_quota_of(accounts.bob), Some(0));
+
+            // Minters can't manage quotas
+            assert_eq!(
+                token.set_minter_quota(accounts.bob, 1000),
+                Err(Error::Unauthorized)
+            );
+
+            // Accounts without a role can't mint at all
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(token.mint(accounts.django, 1), Err(Error::Unauthorized));
+        }
+
+        #[ink::test]
+        fn test_faucet_cooldown() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.claim_faucet(), Err(Error::FaucetDisabled));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.set_faucet(50, 1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.claim_faucet(), Ok(50));
+            assert_eq!(token.claim_faucet(), Err(Error::FaucetCooldown));
+            assert_eq!(token.next_faucet_claim(accounts.bob), 1_000);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            assert_eq!(token.claim_faucet(), Ok(50));
+            assert_eq!(token.balance_of(accounts.bob), 100);
+            assert_eq!(token.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn test_revoke_minter_quota() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.set_minter_quota(accounts.bob, 500).unwrap();
+            token.set_minter_quota(accounts.bob, 0).unwrap();
+            assert_eq!(token.minter_quota_of(accounts.bob), None);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.mint(accounts.bob, 1), Err(Error::Unauthorized));
+        }
+
+        #[ink::test]
+        fn test_token_metadata() {
+            let token = Token::new_with_metadata(
+                Some(String::from("Simple Bank Token")),
+                Some(String::from("SBT")),
+                10,
+            );
+
+            assert_eq!(token.token_name(), Some(String::from("Simple Bank Token")));
+            assert_eq!(token.token_symbol(), Some(String::from("SBT")));
+            assert_eq!(token.token_decimals(), 10);
+
+            // Default constructor leaves metadata unset
+            let token = Token::new();
+            assert_eq!(token.token_name(), None);
+            assert_eq!(token.token_symbol(), None);
+            assert_eq!(token.token_decimals(), 0);
+        }
+
+        #[ink::test]
+        fn test_new_with_supply() {
+            let accounts = get_accounts();
+            let token = Token::new_with_supply(1_000_000).unwrap();
+
+            assert_eq!(token.balance_of(accounts.alice), 1_000_000);
+            assert_eq!(token.total_supply(), 1_000_000);
+            assert_eq!(token.owner(), accounts.alice);
+
+            let event = last_transfer_event();
+            assert_eq!(event.from, None);
+            assert_eq!(event.to, Some(accounts.alice));
+            assert_eq!(event.value, 1_000_000);
+
+            // Zero supply deploys an empty token
+            let token = Token::new_with_supply(0).unwrap();
+            assert_eq!(token.total_supply(), 0);
+        }
+
+        #[ink::test]
+        fn test_new_configured() {
+            let accounts = get_accounts();
+            let token = Token::new_configured(
+                Some(String::from("Simple Bank Token")),
+                Some(String::from("SBT")),
+                12,
+                Some(10_000),
+                ink::prelude::vec![(accounts.bob, 4000), (accounts.charlie, 6000)],
+            )
+            .unwrap();
+
+            assert_eq!(token.token_symbol(), Some(String::from("SBT")));
+            assert_eq!(token.token_decimals(), 12);
+            assert_eq!(token.balance_of(accounts.bob), 4000);
+            assert_eq!(token.balance_of(accounts.charlie), 6000);
+            assert_eq!(token.total_supply(), 10_000);
+        }
+
+        #[ink::test]
+        fn test_new_configured_validation() {
+            let accounts = get_accounts();
+
+            let duplicate = ink::prelude::vec![(accounts.bob, 100), (accounts.bob, 100)];
+            assert_eq!(
+                Token::new_configured(None, None, 0, None, duplicate).err(),
+                Some(Error::DuplicateAllocation)
+            );
+
+            // Instances share off-chain storage, so use fresh accounts for each case
+            let above_cap = ink::prelude::vec![(accounts.charlie, 600), (accounts.django, 600)];
+            assert_eq!(
+                Token::new_configured(None, None, 0, Some(1000), above_cap).err(),
+                Some(Error::CapExceeded)
+            );
+
+            let overflow = ink::prelude::vec![(accounts.eve, u128::MAX), (accounts.frank, 1)];
+            assert_eq!(
+                Token::new_configured(None, None, 0, None, overflow).err(),
+                Some(Error::Overflow)
+            );
+        }
+
+        #[ink::test]
+        fn test_cap_limits_minting() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            assert_eq!(token.cap(), None);
+            token.set_cap(1000).unwrap();
+            assert_eq!(token.cap(), Some(1000));
+
+            token.mint(accounts.bob, 900).unwrap();
+            assert_eq!(token.mint(accounts.bob, 101), Err(Error::CapExceeded));
+
+            let recipients = ink::prelude::vec![(accounts.charlie, 50), (accounts.django, 51)];
+            assert_eq!(token.batch_mint(recipients), Err(Error::CapExceeded));
+            assert_eq!(token.balance_of(accounts.charlie), 0);
+
+            // Burning frees room under the cap
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.burn(100).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.mint(accounts.charlie, 200).unwrap();
+            assert_eq!(token.total_supply(), 1000);
+        }
+
+        #[ink::test]
+        fn test_set_cap_only_downward() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.bob, 500).unwrap();
+            token.set_cap(1000).unwrap();
+
+            assert_eq!(token.set_cap(2000), Err(Error::CapExceeded));
+            assert_eq!(token.set_cap(499), Err(Error::CapExceeded));
+            token.set_cap(500).unwrap();
+            assert_eq!(token.cap(), Some(500));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.set_cap(400), Err(Error::Unauthorized));
+        }
+
+        #[ink::test]
+        fn test_burn() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            // Setup: mint some tokens
+            token.mint(accounts.alice, 1000).unwrap();
+
+            // Burn tokens
+            token.burn(300).unwrap();
+
+            assert_eq!(token.balance_of(accounts.alice), 700);
+            assert_eq!(token.total_supply(), 700);
+
+            // Burn more
+            token.burn(200).unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 500);
+            assert_eq!(token.total_supply(), 500);
+        }
+
+        #[ink::test]
+        fn test_burn_from() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1000).unwrap();
+            token.approve(accounts.bob, 300).unwrap();
+
+            // Bob burns from alice using his allowance
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.burn_from(accounts.alice, 200).unwrap();
+
+            assert_eq!(token.balance_of(accounts.alice), 800);
+            assert_eq!(token.total_supply(), 800);
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 100);
+
+            let event = last_transfer_event();
+            assert_eq!(event.from, Some(accounts.alice));
+            assert_eq!(event.to, None);
+            assert_eq!(event.value, 200);
+        }
+
+        #[ink::test]
+        fn test_burn_from_insufficient_allowance() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1000).unwrap();
+            token.approve(accounts.bob, 100).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.burn_from(accounts.alice, 200),
+                Err(Error::InsufficientAllowance)
+            );
+            assert_eq!(token.balance_of(accounts.alice), 1000);
+        }
+
+        #[ink::test]
+        fn test_deposit_and_withdraw() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(600);
+            token.deposit().unwrap();
+
+            assert_eq!(token.balance_of(accounts.bob), 600);
+            assert_eq!(token.total_supply(), 600);
+            assert_eq!(native_balance(contract), 600);
+
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            token.withdraw(250).unwrap();
+
+            assert_eq!(token.balance_of(accounts.bob), 350);
+            assert_eq!(token.total_supply(), 350);
+            assert_eq!(native_balance(contract), 350);
+            assert_eq!(native_balance(accounts.bob), 1000 - 600 + 250);
+        }
+
+        #[ink::test]
+        fn test_bonding_curve() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.quote_curve_buy(100), Err(Error::BondingCurveDisabled));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let curve = BondingCurve {
+                base_price: CURVE_PRICE_SCALE,
+                slope: CURVE_PRICE_SCALE / 100,
+            };
+            token.set_bonding_curve(Some(curve)).unwrap();
+
+            // 100 units at 1 native rising by 0.01 per unit, the excess refunded
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.quote_curve_buy(100), Ok(150));
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(149);
+            assert_eq!(token.buy_from_curve(100), Err(Error::InsufficientPayment));
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(200);
+            assert_eq!(token.buy_from_curve(100), Ok(150));
+            assert_eq!(token.balance_of(accounts.bob), 100);
+            assert_eq!(token.curve_reserve(), 150);
+
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(token.quote_curve_sell(50), Ok(87));
+            assert_eq!(token.sell_to_curve(50, 88), Err(Error::SlippageExceeded));
+            let native_before = native_balance(accounts.bob);
+            assert_eq!(token.sell_to_curve(50, 87), Ok(87));
+            assert_eq!(native_balance(accounts.bob), native_before + 87);
+            assert_eq!((token.curve_supply(), token.curve_reserve()), (50, 63));
+            assert!(native_balance(contract) >= 63);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(token.set_bonding_curve(None), Err(Error::BondingCurveInUse));
+        }
+
+        #[ink::test]
+        fn test_token_sale() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            assert_eq!(token.create_sale(2, 0, 1_000, 500, 300, true), Ok(1));
+            assert_eq!(token.balance_of(accounts.alice), 500);
+            token.add_to_whitelist(accounts.bob).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(10);
+            assert_eq!(token.buy(), Err(Error::NotWhitelisted));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(100);
+            assert_eq!(token.buy(), Ok(200));
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(51);
+            assert_eq!(token.buy(), Err(Error::SaleCapExceeded));
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(50);
+            assert_eq!(token.buy(), Ok(100));
+            assert_eq!(token.sale_purchased(accounts.bob), 300);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(token.reclaim_unsold(), Err(Error::SaleNotEnded));
+
+            // Once closed, the owner takes back the rest and the proceeds
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            let native_before = native_balance(accounts.alice);
+            assert_eq!(token.reclaim_unsold(), Ok(200));
+            assert_eq!(token.withdraw_sale_proceeds(), Ok(150));
+            assert_eq!(token.withdraw_sale_proceeds(), Err(Error::NothingToClaim));
+            assert_eq!(token.balance_of(accounts.alice), 700);
+            assert_eq!(native_balance(accounts.alice), native_before + 150);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(10);
+            assert_eq!(token.buy(), Err(Error::SaleNotActive));
+        }
+
+        #[ink::test]
+        fn test_swap_pair_reserve() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            assert_eq!(token.swap_to_pair(10), Err(Error::SwapNotConfigured));
+            assert_eq!(
+                token.set_swap_pair(Some(SwapPair {
+                    token: contract,
+                    rate: 3
+                })),
+                Err(Error::NotAContract)
+            );
+
+            token.fund_swap_reserve(400).unwrap();
+            assert_eq!(token.swap_reserve(), 400);
+            assert_eq!(token.balance_of(contract), 400);
+            token.withdraw_swap_reserve(100).unwrap();
+            assert_eq!(
+                token.withdraw_swap_reserve(301),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(token.balance_of(accounts.alice), 700);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "off-chain environment does not support contract invocation")]
+        fn test_swap_to_pair_calls_pair_token() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            ink::env::test::set_contract::<ink::env::DefaultEnvironment>(accounts.django);
+            token
+                .set_swap_pair(Some(SwapPair {
+                    token: accounts.django,
+                    rate: 3,
+                }))
+                .unwrap();
+            let _ = token.swap_to_pair(10);
+        }
+
+        #[ink::test]
+        fn test_dvp_seller_leg_refund() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            ink::env::test::set_contract::<ink::env::DefaultEnvironment>(accounts.django);
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            assert_eq!(
+                token.create_dvp(accounts.alice, accounts.bob, 300, contract, 50, 2_000),
+                Err(Error::NotAContract)
+            );
+            assert_eq!(
+                token.create_dvp(
+                    accounts.bob,
+                    accounts.charlie,
+                    300,
+                    accounts.django,
+                    50,
+                    2_000
+                ),
+                Err(Error::Unauthorized)
+            );
+            let id = token
+                .create_dvp(
+                    accounts.alice,
+                    accounts.bob,
+                    300,
+                    accounts.django,
+                    50,
+                    2_000,
+                )
+                .unwrap();
+
+            token.fund_dvp(id).unwrap();
+            assert_eq!(token.fund_dvp(id), Err(Error::DvpNotFound));
+            assert_eq!(token.balance_of(contract), 300);
+            assert!(token.dvp_trade(id).unwrap().seller_funded);
+            assert_eq!(token.refund_dvp(id), Err(Error::DvpNotExpired));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(2_000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.fund_dvp(id), Err(Error::DvpExpired));
+            token.refund_dvp(id).unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 1_000);
+            assert_eq!(token.dvp_trade(id), None);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "off-chain environment does not support contract invocation")]
+        fn test_dvp_buyer_leg_calls_asset() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            ink::env::test::set_contract::<ink::env::DefaultEnvironment>(accounts.django);
+            let id = token
+                .create_dvp(
+                    accounts.alice,
+                    accounts.bob,
+                    300,
+                    accounts.django,
+                    50,
+                    2_000,
+                )
+                .unwrap();
+            token.fund_dvp(id).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let _ = token.fund_dvp(id);
+        }
+
+        #[ink::test]
+        fn test_htlc_claim() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            let hashlock = htlc_hashlock(b"swap secret");
+            token
+                .lock_with_hash(accounts.bob, 300, hashlock, 10_000)
+                .unwrap();
+            assert_eq!(
+                token.lock_with_hash(accounts.bob, 100, hashlock, 10_000),
+                Err(Error::HtlcExists)
+            );
+            assert_eq!(token.balance_of(contract), 300);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(
+                token.claim(b"wrong secret".to_vec()),
+                Err(Error::HtlcNotFound)
+            );
+            token.claim(b"swap secret".to_vec()).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 300);
+            assert_eq!(token.balance_of(contract), 0);
+            assert_eq!(token.htlc(hashlock), None);
+        }
+
+        #[ink::test]
+        fn test_htlc_refund_after_timelock() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            let hashlock = htlc_hashlock(b"swap secret");
+            token
+                .lock_with_hash(accounts.bob, 300, hashlock, 10_000)
+                .unwrap();
+            assert_eq!(token.refund_htlc(hashlock), Err(Error::HtlcNotExpired));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10_000);
+            assert_eq!(
+                token.claim(b"swap secret".to_vec()),
+                Err(Error::HtlcExpired)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.refund_htlc(hashlock), Err(Error::Unauthorized));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.refund_htlc(hashlock).unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 1_000);
+            assert_eq!(token.balance_of(contract), 0);
+        }
+
+        #[ink::test]
+        fn test_deposit_and_withdraw_invalid_amounts() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            set_contract_account();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(100);
+            token.deposit().unwrap();
+
+            assert_eq!(token.withdraw(101), Err(Error::InsufficientBalance));
+
+            // Depositing nothing doesn't mint
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(token.deposit(), Err(Error::InvalidAmount));
+        }
+
+        #[ink::test]
+        fn test_transfer() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            // Setup: mint tokens to alice
+            token.mint(accounts.alice, 1000).unwrap();
+
+            // Transfer to bob
+            token.transfer(accounts.bob, 300, Vec::new()).unwrap();
+
+            assert_eq!(token.balance_of(accounts.alice), 700);
+            assert_eq!(token.balance_of(accounts.bob), 300);
+            assert_eq!(token.total_supply(), 1000); // Total unchanged
+
+            // Transfer to charlie
+            token.transfer(accounts.charlie, 200, Vec::new()).unwrap();
+
+            assert_eq!(token.balance_of(accounts.alice), 500);
+            assert_eq!(token.balance_of(accounts.charlie), 200);
+        }
+
+        fn ecdsa_account(secret_key: &secp256k1::SecretKey) -> AccountId {
+            let public_key = secret_key.public_key(secp256k1::SECP256K1).serialize();
+            let mut output = [0u8; 32];
+            ink::env::hash_bytes::<Blake2x256>(&public_key, &mut output);
+            AccountId::from(output)
+        }
+
+        fn ecdsa_sign(secret_key: &secp256k1::SecretKey, message_hash: [u8; 32]) -> [u8; 65] {
+            let message = secp256k1::Message::from_digest_slice(&message_hash).unwrap();
+            let (recovery_id, compact) = secp256k1::SECP256K1
+                .sign_ecdsa_recoverable(&message, secret_key)
+                .serialize_compact();
+            let mut signature = [0u8; 65];
+            signature[..64].copy_from_slice(&compact);
+            signature[64] = recovery_id.to_i32() as u8;
+            signature
+        }
+
+        fn sr25519_keypair(seed: u8) -> schnorrkel::Keypair {
+            schnorrkel::MiniSecretKey::from_bytes(&[seed; 32])
+                .unwrap()
+                .expand_to_keypair(schnorrkel::ExpansionMode::Ed25519)
+        }
+
+        fn sr25519_account(keypair: &schnorrkel::Keypair) -> AccountId {
+            AccountId::from(keypair.public.to_bytes())
+        }
+
+        fn sr25519_sign(keypair: &schnorrkel::Keypair, message: &[u8]) -> [u8; 64] {
+            keypair.sign_simple(b"substrate", message).to_bytes()
+        }
+
+        /// Gives the contract its own account, distinct from the default test accounts
+        fn set_contract_account() -> AccountId {
+            let contract = AccountId::from([0xC0; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            contract
+        }
+
+        fn gift_code_hash(code: &[u8]) -> Hash {
+            let mut output = [0u8; 32];
+            ink::env::hash_bytes::<Blake2x256>(code, &mut output);
+            Hash::from(output)
+        }
+
+        fn htlc_hashlock(preimage: &[u8]) -> Hash {
+            let mut output = [0u8; 32];
+            ink::env::hash_bytes::<Sha2x256>(preimage, &mut output);
+            Hash::from(output)
+        }
+
+        fn native_balance(account: AccountId) -> Balance {
+            ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(account).unwrap()
+        }
+
+        fn last_transfer_event() -> Transfer {
+            let signature =
+                <Transfer as ink::env::Event>::SIGNATURE_TOPIC.map(|topic| topic.to_vec());
+            let event = ink::env::test::recorded_events()
+                .filter(|event| event.topics.first() == signature.as_ref())
+                .last()
+                .expect("no Transfer event recorded");
+            <Transfer as ink::scale::Decode>::decode(&mut &event.data[..])
+                .expect("invalid Transfer event")
+        }
+
+        #[ink::test]
+        fn test_wipe_blacklisted() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 500).unwrap();
+            token.mint(accounts.bob, 1000).unwrap();
+            token.freeze(accounts.bob, 300).unwrap();
+
+            assert_eq!(
+                token.wipe_blacklisted(accounts.bob),
+                Err(Error::NotBlacklisted)
+            );
+
+            token.blacklist(accounts.bob).unwrap();
+            token.wipe_blacklisted(accounts.bob).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 0);
+            assert_eq!(token.frozen_balance_of(accounts.bob), 0);
+            assert_eq!(token.total_supply(), 500);
+
+            // Nothing left to wipe
+            assert_eq!(
+                token.wipe_blacklisted(accounts.bob),
+                Err(Error::InvalidAmount)
+            );
+        }
+
+        #[ink::test]
+        fn test_force_transfer() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.bob, 1000).unwrap();
+            token.freeze(accounts.bob, 800).unwrap();
+            token.blacklist(accounts.bob).unwrap();
+            token.set_compliance_officer(Some(accounts.eve)).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                token.force_transfer(accounts.bob, accounts.charlie, 100),
+                Err(Error::Unauthorized)
+            );
+
+            // Seizure ignores blacklist and freeze; the freeze shrinks to what's left
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(
+                token.force_transfer(accounts.bob, accounts.eve, 900),
+                Ok(None)
+            );
+            assert_eq!(token.balance_of(accounts.bob), 100);
+            assert_eq!(token.balance_of(accounts.eve), 900);
+            assert_eq!(token.frozen_balance_of(accounts.bob), 100);
+            assert_eq!(
+                token.force_transfer(accounts.bob, accounts.eve, 101),
+                Err(Error::InsufficientBalance)
+            );
+        }
+
+        #[ink::test]
+        fn test_force_transfer_is_timelocked() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.bob, 1000).unwrap();
+            token.set_timelock(1000, u128::MAX).unwrap();
+
+            let id = token
+                .force_transfer(accounts.bob, accounts.alice, 500)
+                .unwrap()
+                .unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 1000);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1000);
+            token.execute_scheduled(id).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 500);
+            assert_eq!(token.balance_of(accounts.alice), 500);
+        }
+
+        #[ink::test]
+        fn test_audit_log() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.bob, 100).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(50);
+            token.blacklist(accounts.charlie).unwrap();
+            token.pause().unwrap();
+            // Holder actions are not logged
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.approve(accounts.django, 10).unwrap();
+
+            assert_eq!(token.audit_log_len(), 3);
+            let log = token.audit_log(0, 10);
+            assert_eq!(log.len(), 3);
+            assert_eq!(
+                log[0].1,
+                AuditEntry {
+                    caller: accounts.alice,
+                    timestamp: 0,
+                    operation: AuditOperation::Mint,
+                    params: ink::scale::Encode::encode(&(accounts.bob, 100u128)),
+                }
+            );
+            assert_eq!(log[1].1.operation, AuditOperation::Blacklist);
+            assert_eq!(log[1].1.timestamp, 50);
+            assert_eq!(log[2].1.operation, AuditOperation::Pause);
+
+            let page = token.audit_log(1, 1);
+            assert_eq!(page.len(), 1);
+            assert_eq!(page[0].0, 1);
+        }
+
+        #[ink::test]
+        fn test_audit_log_is_bounded() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            for _ in 0..AUDIT_LOG_CAPACITY + 2 {
+                token.mint(accounts.bob, 1).unwrap();
+            }
+
+            assert_eq!(token.audit_log_len(), AUDIT_LOG_CAPACITY + 2);
+            // The two oldest entries were overwritten
+            let log = token.audit_log(0, 3);
+            assert_eq!(log.len(), 1);
+            assert_eq!(log[0].0, 2);
+        }
+
+        #[ink::test]
+        fn test_self_lock() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.bob, 1000).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+
+            // Nothing to unlock yet
+            assert_eq!(
+                token.unlock_my_account_after(MIN_SELF_UNLOCK_DELAY),
+                Err(Error::InvalidAmount)
+            );
+
+            token.lock_my_account().unwrap();
+            assert!(token.is_account_locked(accounts.bob));
+            assert_eq!(
+                token.transfer(accounts.charlie, 100, Vec::new()),
+                Err(Error::AccountLocked.into())
+            );
+
+            // Incoming transfers still work
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.mint(accounts.alice, 100).unwrap();
+            token.transfer(accounts.bob, 100, Vec::new()).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.unlock_my_account_after(1), Err(Error::InvalidAmount));
+            token
+                .unlock_my_account_after(MIN_SELF_UNLOCK_DELAY * 2)
+                .unwrap();
+            // A pending unlock can't be brought forward
+            assert_eq!(
+                token.unlock_my_account_after(MIN_SELF_UNLOCK_DELAY),
+                Err(Error::InvalidAmount)
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                MIN_SELF_UNLOCK_DELAY * 2,
+            );
+            assert!(!token.is_account_locked(accounts.bob));
+            token.transfer(accounts.charlie, 100, Vec::new()).unwrap();
+        }
+
+        #[ink::test]
+        fn test_inheritance() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            const DAY: Timestamp = 24 * 60 * 60 * 1000;
+            token.mint(accounts.bob, 1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.set_beneficiary(accounts.charlie, MIN_INACTIVITY_PERIOD - 1),
+                Err(Error::InvalidAmount)
+            );
+            token
+                .set_beneficiary(accounts.charlie, MIN_INACTIVITY_PERIOD)
+                .unwrap();
+
+            // A transfer ten days in resets the timer
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10 * DAY);
+            token.transfer(accounts.django, 10, Vec::new()).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(
+                token.claim_inheritance(accounts.bob),
+                Err(Error::NoBeneficiary)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                MIN_INACTIVITY_PERIOD,
+            );
+            assert_eq!(
+                token.claim_inheritance(accounts.bob),
+                Err(Error::HolderStillActive)
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                10 * DAY + MIN_INACTIVITY_PERIOD,
+            );
+            assert_eq!(token.claim_inheritance(accounts.bob), Ok(990));
+            assert_eq!(token.balance_of(accounts.charlie), 990);
+            assert_eq!(token.balance_of(accounts.bob), 0);
+            assert_eq!(token.inheritance_of(accounts.bob), None);
+        }
+
+        #[ink::test]
+        fn test_inheritance_check_in() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            token.mint(accounts.bob, 1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.check_in(), Err(Error::NoBeneficiary));
+            token
+                .set_beneficiary(accounts.charlie, MIN_INACTIVITY_PERIOD)
+                .unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                MIN_INACTIVITY_PERIOD - 1,
+            );
+            token.check_in().unwrap();
+            assert_eq!(
+                token.inheritance_of(accounts.bob).unwrap().last_active,
+                MIN_INACTIVITY_PERIOD - 1
+            );
+
+            token.remove_beneficiary().unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                3 * MIN_INACTIVITY_PERIOD,
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                token.claim_inheritance(accounts.bob),
+                Err(Error::NoBeneficiary)
+            );
+        }
+
+        #[ink::test]
+        fn test_social_recovery() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            token.mint(accounts.bob, 1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.set_recovery_guardians(vec![accounts.charlie, accounts.charlie], 1),
+                Err(Error::DuplicateGuardian)
+            );
+            token
+                .set_recovery_guardians(vec![accounts.alice, accounts.charlie, accounts.django], 2)
+                .unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(
+                token.approve_recovery(accounts.bob, accounts.eve),
+                Err(Error::NotRecoveryGuardian)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token
+                .approve_recovery(accounts.bob, accounts.frank)
+                .unwrap();
+            assert_eq!(
+                token.execute_recovery(accounts.bob),
+                Err(Error::RecoveryNotReady)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            token
+                .approve_recovery(accounts.bob, accounts.frank)
+                .unwrap();
+            assert_eq!(
+                token.recovery(accounts.bob).unwrap().executable_at,
+                Some(RECOVERY_DELAY)
+            );
+            assert_eq!(
+                token.execute_recovery(accounts.bob),
+                Err(Error::RecoveryNotReady)
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(RECOVERY_DELAY);
+            assert_eq!(token.execute_recovery(accounts.bob), Ok(1_000));
+            assert_eq!(token.balance_of(accounts.frank), 1_000);
+            assert_eq!(token.recovery(accounts.bob), None);
+        }
+
+        #[ink::test]
+        fn test_holder_cancels_recovery() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            token.mint(accounts.bob, 1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token
+                .set_recovery_guardians(vec![accounts.charlie], 1)
+                .unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token.approve_recovery(accounts.bob, accounts.eve).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.cancel_recovery().unwrap();
+            assert_eq!(token.cancel_recovery(), Err(Error::RecoveryNotFound));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(RECOVERY_DELAY);
+            assert_eq!(
+                token.execute_recovery(accounts.bob),
+                Err(Error::RecoveryNotFound)
+            );
+            assert_eq!(token.balance_of(accounts.bob), 1_000);
+        }
+
+        #[ink::test]
+        fn test_freeze_partial_balance() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.bob, 1000).unwrap();
+            token.freeze(accounts.bob, 600).unwrap();
+            assert_eq!(token.frozen_balance_of(accounts.bob), 600);
+            assert_eq!(token.spendable_balance_of(accounts.bob), 400);
+
+            // Can't freeze more than the balance
+            assert_eq!(
+                token.freeze(accounts.bob, 401),
+                Err(Error::InsufficientBalance)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.transfer(accounts.charlie, 401, Vec::new()),
+                Err(PSP22Error::InsufficientBalance)
+            );
+            assert_eq!(token.burn(401), Err(Error::InsufficientBalance));
+            assert_eq!(token.unfreeze(accounts.bob, 600), Err(Error::Unauthorized));
+
+            // transfer_all only moves the spendable part
+            token.transfer_all(accounts.charlie).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 600);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.unfreeze(accounts.bob, 600).unwrap();
+            assert_eq!(token.spendable_balance_of(accounts.bob), 600);
+        }
+
+        #[ink::test]
+        fn test_whitelist_mode() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1000).unwrap();
+            token.set_whitelist_mode(true).unwrap();
+            token.add_to_whitelist(accounts.alice).unwrap();
+            token.add_to_whitelist(accounts.bob).unwrap();
+            assert!(token.is_whitelisted(accounts.bob));
+
+            token.transfer(accounts.bob, 100, Vec::new()).unwrap();
+            assert_eq!(
+                token.transfer(accounts.charlie, 100, Vec::new()),
+                Err(Error::NotWhitelisted.into())
+            );
+
+            // Removed accounts can no longer send
+            token.remove_from_whitelist(accounts.bob).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.transfer(accounts.alice, 50, Vec::new()),
+                Err(Error::NotWhitelisted.into())
+            );
+            assert_eq!(
+                token.add_to_whitelist(accounts.bob),
+                Err(Error::Unauthorized)
+            );
+
+            // Turning the mode off lifts the restriction
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.set_whitelist_mode(false).unwrap();
+            token.transfer(accounts.charlie, 100, Vec::new()).unwrap();
+        }
+
+        #[ink::test]
+        fn test_kyc_tier_limits() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.set_compliance_officer(Some(accounts.eve)).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            token
+                .set_tier_limits(
+                    KycTier::Unverified,
+                    TierLimits {
+                        max_transfer: Some(100),
+                        max_balance: Some(500),
+                    },
+                )
+                .unwrap();
+            token.set_tier(accounts.alice, KycTier::Full).unwrap();
+            assert_eq!(token.tier_of(accounts.alice), KycTier::Full);
+            assert_eq!(token.tier_of(accounts.bob), KycTier::Unverified);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.mint(accounts.alice, 10_000).unwrap();
+
+            // Unverified accounts can't hold more than 500
+            assert_eq!(token.mint(accounts.bob, 501), Err(Error::TierLimitExceeded));
+            token.transfer(accounts.bob, 400, Vec::new()).unwrap();
+            assert_eq!(token.remaining_limits(accounts.bob), (Some(100), Some(100)));
+            assert_eq!(
+                token.transfer(accounts.bob, 101, Vec::new()),
+                Err(Error::TierLimitExceeded.into())
+            );
+
+            // Nor send more than 100 at once
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.transfer(accounts.charlie, 101, Vec::new()),
+                Err(Error::TierLimitExceeded.into())
+            );
+            token.transfer(accounts.charlie, 100, Vec::new()).unwrap();
+            assert_eq!(token.remaining_limits(accounts.alice), (None, None));
+        }
+
+        #[ink::test]
+        fn test_set_tier_requires_compliance() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.set_tier(accounts.bob, KycTier::Full),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                token.set_tier_limits(KycTier::Basic, TierLimits::default()),
+                Err(Error::Unauthorized)
+            );
+
+            // The owner acts as compliance officer until one is appointed
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.set_tier(accounts.bob, KycTier::Basic).unwrap();
+            assert_eq!(token.tier_of(accounts.bob), KycTier::Basic);
+        }
+
+        #[ink::test]
+        fn test_transfer_fee() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 10_000).unwrap();
+            token.set_fee_bps(250).unwrap();
+            // No collector, no fee
+            token.transfer(accounts.bob, 1000, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 1000);
+
+            token.set_fee_collector(Some(accounts.eve)).unwrap();
+            token.transfer(accounts.bob, 1000, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 1975);
+            assert_eq!(token.balance_of(accounts.eve), 25);
+            assert_eq!(token.balance_of(accounts.alice), 8000);
+            assert_eq!(token.total_supply(), 10_000);
+
+            // Fees round down
+            token.transfer(accounts.charlie, 39, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.charlie), 39);
+
+            // The collector itself pays no fee
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            token.transfer(accounts.bob, 25, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 2000);
+        }
+
+        #[ink::test]
+        fn test_savings_interest() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 10_000).unwrap();
+            token.set_savings_apr(1_000).unwrap();
+            token.deposit_to_savings(4_000).unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 6_000);
+            assert_eq!(token.balance_of(contract), 4_000);
+            assert_eq!(token.savings_of(accounts.alice), 4_000);
+
+            // Half a year at 10%
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(YEAR / 2);
+            assert_eq!(token.savings_of(accounts.alice), 4_200);
+            assert_eq!(token.total_supply(), 10_000);
+
+            // The rate change applies from now on
+            token.set_savings_apr(0).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(YEAR);
+            assert_eq!(token.savings_of(accounts.alice), 4_200);
+
+            assert_eq!(
+                token.withdraw_from_savings(4_201),
+                Err(Error::InsufficientSavings)
+            );
+            token.withdraw_from_savings(4_200).unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 10_200);
+            assert_eq!(token.balance_of(contract), 0);
+            assert_eq!(token.savings_of(accounts.alice), 0);
+            assert_eq!(token.total_supply(), 10_200);
+        }
+
+        #[ink::test]
+        fn test_savings_validation() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            set_contract_account();
+
+            token.mint(accounts.alice, 100).unwrap();
+            assert_eq!(
+                token.set_savings_apr(MAX_SAVINGS_APR_BPS + 1),
+                Err(Error::InvalidAmount)
+            );
+            assert_eq!(
+                token.deposit_to_savings(101),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(token.deposit_to_savings(0), Err(Error::InvalidAmount));
+
+            // Interest stops at the cap
+            token.set_cap(150).unwrap();
+            token.set_savings_apr(MAX_SAVINGS_APR_BPS).unwrap();
+            token.deposit_to_savings(100).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(YEAR * 10);
+            token.withdraw_from_savings(1).unwrap();
+            assert_eq!(token.total_supply(), 150);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.set_savings_apr(10), Err(Error::Unauthorized));
+        }
+
+        #[ink::test]
+        fn test_overdraft() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            token.mint(accounts.bob, 100).unwrap();
+            token.set_credit_limit(accounts.bob, 500).unwrap();
+            token.set_overdraft_apr(1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.set_credit_limit(accounts.bob, 10_000),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(token.repay_overdraft(10), Err(Error::NoDebt));
+            token.transfer(accounts.charlie, 600, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 0);
+            assert_eq!(token.debt_of(accounts.bob), 500);
+            assert_eq!(token.available_credit(accounts.bob), 0);
+            assert_eq!(token.total_supply(), 1_600);
+            assert_eq!(
+                token.transfer(accounts.charlie, 1, Vec::new()),
+                Err(PSP22Error::InsufficientBalance)
+            );
+
+            // Half a year at 10%
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(YEAR / 2);
+            assert_eq!(token.debt_of(accounts.bob), 525);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.transfer(accounts.bob, 600, Vec::new()).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.repay_overdraft(1_000), Ok(525));
+            assert_eq!(token.balance_of(accounts.bob), 75);
+            assert_eq!(token.debt_of(accounts.bob), 0);
+            assert_eq!(token.available_credit(accounts.bob), 500);
+            assert_eq!(token.total_supply(), 1_075);
+        }
+
+        #[ink::test]
+        fn test_credit_score_gates_overdraft() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            const MONTH: Timestamp = YEAR / 12;
+
+            token.mint(accounts.bob, 1_000).unwrap();
+            token.set_credit_limit(accounts.bob, 500).unwrap();
+            assert_eq!(token.set_min_credit_score(1_001), Err(Error::InvalidAmount));
+            token.set_min_credit_score(300).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            for _ in 0..3 {
+                token.transfer(accounts.charlie, 10, Vec::new()).unwrap();
+            }
+
+            // A month old with three transfers that month
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(MONTH);
+            assert_eq!(token.credit_score(accounts.bob), 25 + 30);
+            assert_eq!(token.credit_score(accounts.django), 0);
+            assert_eq!(
+                token.transfer_with_data(accounts.charlie, 1_000, Vec::new()),
+                Err(Error::CreditScoreTooLow)
+            );
+            // Transfers within the balance aren't gated
+            token.transfer(accounts.charlie, 10, Vec::new()).unwrap();
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(YEAR);
+            assert_eq!(token.credit_score(accounts.bob), 300);
+            token.transfer(accounts.charlie, 1_000, Vec::new()).unwrap();
+            assert_eq!(token.debt_of(accounts.bob), 40);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.mint(accounts.bob, 40).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.repay_overdraft(40).unwrap();
+            assert_eq!(token.credit_score(accounts.bob), 350);
+            assert_eq!(
+                token.credit_history(accounts.bob),
+                Some(CreditHistory {
+                    opened_at: 0,
+                    transfers: 5,
+                    repayments: 1,
+                    liquidations: 0,
+                })
+            );
+        }
+
+        #[ink::test]
+        fn test_collateralized_loan() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+            token.set_loan_to_value(5_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.set_loan_to_value(9_000), Err(Error::Unauthorized));
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(400);
+            token.deposit_collateral().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(token.collateral_of(accounts.bob), 400);
+            assert_eq!(token.borrowing_power(accounts.bob), 200);
+
+            assert_eq!(token.borrow(201), Err(Error::InsufficientCollateral));
+            token.borrow(150).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 150);
+            assert_eq!(token.loan_of(accounts.bob), 150);
+            assert_eq!(token.borrowing_power(accounts.bob), 50);
+
+            // 300 is needed to back a loan of 150
+            assert_eq!(
+                token.withdraw_collateral(101),
+                Err(Error::InsufficientCollateral)
+            );
+            token.withdraw_collateral(100).unwrap();
+            assert_eq!(native_balance(contract), 300);
+
+            assert_eq!(token.repay_loan(1_000), Ok(150));
+            assert_eq!(token.repay_loan(1), Err(Error::NoLoan));
+            assert_eq!(token.total_supply(), 0);
+            token.withdraw_collateral(300).unwrap();
+            assert_eq!(token.collateral_of(accounts.bob), 0);
+            assert_eq!(native_balance(accounts.bob), 1000);
+        }
+
+        #[ink::test]
+        fn test_liquidation() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+            token.set_loan_to_value(5_000).unwrap();
+            assert_eq!(
+                token.set_liquidation_terms(4_000, 0),
+                Err(Error::InvalidAmount)
+            );
+            token.set_liquidation_terms(8_000, 1_000).unwrap();
+            token.set_price_oracle(Some(accounts.charlie)).unwrap();
+            token.mint(accounts.alice, 1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(1_000);
+            token.deposit_collateral().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            token.borrow(500).unwrap();
+            assert_eq!(token.health_factor(accounts.bob), Some(16_000));
+            assert_eq!(
+                token.set_collateral_price(PRICE_PRECISION / 2),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(token.liquidate(accounts.bob), Err(Error::PositionHealthy));
+            assert_eq!(token.liquidate(accounts.django), Err(Error::NoLoan));
+
+            // Collateral now covers 480 at the threshold, less than the loan
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token
+                .set_collateral_price(PRICE_PRECISION * 6 / 10)
+                .unwrap();
+            assert_eq!(token.health_factor(accounts.bob), Some(9_600));
+            assert_eq!(token.borrowing_power(accounts.bob), 0);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let alice_native = native_balance(accounts.alice);
+            // 500 repaid plus the 10% discount is 550 tokens, i.e. 916 native
+            assert_eq!(token.liquidate(accounts.bob), Ok(916));
+            assert_eq!(token.balance_of(accounts.alice), 500);
+            assert_eq!(native_balance(accounts.alice), alice_native + 916);
+            assert_eq!(native_balance(contract), 84);
+            assert_eq!(token.loan_of(accounts.bob), 0);
+            assert_eq!(token.collateral_of(accounts.bob), 84);
+            assert_eq!(token.health_factor(accounts.bob), None);
+            assert_eq!(token.credit_history(accounts.bob).unwrap().liquidations, 1);
+        }
+
+        #[ink::test]
+        fn test_interest_rate_model() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            set_contract_account();
+            token.set_loan_to_value(5_000).unwrap();
+            assert_eq!(
+                token.set_interest_rate_model(Some(InterestRateModel {
+                    base_rate_bps: 1_000,
+                    slope_bps: MAX_LOAN_APR_BPS,
+                })),
+                Err(Error::InvalidAmount)
+            );
+            token
+                .set_interest_rate_model(Some(InterestRateModel {
+                    base_rate_bps: 200,
+                    slope_bps: 1_800,
+                }))
+                .unwrap();
+            assert_eq!(token.set_savings_apr(500), Err(Error::RateModelActive));
+            assert_eq!(token.loan_apr(), 200);
+            assert_eq!(token.savings_apr(), 0);
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            token.deposit_to_savings(1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::transfer_in::<ink::env::DefaultEnvironment>(1_000);
+            token.deposit_collateral().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            token.borrow(500).unwrap();
+
+            // Half the savings are lent out
+            assert_eq!(token.utilization(), 5_000);
+            assert_eq!(token.loan_apr(), 1_100);
+            assert_eq!(token.savings_apr(), 550);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(YEAR);
+            assert_eq!(token.loan_of(accounts.bob), 555);
+            assert_eq!(token.savings_of(accounts.alice), 1_055);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.mint(accounts.bob, 55).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.repay_loan(1_000), Ok(555));
+            assert_eq!(token.utilization(), 0);
+            assert_eq!(token.loan_apr(), 200);
+        }
+
+        #[ink::test]
+        fn test_bill_split() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.bob, 100).unwrap();
+            token.mint(accounts.charlie, 100).unwrap();
+            let id = token
+                .create_bill(
+                    accounts.eve,
+                    ink::prelude::vec![(accounts.bob, 60), (accounts.charlie, 40)],
+                    10_000,
+                )
+                .unwrap();
+
+            assert_eq!(token.pay_bill_share(id), Err(Error::BillNotFound));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.pay_bill_share(id).unwrap();
+            assert_eq!(token.pay_bill_share(id), Err(Error::ShareAlreadyPaid));
+            assert_eq!(token.balance_of(contract), 60);
+            assert_eq!(token.balance_of(accounts.eve), 0);
+
+            // The last share releases the total
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token.pay_bill_share(id).unwrap();
+            assert_eq!(token.balance_of(accounts.eve), 100);
+            assert_eq!(token.balance_of(contract), 0);
+            assert_eq!(token.bill(id), None);
+        }
+
+        #[ink::test]
+        fn test_bill_refund_after_expiry() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.bob, 100).unwrap();
+            assert_eq!(
+                token.create_bill(
+                    accounts.eve,
+                    ink::prelude::vec![(accounts.bob, 60), (accounts.bob, 40)],
+                    10_000,
+                ),
+                Err(Error::InvalidAmount)
+            );
+            let id = token
+                .create_bill(
+                    accounts.eve,
+                    ink::prelude::vec![(accounts.bob, 60), (accounts.charlie, 40)],
+                    10_000,
+                )
+                .unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.pay_bill_share(id).unwrap();
+
+            assert_eq!(token.refund_bill(id), Err(Error::BillNotExpired));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10_000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(token.pay_bill_share(id), Err(Error::BillExpired));
+            token.refund_bill(id).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 100);
+            assert_eq!(token.balance_of(contract), 0);
+        }
+
+        #[ink::test]
+        fn test_gift_card_redeem() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            let code_hash = gift_code_hash(b"HAPPY-BIRTHDAY");
+            token.issue_gift_card(code_hash, 250, 10_000).unwrap();
+            assert_eq!(
+                token.issue_gift_card(code_hash, 100, 10_000),
+                Err(Error::GiftCardExists)
+            );
+            assert_eq!(token.balance_of(contract), 250);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(
+                token.redeem_gift_card(b"HAPPY-BIRTHDAY!".to_vec()),
+                Err(Error::GiftCardNotFound)
+            );
+            token.redeem_gift_card(b"HAPPY-BIRTHDAY".to_vec()).unwrap();
+            assert_eq!(token.balance_of(accounts.django), 250);
+            assert_eq!(token.balance_of(contract), 0);
+            assert_eq!(token.gift_card(code_hash), None);
+            assert_eq!(
+                token.redeem_gift_card(b"HAPPY-BIRTHDAY".to_vec()),
+                Err(Error::GiftCardNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn test_gift_card_reclaim_after_expiry() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            let code_hash = gift_code_hash(b"WELCOME");
+            token.issue_gift_card(code_hash, 400, 10_000).unwrap();
+            assert_eq!(
+                token.reclaim_gift_card(code_hash),
+                Err(Error::GiftCardNotExpired)
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10_000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.redeem_gift_card(b"WELCOME".to_vec()),
+                Err(Error::GiftCardExpired)
+            );
+            assert_eq!(token.reclaim_gift_card(code_hash), Err(Error::Unauthorized));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.reclaim_gift_card(code_hash).unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 1_000);
+            assert_eq!(token.balance_of(contract), 0);
+        }
+
+        #[ink::test]
+        fn test_joint_account() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let owners = vec![accounts.alice, accounts.bob, accounts.charlie];
+
+            assert_eq!(
+                token.create_joint_account(vec![accounts.bob, accounts.charlie], 1, 100),
+                Err(Error::NotJointOwner)
+            );
+            assert_eq!(
+                token.create_joint_account(owners.clone(), 4, 100),
+                Err(Error::InvalidThreshold)
+            );
+            assert_eq!(
+                token.create_joint_account(vec![accounts.alice, accounts.alice], 1, 100),
+                Err(Error::DuplicateOwner)
+            );
+            let joint = token.create_joint_account(owners, 2, 100).unwrap();
+            token.mint(joint, 1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.transfer_from_joint(joint, accounts.bob, 50).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 50);
+            assert_eq!(
+                token.transfer_from_joint(joint, accounts.bob, 101),
+                Err(Error::JointApprovalRequired)
+            );
+
+            let id = token
+                .propose_joint_transfer(joint, accounts.eve, 300)
+                .unwrap();
+            assert_eq!(
+                token.approve_joint_transfer(id),
+                Err(Error::AlreadyConfirmed)
+            );
+            assert_eq!(
+                token.execute_joint_transfer(id),
+                Err(Error::JointApprovalRequired)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(token.approve_joint_transfer(id), Err(Error::NotJointOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token.approve_joint_transfer(id).unwrap();
+            assert_eq!(token.pending_joint_transfers(joint), vec![id]);
+            assert_eq!(
+                token.joint_transfer(id).unwrap().approvals,
+                vec![accounts.bob, accounts.charlie]
+            );
+            token.execute_joint_transfer(id).unwrap();
+
+            assert_eq!(token.balance_of(accounts.eve), 300);
+            assert_eq!(token.balance_of(joint), 650);
+            assert!(token.pending_joint_transfers(joint).is_empty());
+            assert_eq!(token.joint_transfer(id), None);
+        }
+
+        #[ink::test]
+        fn test_cancel_joint_transfer() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let joint = token
+                .create_joint_account(vec![accounts.alice, accounts.bob], 2, 0)
+                .unwrap();
+
+            let id = token
+                .propose_joint_transfer(joint, accounts.eve, 300)
+                .unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.cancel_joint_transfer(id), Err(Error::Unauthorized));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.cancel_joint_transfer(id).unwrap();
+            assert!(token.pending_joint_transfers(joint).is_empty());
+            assert_eq!(
+                token.approve_joint_transfer(id),
+                Err(Error::JointTransferNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn test_envelopes() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+            let rent = String::from("rent");
+            let savings = String::from("savings");
+            token.mint(accounts.bob, 1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.fund_envelope(String::new(), 100),
+                Err(Error::InvalidEnvelopeName)
+            );
+            token.fund_envelope(rent.clone(), 600).unwrap();
+            token
+                .move_between_envelopes(rent.clone(), savings.clone(), 200)
+                .unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 400);
+            assert_eq!(token.balance_of(contract), 600);
+            assert_eq!(
+                token.envelopes_of(accounts.bob),
+                vec![
+                    (
+                        rent.clone(),
+                        Envelope {
+                            balance: 400,
+                            locked_until: 0
+                        }
+                    ),
+                    (
+                        savings.clone(),
+                        Envelope {
+                            balance: 200,
+                            locked_until: 0
+                        }
+                    ),
+                ]
+            );
+
+            token.lock_envelope(savings.clone(), 1_000).unwrap();
+            assert_eq!(
+                token.lock_envelope(savings.clone(), 500),
+                Err(Error::EnvelopeLocked)
+            );
+            assert_eq!(
+                token.release_envelope(savings.clone(), 50),
+                Err(Error::EnvelopeLocked)
+            );
+
+            token
+                .spend_from_envelope(rent.clone(), accounts.charlie, 400)
+                .unwrap();
+            assert_eq!(token.balance_of(accounts.charlie), 400);
+            assert_eq!(token.envelope(accounts.bob, rent.clone()), None);
+            assert_eq!(
+                token.release_envelope(rent, 1),
+                Err(Error::EnvelopeNotFound)
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            token.release_envelope(savings, 200).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 600);
+            assert!(token.envelopes_of(accounts.bob).is_empty());
+        }
+
+        #[ink::test]
+        fn test_payment_requests() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.bob, 1_000).unwrap();
+            let first = token
+                .create_payment_request(accounts.bob, 300, b"INV-1".to_vec(), 10_000)
+                .unwrap();
+            let second = token
+                .create_payment_request(accounts.bob, 200, b"INV-2".to_vec(), 10_000)
+                .unwrap();
+            assert_eq!(token.open_payment_request_count(accounts.alice), 2);
+
+            // Only the payer can settle it, and only once
+            assert_eq!(token.pay_request(first), Err(Error::PaymentRequestNotFound));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.pay_request(first).unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 300);
+            assert!(token.payment_request(first).unwrap().paid);
+            assert_eq!(token.pay_request(first), Err(Error::PaymentRequestSettled));
+
+            let open = token.open_payment_requests(accounts.alice, 0, 10);
+            assert_eq!(open.len(), 1);
+            assert_eq!(open[0].0, second);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10_000);
+            assert_eq!(token.pay_request(second), Err(Error::PaymentRequestExpired));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.cancel_payment_request(second).unwrap();
+            assert_eq!(token.open_payment_request_count(accounts.alice), 0);
+            assert_eq!(token.payment_request(second), None);
+        }
+
+        #[ink::test]
+        fn test_escrow_release() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            let id = token
+                .create_escrow(accounts.bob, accounts.charlie, 400, 10_000)
+                .unwrap();
+            assert_eq!(token.balance_of(contract), 400);
+
+            // Neither the payee nor the arbiter can release as payer
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.release_escrow(id), Err(Error::EscrowNotFound));
+            assert_eq!(token.resolve_escrow(id, true), Err(Error::EscrowNotFound));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.release_escrow(id).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 400);
+            assert_eq!(token.escrow(id), None);
+
+            // The arbiter can rule either way
+            let id = token
+                .create_escrow(accounts.bob, accounts.charlie, 100, 10_000)
+                .unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token.resolve_escrow(id, false).unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 600);
+        }
+
+        #[ink::test]
+        fn test_milestone_escrow() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            let id = token
+                .create_milestone_escrow(
+                    accounts.bob,
+                    accounts.charlie,
+                    ink::prelude::vec![100, 200, 300],
+                    10_000,
+                )
+                .unwrap();
+            assert_eq!(token.balance_of(contract), 600);
+
+            token.approve_milestone(id, 1).unwrap();
+            assert_eq!(token.approve_milestone(id, 1), Err(Error::InvalidMilestone));
+            assert_eq!(token.approve_milestone(id, 3), Err(Error::InvalidMilestone));
+
+            // The arbiter can approve too, the payee can't
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.approve_milestone(id, 0), Err(Error::EscrowNotFound));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token.approve_milestone(id, 0).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 300);
+            assert_eq!(token.escrow(id).unwrap().amount, 300);
+
+            // Cancelling refunds only the unapproved milestone
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.cancel_escrow(id).unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 700);
+            assert_eq!(token.balance_of(contract), 0);
+            assert_eq!(token.escrow(id), None);
+        }
+
+        #[ink::test]
+        fn test_escrow_timeout_refund() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            assert_eq!(
+                token.create_escrow(accounts.bob, accounts.bob, 100, 10_000),
+                Err(Error::InvalidAmount)
+            );
+            let id = token
+                .create_escrow(accounts.bob, accounts.charlie, 300, 10_000)
+                .unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(
+                token.refund_expired_escrow(id),
+                Err(Error::EscrowNotExpired)
+            );
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10_000);
+            token.refund_expired_escrow(id).unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 1_000);
+            assert_eq!(token.escrow(id), None);
+        }
+
+        #[ink::test]
+        fn test_payroll_in_chunks() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            token.blacklist(accounts.django).unwrap();
+            let id = token.create_payroll().unwrap();
+            token
+                .add_payroll_entries(
+                    id,
+                    ink::prelude::vec![(accounts.bob, 100), (accounts.charlie, 200)],
+                )
+                .unwrap();
+            token
+                .add_payroll_entries(
+                    id,
+                    ink::prelude::vec![(accounts.django, 300), (accounts.eve, 50)],
+                )
+                .unwrap();
+            assert_eq!(token.run_payroll(id, 2), Err(Error::InvalidPayrollStatus));
+
+            token.fund_payroll(id).unwrap();
+            assert_eq!(token.balance_of(contract), 650);
+            assert_eq!(
+                token.add_payroll_entries(id, ink::prelude::vec![(accounts.bob, 1)]),
+                Err(Error::InvalidPayrollStatus)
+            );
+
+            assert_eq!(token.run_payroll(id, 2), Ok(2));
+            let payroll = token.payroll(id).unwrap();
+            assert_eq!(payroll.status, PayrollStatus::Funded);
+            assert_eq!((payroll.next_index, payroll.paid), (2, 300));
+
+            // Blacklisted django is skipped and refunded at the end
+            assert_eq!(token.run_payroll(id, 10), Ok(2));
+            let payroll = token.payroll(id).unwrap();
+            assert_eq!(payroll.status, PayrollStatus::Completed);
+            assert_eq!(payroll.paid, 350);
+            assert_eq!(token.balance_of(accounts.eve), 50);
+            assert_eq!(token.balance_of(accounts.django), 0);
+            assert_eq!(token.balance_of(accounts.alice), 650);
+            assert_eq!(token.balance_of(contract), 0);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.run_payroll(id, 1), Err(Error::PayrollNotFound));
+        }
+
+        #[ink::test]
+        fn test_dividend_distribution() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            token.transfer(accounts.bob, 300, Vec::new()).unwrap();
+            let id = token.create_distribution(100).unwrap();
+            assert_eq!(token.distribution(id).unwrap().eligible_supply, 900);
+
+            // Balances after the snapshot don't change the shares
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.transfer(accounts.charlie, 300, Vec::new()).unwrap();
+            assert_eq!(token.balance_at(accounts.bob, 1), 300);
+            assert_eq!(token.dividend_of(id, accounts.bob), 33);
+            assert_eq!(token.claim_dividend(id), Ok(33));
+            assert_eq!(token.balance_of(accounts.bob), 33);
+            assert_eq!(token.claim_dividend(id), Err(Error::DividendAlreadyClaimed));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(token.claim_dividend(id), Err(Error::NothingToClaim));
+            assert_eq!(token.claim_dividend(1), Err(Error::DistributionNotFound));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(token.claim_dividend(id), Ok(66));
+            assert_eq!(token.distribution(id).unwrap().claimed, 99);
+        }
+
+        #[ink::test]
+        fn test_merkle_airdrop() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            let bob_leaf = token.merkle_leaf(accounts.bob, 100);
+            let charlie_leaf = token.merkle_leaf(accounts.charlie, 200);
+            let root = token.merkle_root(bob_leaf, &[charlie_leaf]);
+            let id = token.create_airdrop(root, 300, 1_000).unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 700);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.claim_airdrop(id, 200, ink::prelude::vec![charlie_leaf]),
+                Err(Error::InvalidMerkleProof)
+            );
+            token
+                .claim_airdrop(id, 100, ink::prelude::vec![charlie_leaf])
+                .unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 100);
+            assert_eq!(
+                token.claim_airdrop(id, 100, ink::prelude::vec![charlie_leaf]),
+                Err(Error::AirdropAlreadyClaimed)
+            );
+            assert_eq!(token.reclaim_airdrop(id), Err(Error::AirdropNotExpired));
+
+            // Charlie misses the deadline and the rest goes to the treasury
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                token.claim_airdrop(id, 200, ink::prelude::vec![bob_leaf]),
+                Err(Error::AirdropExpired)
+            );
+            assert_eq!(token.reclaim_airdrop(id), Ok(200));
+            assert_eq!(token.treasury_balance(), 200);
+            assert_eq!(token.reclaim_airdrop(id), Err(Error::AirdropNotFound));
+        }
+
+        #[ink::test]
+        fn test_salary_advance() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            set_contract_account();
+
+            token.mint(accounts.alice, 10_000).unwrap();
+            token.set_salary_advance_terms(5_000, 200).unwrap();
+            token.set_fee_collector(Some(accounts.eve)).unwrap();
+            let pay_bob = |token: &mut Token| {
+                ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+                let id = token.create_payroll().unwrap();
+                token
+                    .add_payroll_entries(id, ink::prelude::vec![(accounts.bob, 1_000)])
+                    .unwrap();
+                token.fund_payroll(id).unwrap();
+                token.run_payroll(id, 1).unwrap();
+                ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            };
+
+            pay_bob(&mut token);
+            assert_eq!(
+                token.request_salary_advance(100),
+                Err(Error::NoPayrollIncome)
+            );
+
+            pay_bob(&mut token);
+            assert_eq!(token.available_salary_advance(accounts.bob), 500);
+            assert_eq!(
+                token.request_salary_advance(501),
+                Err(Error::SalaryAdvanceLimitExceeded)
+            );
+            assert_eq!(token.request_salary_advance(400), Ok(8));
+            assert_eq!(token.balance_of(accounts.bob), 2_400);
+            assert_eq!(token.available_salary_advance(accounts.bob), 100);
+
+            // The next payment repays the advance and its fee first
+            pay_bob(&mut token);
+            assert_eq!(token.balance_of(accounts.bob), 2_992);
+            assert_eq!(token.balance_of(accounts.eve), 8);
+            assert_eq!(token.salary_advance_of(accounts.bob), None);
+            assert_eq!(token.total_supply(), 10_000);
+            assert_eq!(token.payroll_income(accounts.bob).unwrap().payments, 3);
+        }
+
+        #[ink::test]
+        fn test_direct_debit() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 500).unwrap();
+            let id = token.create_mandate(accounts.bob, 400).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.collect_direct_debit(id, 300), Ok(true));
+            assert_eq!(token.balance_of(accounts.bob), 300);
+            assert_eq!(
+                token.collect_direct_debit(id, 401),
+                Err(Error::MandateLimitExceeded)
+            );
+
+            // Dishonoured, nothing moves
+            assert_eq!(token.collect_direct_debit(id, 300), Ok(false));
+            assert_eq!(token.balance_of(accounts.alice), 200);
+            assert_eq!(token.mandate(id).unwrap().dishonoured, 1);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                token.collect_direct_debit(id, 100),
+                Err(Error::MandateNotFound)
+            );
+            assert_eq!(token.revoke_mandate(id), Err(Error::MandateNotFound));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.revoke_mandate(id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.collect_direct_debit(id, 100),
+                Err(Error::MandateNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn test_subscription_charges() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            let id = token
+                .create_subscription(accounts.bob, 300, 30_000)
+                .unwrap();
+
+            // Only the merchant can charge
+            assert_eq!(
+                token.charge_subscription(id),
+                Err(Error::SubscriptionNotFound)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.charge_subscription(id).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 300);
+            assert_eq!(token.charge_subscription(id), Err(Error::ChargeTooEarly));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(30_000);
+            token.charge_subscription(id).unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 400);
+            assert_eq!(token.subscription(id).unwrap().next_charge_at, 60_000);
+
+            // The subscriber can stop further charges
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.cancel_subscription(id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            assert_eq!(
+                token.charge_subscription(id),
+                Err(Error::SubscriptionNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn test_standing_orders() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 250).unwrap();
+            let id = token
+                .create_standing_order(accounts.bob, 100, 1_000, 1_000, 3_000)
+                .unwrap();
+            assert_eq!(token.standing_orders_of(accounts.alice), [id]);
+
+            // Anyone can process due orders
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                token.process_due_orders(ink::prelude::vec![id, 7]),
+                [Err(Error::OrderNotDue), Err(Error::StandingOrderNotFound)]
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(2_500);
+            assert_eq!(token.process_due_orders(ink::prelude::vec![id]), [Ok(())]);
+            // Catches up on one missed payment per call
+            assert_eq!(token.process_due_orders(ink::prelude::vec![id]), [Ok(())]);
+            assert_eq!(
+                token.process_due_orders(ink::prelude::vec![id]),
+                [Err(Error::OrderNotDue)]
+            );
+            assert_eq!(token.balance_of(accounts.bob), 200);
+            assert_eq!(token.standing_order(id).unwrap().next_due, 3_000);
+
+            // The last payment fails for lack of funds and ends the order
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(3_000);
+            assert_eq!(
+                token.process_due_orders(ink::prelude::vec![id]),
+                [Err(Error::InsufficientBalance)]
+            );
+            assert_eq!(token.standing_order(id), None);
+            assert!(token.standing_orders_of(accounts.alice).is_empty());
+        }
+
+        #[ink::test]
+        fn test_cancel_standing_order() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            assert_eq!(
+                token.create_standing_order(accounts.bob, 100, 2_000, 1_000, 1_000),
+                Err(Error::InvalidAmount)
+            );
+            let id = token
+                .create_standing_order(accounts.bob, 100, 1_000, 1_000, 10_000)
+                .unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.cancel_standing_order(id),
+                Err(Error::StandingOrderNotFound)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.cancel_standing_order(id).unwrap();
+            assert_eq!(token.standing_order(id), None);
+        }
+
+        #[ink::test]
+        fn test_stream_withdraw() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 10_000).unwrap();
+            let id = token
+                .create_stream(accounts.bob, 3_600, 1_000, 3_601_000)
+                .unwrap();
+            assert_eq!(token.balance_of(contract), 3_600);
+
+            // One token a second, counted in whole seconds
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(11_999);
+            assert_eq!(token.withdrawable_from_stream(id), 10);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.withdraw_from_stream(id), Ok(10));
+            assert_eq!(token.withdraw_from_stream(id), Err(Error::NothingToClaim));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(4_000_000);
+            assert_eq!(token.withdraw_from_stream(id), Ok(3_590));
+            assert_eq!(token.balance_of(accounts.bob), 3_600);
+            assert_eq!(token.stream(id), None);
+        }
+
+        #[ink::test]
+        fn test_stream_cancel() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 10_000).unwrap();
+            assert_eq!(
+                token.create_stream(accounts.bob, 100, 0, 999),
+                Err(Error::InvalidAmount)
+            );
+            let id = token
+                .create_stream(accounts.bob, 1_000, 0, 100_000)
+                .unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(token.cancel_stream(id), Err(Error::StreamNotFound));
+
+            // Settled pro rata at 25% of the way through
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(25_000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.cancel_stream(id).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 250);
+            assert_eq!(token.balance_of(accounts.alice), 9_750);
+            assert_eq!(token.balance_of(contract), 0);
+            assert_eq!(token.stream(id), None);
+        }
+
+        #[ink::test]
+        fn test_transfer_locked() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            let id = token.transfer_locked(accounts.bob, 300, 5_000).unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 700);
+            assert_eq!(token.balance_of(contract), 300);
+            assert_eq!(token.pending_locked_transfers(accounts.alice), [id]);
+            assert_eq!(token.locked_transfer(id).unwrap().unlock_at, 5_000);
+
+            // Only the recipient can claim, and only after the unlock time
+            assert_eq!(
+                token.claim_locked_transfer(id),
+                Err(Error::LockedTransferNotFound)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.claim_locked_transfer(id),
+                Err(Error::TransferStillLocked)
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(5_000);
+            assert_eq!(token.claim_locked_transfer(id), Ok(300));
+            assert_eq!(token.balance_of(accounts.bob), 300);
+            assert!(token.pending_locked_transfers(accounts.alice).is_empty());
+            assert_eq!(
+                token.claim_locked_transfer(id),
+                Err(Error::LockedTransferNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn test_vesting_with_cliff() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 10_000).unwrap();
+            let id = token
+                .create_vesting_schedule(accounts.bob, 4_000, 1_000, 250, 1_000)
+                .unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 6_000);
+            assert_eq!(token.balance_of(contract), 4_000);
+            assert_eq!(token.vesting_schedules_of(accounts.bob), [id]);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_249);
+            assert_eq!(token.vested_amount(id), 0);
+            assert_eq!(token.claim_vested(), Err(Error::NothingToClaim));
+
+            // Past the cliff, vesting counts from the start
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_500);
+            assert_eq!(token.vested_amount(id), 2_000);
+            assert_eq!(token.claim_vested(), Ok(2_000));
+            assert_eq!(token.releasable_amount(id), 0);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_750);
+            assert_eq!(token.vested_amount(id), 3_000);
+            assert_eq!(token.releasable_amount(id), 1_000);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(5_000);
+            assert_eq!(token.claim_vested(), Ok(2_000));
+            assert_eq!(token.balance_of(accounts.bob), 4_000);
+            assert_eq!(token.vesting_schedule(id), None);
+            assert!(token.vesting_schedules_of(accounts.bob).is_empty());
+        }
+
+        #[ink::test]
+        fn test_vesting_validation() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            set_contract_account();
+
+            token.mint(accounts.alice, 100).unwrap();
+            assert_eq!(
+                token.create_vesting_schedule(accounts.bob, 100, 0, 11, 10),
+                Err(Error::InvalidAmount)
+            );
+            assert_eq!(
+                token.create_vesting_schedule(accounts.bob, 101, 0, 0, 10),
+                Err(Error::InsufficientBalance)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.create_vesting_schedule(accounts.bob, 10, 0, 0, 10),
+                Err(Error::Unauthorized)
+            );
+        }
+
+        #[ink::test]
+        fn test_vault_shares() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 10_000).unwrap();
+            token.mint(accounts.bob, 10_000).unwrap();
+            token.set_vault_strategy(Some(accounts.charlie)).unwrap();
+            token.mint(accounts.charlie, 1_000).unwrap();
+
+            assert_eq!(token.vault_deposit(1_000), Ok(1_000));
+            assert_eq!(token.balance_of(contract), 1_000);
+
+            // Yield doubles the share price
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token.credit_vault_yield(1_000).unwrap();
+            assert_eq!(token.vault_total_assets(), 2_000);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.preview_deposit(1_000), Ok(500));
+            assert_eq!(token.vault_deposit(1_000), Ok(500));
+            assert_eq!(token.vault_shares_of(accounts.bob), 500);
+
+            // Withdrawing rounds the shares burned up, redeeming rounds assets down
+            assert_eq!(token.preview_withdraw(999), Ok(500));
+            assert_eq!(token.preview_redeem(500), Ok(999));
+            assert_eq!(token.vault_redeem(500), Ok(999));
+            assert_eq!(token.balance_of(accounts.bob), 9_999);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(token.vault_withdraw(1_000), Ok(500));
+            assert_eq!(token.vault_shares_of(accounts.alice), 500);
+            assert_eq!(token.vault_redeem(501), Err(Error::InsufficientShares));
+        }
+
+        #[ink::test]
+        fn test_vault_yield_access() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            set_contract_account();
+
+            token.mint(accounts.alice, 1_000).unwrap();
+            // Nothing to credit yield to yet
+            assert_eq!(token.credit_vault_yield(100), Err(Error::InvalidAmount));
+            token.vault_deposit(100).unwrap();
+            token.credit_vault_yield(100).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.credit_vault_yield(100), Err(Error::Unauthorized));
+            assert_eq!(
+                token.set_vault_strategy(Some(accounts.bob)),
+                Err(Error::Unauthorized)
+            );
+        }
+
+        #[ink::test]
+        fn test_savings_goal() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+            token.set_goal_penalty(1_000).unwrap();
+            token.mint(accounts.bob, 1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.create_savings_goal(None, None),
+                Err(Error::InvalidAmount)
+            );
+            let id = token.create_savings_goal(Some(500), Some(1_000)).unwrap();
+            token.deposit_to_goal(id, 600).unwrap();
+            assert_eq!(token.balance_of(contract), 600);
+
+            // The target is reached but the goal is still locked
+            assert!(!token.goal_met(id));
+            assert_eq!(token.withdraw_from_goal(id, 100), Ok(90));
+            assert_eq!(token.incentive_pool(), 10);
+            assert_eq!(token.balance_of(accounts.bob), 490);
+            assert_eq!(
+                token.pay_incentive(accounts.bob, 10),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            assert!(token.goal_met(id));
+            assert_eq!(token.withdraw_from_goal(id, 500), Ok(500));
+            assert_eq!(token.savings_goal(id), None);
+            assert!(token.savings_goals_of(accounts.bob).is_empty());
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                token.pay_incentive(accounts.charlie, 11),
+                Err(Error::InsufficientBalance)
+            );
+            token.pay_incentive(accounts.charlie, 10).unwrap();
+            assert_eq!(token.balance_of(accounts.charlie), 10);
+            assert_eq!(token.balance_of(contract), 0);
+        }
+
+        #[ink::test]
+        fn test_cashback() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+            token.mint(accounts.alice, 1_000).unwrap();
+            token.mint(accounts.bob, 10_000).unwrap();
+            token.fund_rewards_pool(15).unwrap();
+            token.set_cashback_rate(100).unwrap();
+            token.set_cashback_merchant(accounts.django, true).unwrap();
+
+            // 1% of payments to the merchant only, capped by the pool
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.transfer(accounts.charlie, 1_000, Vec::new()).unwrap();
+            assert_eq!(token.cashback_of(accounts.bob), 0);
+            token.transfer(accounts.django, 1_000, Vec::new()).unwrap();
+            assert_eq!(token.cashback_of(accounts.bob), 10);
+            token.transfer(accounts.django, 1_000, Vec::new()).unwrap();
+            assert_eq!(token.cashback_of(accounts.bob), 15);
+            assert_eq!(token.rewards_pool(), 0);
+
+            assert_eq!(token.claim_cashback(), Ok(15));
+            assert_eq!(token.balance_of(accounts.bob), 7_015);
+            assert_eq!(token.balance_of(contract), 0);
+            assert_eq!(token.claim_cashback(), Err(Error::InvalidAmount));
+        }
+
+        #[ink::test]
+        fn test_merchant_settlement() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+            token.mint(accounts.alice, 1_000).unwrap();
+            token.set_fee_collector(Some(accounts.eve)).unwrap();
+            token.set_settlement_fee(100).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.register_merchant(accounts.django, 1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                token.pay_merchant(accounts.charlie, 100),
+                Err(Error::MerchantNotFound)
+            );
+            token.pay_merchant(accounts.bob, 300).unwrap();
+            token.pay_merchant(accounts.bob, 200).unwrap();
+            assert_eq!(token.merchant(accounts.bob).unwrap().pending, 500);
+            assert_eq!(token.balance_of(contract), 500);
+
+            // Others have to wait for the schedule, the merchant doesn't
+            assert_eq!(
+                token.settle_merchant(accounts.bob),
+                Err(Error::SettlementNotDue)
+            );
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            assert_eq!(token.settle_merchant(accounts.bob), Ok(495));
+            assert_eq!(token.balance_of(accounts.django), 495);
+            assert_eq!(token.balance_of(accounts.eve), 5);
+            assert_eq!(token.balance_of(contract), 0);
+            assert_eq!(
+                token.settlement_report(accounts.bob, 0),
+                SettlementReport {
+                    settlements: 1,
+                    gross: 500,
+                    fees: 5
+                }
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.settle_merchant(accounts.bob), Ok(0));
+            assert_eq!(token.settlement_report(accounts.bob, 0).settlements, 2);
+        }
+
+        #[ink::test]
+        fn test_protected_payment_dispute() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+            token.mint(accounts.bob, 1_000).unwrap();
+            token.set_compliance_officer(Some(accounts.eve)).unwrap();
+            token.set_dispute_window(1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let kept = token.pay_protected(accounts.charlie, 300).unwrap();
+            let disputed = token.pay_protected(accounts.charlie, 200).unwrap();
+            assert_eq!(token.balance_of(contract), 500);
+
+            // The payee waits out the window
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                token.release_protected_payment(kept),
+                Err(Error::DisputeWindowOpen)
+            );
+            assert_eq!(
+                token.dispute_payment(disputed),
+                Err(Error::ProtectedPaymentNotFound)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.dispute_payment(disputed).unwrap();
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            assert_eq!(token.dispute_payment(kept), Err(Error::DisputeWindowClosed));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token.release_protected_payment(kept).unwrap();
+            assert_eq!(token.balance_of(accounts.charlie), 300);
+            assert_eq!(
+                token.release_protected_payment(disputed),
+                Err(Error::PaymentDisputed)
+            );
+
+            // Only compliance rules on the dispute
+            assert_eq!(
+                token.resolve_dispute(disputed, true),
+                Err(Error::Unauthorized)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            token.resolve_dispute(disputed, true).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 700);
+            assert_eq!(token.balance_of(contract), 0);
+            assert_eq!(token.protected_payment(disputed), None);
+        }
+
+        #[ink::test]
+        fn test_donation_matching() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+            token.mint(accounts.alice, 1_000).unwrap();
+            token.mint(accounts.bob, 1_000).unwrap();
+            token.set_cause(accounts.django, true).unwrap();
+            token.set_matching_ratio(20_000).unwrap();
+            token.fund_matching_pool(250).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.donate(accounts.charlie, 100),
+                Err(Error::CauseNotFound)
+            );
+            assert_eq!(token.donate(accounts.django, 100), Ok(200));
+            // The pool runs out part way
+            assert_eq!(token.donate(accounts.django, 100), Ok(50));
+            assert_eq!(token.donate(accounts.django, 100), Ok(0));
+            assert_eq!(token.balance_of(accounts.django), 550);
+            assert_eq!(token.balance_of(contract), 0);
+            assert_eq!(
+                token.cause(accounts.django),
+                Some(Cause {
+                    active: true,
+                    donated: 300,
+                    matched: 250
+                })
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.set_cause(accounts.django, false).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.donate(accounts.django, 100),
+                Err(Error::CauseNotFound)
+            );
+            assert_eq!(token.cause(accounts.django).unwrap().donated, 300);
+        }
+
+        #[ink::test]
+        fn test_crowdfunding_campaign() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+            token.mint(accounts.bob, 1_000).unwrap();
+            token.mint(accounts.charlie, 1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            let funded = token.create_campaign(500, 1_000).unwrap();
+            let missed = token.create_campaign(5_000, 1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.pledge(funded, 200).unwrap();
+            token.pledge(funded, 100).unwrap();
+            token.pledge(missed, 400).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token.pledge(funded, 300).unwrap();
+            assert_eq!(
+                token.pledges(funded, 0, 10),
+                ink::prelude::vec![(accounts.bob, 300), (accounts.charlie, 300)]
+            );
+            assert_eq!(token.campaigns(1, 5).len(), 1);
+            assert_eq!(token.balance_of(contract), 1_000);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(token.claim_campaign(funded), Err(Error::CampaignNotEnded));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            assert_eq!(token.claim_campaign(missed), Err(Error::TargetNotMet));
+            assert_eq!(token.claim_campaign(funded), Ok(600));
+            assert_eq!(token.balance_of(accounts.django), 600);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.pledge(missed, 1), Err(Error::CampaignEnded));
+            assert_eq!(token.refund_pledge(funded), Err(Error::TargetMet));
+            assert_eq!(token.refund_pledge(missed), Ok(400));
+            assert_eq!(token.refund_pledge(missed), Err(Error::InvalidAmount));
+            assert_eq!(token.balance_of(accounts.bob), 700);
+            assert_eq!(token.balance_of(contract), 0);
+        }
+
+        #[ink::test]
+        fn test_treasury_budgets() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+            token.mint(accounts.alice, 1_000).unwrap();
+            token.fund_treasury(600).unwrap();
+            token.set_treasurer(Some(accounts.eve)).unwrap();
+            token.set_budget(String::from("payroll"), 500).unwrap();
+            token.set_budget(String::from("marketing"), 100).unwrap();
+
+            let paid = token
+                .propose_spend(String::from("payroll"), accounts.bob, 400)
+                .unwrap();
+            let over = token
+                .propose_spend(String::from("marketing"), accounts.charlie, 150)
+                .unwrap();
+            assert_eq!(
+                token.propose_spend(String::from("legal"), accounts.bob, 1),
+                Err(Error::BudgetNotFound)
+            );
+
+            // Only the treasurer decides
+            assert_eq!(token.decide_spend(paid, true), Err(Error::Unauthorized));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            token.decide_spend(paid, true).unwrap();
+            assert_eq!(token.decide_spend(over, true), Err(Error::BudgetExceeded));
+            token.decide_spend(over, false).unwrap();
+            assert_eq!(token.spend_proposal(over), None);
+
+            assert_eq!(token.balance_of(accounts.bob), 400);
+            assert_eq!(token.treasury_balance(), 200);
+            assert_eq!(token.balance_of(contract), 200);
+            assert_eq!(
+                token.budgets(),
+                ink::prelude::vec![
+                    (
+                        String::from("payroll"),
+                        Budget {
+                            allocated: 500,
+                            spent: 400
+                        }
+                    ),
+                    (
+                        String::from("marketing"),
+                        Budget {
+                            allocated: 100,
+                            spent: 0
+                        }
+                    ),
+                ]
+            );
+        }
+
+        #[ink::test]
+        fn test_treasury_grant_vesting() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+            token.mint(accounts.alice, 1_000).unwrap();
+            token.fund_treasury(1_000).unwrap();
+            token.set_treasurer(Some(accounts.eve)).unwrap();
+            token.set_budget(String::from("grants"), 1_000).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            let id = token
+                .issue_grant(String::from("grants"), accounts.bob, 800, 0, 0, 1_000)
+                .unwrap();
+            assert_eq!(token.treasury_balance(), 200);
+            assert_eq!(
+                token.issue_grant(String::from("grants"), accounts.bob, 300, 0, 0, 1_000),
+                Err(Error::BudgetExceeded)
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(250);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.claim_vested(), Ok(200));
+            assert_eq!(token.revoke_grant(id), Err(Error::Unauthorized));
+
+            // Revoking keeps what vested, returns the rest
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(500);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(token.revoke_grant(id), Ok(400));
+            assert_eq!(token.treasury_balance(), 600);
+            assert_eq!(token.budget(String::from("grants")).unwrap().spent, 400);
+            assert_eq!(token.grant_budget(id), None);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.claim_vested(), Ok(200));
+            assert_eq!(token.vesting_schedule(id), None);
+            assert_eq!(token.balance_of(contract), 600);
+        }
+
+        #[ink::test]
+        fn test_insurance_levy_and_payout() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+            token.mint(accounts.alice, 100_000).unwrap();
+            assert_eq!(token.set_insurance_levy(101), Err(Error::InvalidAmount));
+            token.set_insurance_levy(10).unwrap();
+
+            token.transfer(accounts.bob, 50_000, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 49_950);
+            assert_eq!(token.insurance_fund(), 50);
+            assert_eq!(token.balance_of(contract), 50);
+            assert_eq!(
+                token
+                    .quote_transfer(accounts.alice, accounts.bob, 10_000)
+                    .net,
+                9_990
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.pay_insurance(accounts.bob, 10),
+                Err(Error::Unauthorized)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                token.pay_insurance(accounts.charlie, 51),
+                Err(Error::InsufficientBalance)
+            );
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(7);
+            token.pay_insurance(accounts.charlie, 30).unwrap();
+            assert_eq!(token.balance_of(accounts.charlie), 30);
+            assert_eq!(token.insurance_fund(), 20);
+            assert_eq!(
+                token.insurance_payouts(0, 10),
+                ink::prelude::vec![(
+                    0,
+                    InsurancePayout {
+                        to: accounts.charlie,
+                        amount: 30,
+                        paid_at: 7
+                    }
+                )]
+            );
+        }
+
+        #[ink::test]
+        fn test_dormancy_and_escheatment() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            token.mint(accounts.bob, 500).unwrap();
+            token.mint(accounts.charlie, 500).unwrap();
+            token
+                .set_dormancy_rules(Some(DormancyRules {
+                    dormancy_period: YEAR,
+                    notice_period: 1_000,
+                    custodian: accounts.eve,
+                }))
+                .unwrap();
+            assert_eq!(
+                token.flag_dormant(accounts.bob),
+                Err(Error::AccountNotDormant)
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(YEAR);
+            token.flag_dormant(accounts.bob).unwrap();
+            token.flag_dormant(accounts.charlie).unwrap();
+            assert_eq!(token.dormant_since(accounts.bob), Some(YEAR));
+            assert_eq!(token.escheat(accounts.bob), Err(Error::NoticePeriodActive));
+
+            // Any activity during the notice clears the flag
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token.check_in().unwrap();
+            assert_eq!(token.dormant_since(accounts.charlie), None);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(YEAR + 1_000);
+            assert_eq!(token.escheat(accounts.bob), Err(Error::Unauthorized));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(token.escheat(accounts.bob), Ok(500));
+            assert_eq!(
+                token.escheat(accounts.charlie),
+                Err(Error::AccountNotDormant)
+            );
+            assert_eq!(token.balance_of(accounts.bob), 0);
+            assert_eq!(token.balance_of(accounts.eve), 500);
+        }
+
+        #[ink::test]
+        fn test_close_account() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            token.mint(accounts.alice, 1_000).unwrap();
+            token.mint(accounts.bob, 500).unwrap();
+            token.approve(accounts.bob, 100).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.approve(accounts.charlie, 200).unwrap();
+            assert_eq!(token.close_account(accounts.bob), Err(Error::InvalidAmount));
+            assert_eq!(token.close_account(accounts.django), Ok(500));
+
+            assert_eq!(token.balance_of(accounts.bob), 0);
+            assert_eq!(token.balance_of(accounts.django), 500);
+            assert!(!token.balances.contains(accounts.bob));
+            // Allowances granted and received are both gone
+            assert_eq!(
+                token.allowance_details(accounts.bob, accounts.charlie),
+                Allowance::default()
+            );
+            assert!(!token.allowances.contains((accounts.alice, accounts.bob)));
+        }
+
+        #[ink::test]
+        fn test_min_balance_and_dust() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            set_contract_account();
+            token.mint(accounts.alice, 1_000).unwrap();
+            token.set_min_balance(100, false).unwrap();
+
+            assert_eq!(
+                token.transfer(accounts.bob, 50, Vec::new()),
+                Err(PSP22Error::Custom(String::from("BelowMinimumBalance")))
+            );
+            assert_eq!(
+                token.transfer(accounts.bob, 950, Vec::new()),
+                Err(PSP22Error::Custom(String::from("BelowMinimumBalance")))
+            );
+            token.transfer(accounts.bob, 200, Vec::new()).unwrap();
+
+            // Sweeping sends the leftover along instead
+            token.set_min_balance(100, true).unwrap();
+            token.transfer(accounts.bob, 750, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.alice), 0);
+            assert_eq!(token.balance_of(accounts.bob), 1_000);
+        }
+
+        #[ink::test]
+        fn test_referral_bonus() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            set_contract_account();
+            token.mint(accounts.alice, 1_000).unwrap();
+            token.mint(accounts.bob, 1_000).unwrap();
+            token.fund_rewards_pool(50).unwrap();
+            token
+                .set_referral_program(Some(ReferralProgram {
+                    bonus: 50,
+                    min_transfers: 2,
+                    min_volume: 300,
+                }))
+                .unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.set_referrer(accounts.bob), Err(Error::SelfReferral));
+            token.set_referrer(accounts.charlie).unwrap();
+            assert_eq!(
+                token.set_referrer(accounts.django),
+                Err(Error::ReferrerAlreadySet)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(token.set_referrer(accounts.bob), Err(Error::SelfReferral));
+
+            // Two transfers, but not yet enough volume
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.transfer(accounts.eve, 100, Vec::new()).unwrap();
+            token.transfer(accounts.eve, 100, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.charlie), 0);
+            token.transfer(accounts.eve, 100, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.charlie), 50);
+            assert!(token.referral_of(accounts.bob).unwrap().rewarded);
+            assert_eq!(
+                token.referral_stats(accounts.charlie),
+                ReferralStats {
+                    referred: 1,
+                    rewarded: 1,
+                    earned: 50
+                }
+            );
+
+            // Accounts that already transferred can't pick a referrer
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.transfer(accounts.eve, 100, Vec::new()).unwrap();
+            assert_eq!(
+                token.set_referrer(accounts.charlie),
+                Err(Error::ReferrerAlreadySet)
+            );
+        }
+
+        #[ink::test]
+        fn test_demurrage() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            set_contract_account();
+
+            token.mint(accounts.alice, 10_000).unwrap();
+            token.transfer(accounts.bob, 2_000, Vec::new()).unwrap();
+            token
+                .set_demurrage(Some(Demurrage {
+                    rate_bps: 1_000,
+                    sink: accounts.eve,
+                }))
+                .unwrap();
+
+            // Half a year at 10%, without touching either account
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(YEAR / 2);
+            assert_eq!(token.balance_of(accounts.alice), 7_600);
+            assert_eq!(token.demurrage_owed(accounts.bob), 100);
+            assert_eq!(token.balance_of(accounts.eve), 0);
+
+            // Settled on the next balance change, or by anyone
+            token.transfer(accounts.charlie, 600, Vec::new()).unwrap();
+            token.collect_demurrage(accounts.bob);
+            assert_eq!(token.balance_of(accounts.alice), 7_000);
+            assert_eq!(token.balance_of(accounts.bob), 1_900);
+            assert_eq!(token.balance_of(accounts.charlie), 600);
+            assert_eq!(token.balance_of(accounts.eve), 500);
+            assert_eq!(token.total_supply(), 10_000);
+
+            // Switching off forgives uncollected decay, switching on starts afresh
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(YEAR);
+            token.set_demurrage(None).unwrap();
+            assert_eq!(token.demurrage_owed(accounts.alice), 0);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(YEAR * 2);
+            token
+                .set_demurrage(Some(Demurrage {
+                    rate_bps: 1_000,
+                    sink: accounts.eve,
+                }))
+                .unwrap();
+            assert_eq!(token.balance_of(accounts.charlie), 600);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(YEAR * 3);
+            assert_eq!(token.balance_of(accounts.charlie), 540);
+
+            assert_eq!(
+                token.set_demurrage(Some(Demurrage {
+                    rate_bps: MAX_DEMURRAGE_BPS + 1,
+                    sink: accounts.eve,
+                })),
+                Err(Error::InvalidAmount)
+            );
+        }
+
+        #[ink::test]
+        fn test_term_deposit_at_maturity() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 10_000).unwrap();
+            token.set_term_deposit_terms(500, 1_000).unwrap();
+            let first = token.open_term_deposit(2_000, YEAR).unwrap();
+            let second = token.open_term_deposit(1_000, YEAR / 2).unwrap();
+            assert_eq!(token.term_deposits_of(accounts.alice), [first, second]);
+            assert_eq!(token.term_deposit(first).unwrap().interest, 100);
+            assert_eq!(token.balance_of(accounts.alice), 7_000);
+            assert_eq!(token.balance_of(contract), 3_000);
+
+            // A rate change doesn't affect open deposits
+            token.set_term_deposit_terms(0, 1_000).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(YEAR);
+            assert_eq!(token.close_term_deposit(first), Ok(2_100));
+            assert_eq!(token.balance_of(accounts.alice), 9_100);
+            assert_eq!(token.total_supply(), 10_100);
+            assert_eq!(token.term_deposits_of(accounts.alice), [second]);
+            assert_eq!(token.term_deposit(first), None);
+            assert_eq!(
+                token.close_term_deposit(first),
+                Err(Error::TermDepositNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn test_term_deposit_early_closure() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let contract = set_contract_account();
+
+            token.mint(accounts.alice, 10_000).unwrap();
+            token.set_term_deposit_terms(500, 1_000).unwrap();
+            let id = token.open_term_deposit(2_000, YEAR).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.close_term_deposit(id),
+                Err(Error::TermDepositNotFound)
+            );
+
+            // 10% of the amount is burned, no interest is paid
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(token.close_term_deposit(id), Ok(1_800));
+            assert_eq!(token.balance_of(accounts.alice), 9_800);
+            assert_eq!(token.balance_of(contract), 0);
+            assert_eq!(token.total_supply(), 9_800);
+
+            assert_eq!(token.open_term_deposit(100, 0), Err(Error::InvalidAmount));
+            for _ in 0..MAX_TERM_DEPOSITS {
+                token.open_term_deposit(1, YEAR).unwrap();
+            }
+            assert_eq!(
+                token.open_term_deposit(1, YEAR),
+                Err(Error::TooManyTermDeposits)
+            );
+        }
+
+        #[ink::test]
+        fn test_quote_transfer() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 10_000).unwrap();
+            token.set_fee_bps(250).unwrap();
+            token.set_fee_collector(Some(accounts.eve)).unwrap();
+
+            let quote = token.quote_transfer(accounts.alice, accounts.bob, 1000);
+            assert_eq!(quote.fee, 25);
+            assert_eq!(quote.net, 975);
+            assert_eq!(quote.outcome, Ok(()));
+            // Nothing moved
+            assert_eq!(token.balance_of(accounts.alice), 10_000);
+
+            assert_eq!(
+                token
+                    .quote_transfer(accounts.alice, accounts.bob, 20_000)
+                    .outcome,
+                Err(Error::InsufficientBalance)
+            );
+
+            token.blacklist(accounts.bob).unwrap();
+            assert_eq!(
+                token
+                    .quote_transfer(accounts.alice, accounts.bob, 1000)
+                    .outcome,
+                Err(Error::AccountBlacklisted)
+            );
+
+            token.pause().unwrap();
+            assert_eq!(
+                token
+                    .quote_transfer(accounts.alice, accounts.charlie, 1000)
+                    .outcome,
+                Err(Error::ContractPaused)
+            );
+        }
+
+        #[ink::test]
+        fn test_tiered_fee_schedule() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1_000_000).unwrap();
+            token.set_fee_collector(Some(accounts.eve)).unwrap();
+            token
+                .set_fee_schedule(ink::prelude::vec![
+                    FeeBracket {
+                        min_amount: 100,
+                        fee_bps: 100,
+                    },
+                    FeeBracket {
+                        min_amount: 10_000,
+                        fee_bps: 50,
+                    },
+                ])
+                .unwrap();
+            assert_eq!(token.fee_schedule().len(), 2);
+
+            assert_eq!(token.fee_for(99), 0);
+            assert_eq!(token.fee_for(1000), 10);
+            assert_eq!(token.fee_for(10_000), 50);
+
+            token.transfer(accounts.bob, 20_000, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.eve), 100);
+            assert_eq!(token.balance_of(accounts.bob), 19_900);
+        }
+
+        #[ink::test]
+        fn test_loyalty_tier_discount() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1_000_000).unwrap();
+            token.set_fee_collector(Some(accounts.eve)).unwrap();
+            token.set_fee_bps(100).unwrap();
+            token
+                .set_loyalty_tiers(ink::prelude::vec![
+                    LoyaltyTier {
+                        min_volume: 10_000,
+                        fee_discount_bps: 5_000,
+                    },
+                    LoyaltyTier {
+                        min_volume: 50_000,
+                        fee_discount_bps: 10_000,
+                    },
+                ])
+                .unwrap();
+
+            // The discount applies from the transfer after the threshold is crossed
+            token.transfer(accounts.bob, 10_000, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.eve), 100);
+            assert_eq!(token.loyalty_tier_of(accounts.alice), 1);
+            token.transfer(accounts.bob, 40_000, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.eve), 300);
+            assert_eq!(token.loyalty_tier_of(accounts.alice), 2);
+            token.transfer(accounts.bob, 1_000, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.eve), 300);
+
+            // Volume counts for two windows, then drops out
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(LOYALTY_WINDOW);
+            assert_eq!(token.rolling_volume(accounts.alice), 51_000);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(2 * LOYALTY_WINDOW);
+            assert_eq!(token.loyalty_tier_of(accounts.alice), 0);
+        }
+
+        #[ink::test]
+        fn test_fee_schedule_validation() {
+            let mut token = Token::new();
+
+            let unsorted = ink::prelude::vec![
+                FeeBracket {
+                    min_amount: 100,
+                    fee_bps: 10,
+                },
+                FeeBracket {
+                    min_amount: 100,
+                    fee_bps: 5,
+                },
+            ];
+            assert_eq!(token.set_fee_schedule(unsorted), Err(Error::InvalidAmount));
+
+            let too_high = ink::prelude::vec![FeeBracket {
+                min_amount: 0,
+                fee_bps: MAX_FEE_BPS + 1,
+            }];
+            assert_eq!(token.set_fee_schedule(too_high), Err(Error::InvalidAmount));
+
+            // Clearing the schedule falls back to the flat fee
+            token.set_fee_bps(30).unwrap();
+            token.set_fee_schedule(Vec::new()).unwrap();
+            token.set_fee_collector(Some(get_accounts().eve)).unwrap();
+            assert_eq!(token.fee_for(10_000), 30);
+        }
+
+        #[ink::test]
+        fn test_fee_config_validation() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            assert_eq!(
+                token.set_fee_bps(MAX_FEE_BPS + 1),
+                Err(Error::InvalidAmount)
+            );
+            token.set_fee_bps(MAX_FEE_BPS).unwrap();
+            assert_eq!(token.fee_bps(), MAX_FEE_BPS);
+
+            // Large amounts don't overflow
+            assert_eq!(Token::bps_of(u128::MAX, 10_000), u128::MAX);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.set_fee_bps(1), Err(Error::Unauthorized));
+            assert_eq!(
+                token.set_fee_collector(Some(accounts.bob)),
+                Err(Error::Unauthorized)
+            );
+        }
+
+        #[ink::test]
+        fn test_travel_rule_memo_required() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 10_000).unwrap();
+            token.set_travel_rule_threshold(Some(1000)).unwrap();
+            assert_eq!(token.travel_rule_threshold(), Some(1000));
+
+            // Below the threshold no reference is needed
+            token.transfer(accounts.bob, 1000, Vec::new()).unwrap();
+            assert_eq!(
+                token.transfer(accounts.bob, 1001, Vec::new()),
+                Err(Error::TravelRuleMemoRequired.into())
+            );
+            token
+                .transfer(accounts.bob, 1001, ink::prelude::vec![0xAB; 32])
+                .unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 2001);
+        }
+
+        #[ink::test]
+        fn test_max_transfer_amount() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1000).unwrap();
+            token.set_max_transfer_amount(Some(100)).unwrap();
+            assert_eq!(token.max_transfer_amount(), Some(100));
+
+            token.transfer(accounts.bob, 100, Vec::new()).unwrap();
+            assert_eq!(
+                token.transfer(accounts.bob, 101, Vec::new()),
+                Err(Error::TransferAmountExceeded.into())
+            );
+
+            // Applies to delegated transfers too
+            token.approve(accounts.bob, 500).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.transfer_from(accounts.alice, accounts.charlie, 200, Vec::new()),
+                Err(Error::TransferAmountExceeded.into())
+            );
+            assert_eq!(
+                token.set_max_transfer_amount(None),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.set_max_transfer_amount(None).unwrap();
+            token.transfer(accounts.bob, 500, Vec::new()).unwrap();
+        }
+
+        #[ink::test]
+        fn test_transfer_all() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1234).unwrap();
+            token.transfer_all(accounts.bob).unwrap();
+
+            assert_eq!(token.balance_of(accounts.alice), 0);
+            assert_eq!(token.balance_of(accounts.bob), 1234);
+
+            // Nothing left to move
+            assert_eq!(token.transfer_all(accounts.bob), Err(Error::InvalidAmount));
+        }
+
+        #[ink::test]
+        fn test_transfer_and_call_requires_contract() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1000).unwrap();
+            assert_eq!(
+                token.transfer_and_call(accounts.bob, 100, [0xde, 0xad, 0xbe, 0xef], Vec::new()),
+                Err(Error::NotAContract)
+            );
+            assert_eq!(token.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "off-chain environment does not support contract invocation")]
+        fn test_transfer_and_call_invokes_recipient() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1000).unwrap();
+            ink::env::test::set_contract::<ink::env::DefaultEnvironment>(accounts.django);
+            let _ =
+                token.transfer_and_call(accounts.django, 100, [0xde, 0xad, 0xbe, 0xef], Vec::new());
+        }
+
+        #[ink::test]
+        fn test_transfer_with_data() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1000).unwrap();
+
+            token
+                .transfer_with_data(accounts.bob, 100, b"invoice-42".to_vec())
+                .unwrap();
+
+            assert_eq!(token.balance_of(accounts.bob), 100);
+            let event = last_transfer_event();
+            assert_eq!(event.from, Some(accounts.alice));
+            assert_eq!(event.to, Some(accounts.bob));
+            assert_eq!(event.value, 100);
+            assert_eq!(event.data, b"invoice-42".to_vec());
+        }
+
+        #[ink::test]
+        fn test_transfer_with_memo() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let invoice = Hash::from([1u8; 32]);
+
+            token.mint(accounts.alice, 1000).unwrap();
+            token
+                .transfer_with_memo(accounts.bob, 100, invoice, Some(b"INV-001 part 1".to_vec()))
+                .unwrap();
+            assert_eq!(last_transfer_event().data, b"INV-001 part 1".to_vec());
+
+            // Later payments against the same ID replace the record
+            token
+                .transfer_with_memo(accounts.bob, 50, invoice, None)
+                .unwrap();
+            let record = token.payment_of(invoice).unwrap();
+            assert_eq!(record.amount, 50);
+            assert_eq!(record.memo, None);
+            assert_eq!(token.payment_of(Hash::from([2u8; 32])), None);
+
+            assert_eq!(
+                token.transfer_with_memo(
+                    accounts.bob,
+                    1,
+                    invoice,
+                    Some(ink::prelude::vec![0; MAX_MEMO_LEN + 1])
+                ),
+                Err(Error::MemoTooLong)
+            );
+        }
+
+        #[ink::test]
+        fn test_partial_refunds() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let invoice = Hash::from([4u8; 32]);
+
+            token.mint(accounts.alice, 1000).unwrap();
+            token
+                .transfer_with_memo(accounts.bob, 300, invoice, None)
+                .unwrap();
+
+            // Only the payee can refund
+            assert_eq!(token.refund(invoice, 100), Err(Error::PaymentNotFound));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.refund(invoice, 100).unwrap();
+            assert_eq!(last_transfer_event().data, invoice.as_ref().to_vec());
+            token.refund(invoice, 150).unwrap();
+            assert_eq!(token.payment_of(invoice).unwrap().refunded, 250);
+            assert_eq!(token.balance_of(accounts.alice), 950);
+            assert_eq!(token.refund(invoice, 51), Err(Error::RefundExceedsPayment));
+            assert_eq!(
+                token.refund(Hash::from([5u8; 32]), 1),
+                Err(Error::PaymentNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn test_transfer_from_with_memo() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let invoice = Hash::from([3u8; 32]);
+
+            token.mint(accounts.alice, 1000).unwrap();
+            token.approve(accounts.bob, 200).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token
+                .transfer_from_with_memo(
+                    accounts.alice,
+                    accounts.charlie,
+                    200,
+                    invoice,
+                    Some(b"direct debit".to_vec()),
+                )
+                .unwrap();
+
+            let record = token.payment_of(invoice).unwrap();
+            assert_eq!(record.from, accounts.alice);
+            assert_eq!(record.to, accounts.charlie);
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn test_transfer_from_with_data() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1000).unwrap();
+            token.approve(accounts.bob, 300).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token
+                .transfer_from_with_data(accounts.alice, accounts.charlie, 200, b"ref".to_vec())
+                .unwrap();
+
+            assert_eq!(token.balance_of(accounts.charlie), 200);
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 100);
+            assert_eq!(last_transfer_event().data, b"ref".to_vec());
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "off-chain environment does not support contract invocation")]
+        fn test_transfer_to_contract_calls_receiver_hook() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1000).unwrap();
+
+            // Transfers to contracts must go through the PSP22Receiver hook
+            ink::env::test::set_contract::<ink::env::DefaultEnvironment>(accounts.django);
+            let _ = token.transfer(accounts.django, 100, Vec::new());
+        }
+
+        #[ink::test]
+        fn test_psp22_contract_ref() {
+            let accounts = get_accounts();
+
+            // Integrators can build a typed reference from the token's address
+            let token: ink::contract_ref!(PSP22) = accounts.django.into();
+            assert_eq!(ink::ToAccountId::to_account_id(&token), accounts.django);
+        }
+
+        #[ink::test]
+        fn test_approve_and_transfer_from() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            // Setup: mint tokens to alice
+            token.mint(accounts.alice, 1000).unwrap();
+
+            // Alice approves bob to spend 300
+            token.approve(accounts.bob, 300).unwrap();
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 300);
+
+            // Bob transfers from alice to charlie
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token
+                .transfer_from(accounts.alice, accounts.charlie, 200, Vec::new())
+                .unwrap();
+
+            assert_eq!(token.balance_of(accounts.alice), 800);
+            assert_eq!(token.balance_of(accounts.charlie), 200);
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 100); // Decreased
+        }
+
+        #[ink::test]
+        fn test_allowance_expiry() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            token.mint(accounts.alice, 1000).unwrap();
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            assert_eq!(
+                token.approve_with_expiry(accounts.bob, 300, 1_000),
+                Err(Error::InvalidExpiry)
+            );
+            token.approve_with_expiry(accounts.bob, 300, 2_000).unwrap();
+
+            // Spending and increasing keep the deadline
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token
+                .transfer_from(accounts.alice, accounts.charlie, 100, Vec::new())
+                .unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.increase_allowance(accounts.bob, 50).unwrap();
+            assert_eq!(
+                token.allowance_details(accounts.alice, accounts.bob),
+                Allowance {
+                    value: 250,
+                    expires_at: Some(2_000)
+                }
+            );
+
+            // Once expired it counts as zero, but the stored value stays visible
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(2_000);
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(
+                token.allowance_details(accounts.alice, accounts.bob).value,
+                250
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.transfer_from(accounts.alice, accounts.charlie, 1, Vec::new()),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+
+            // A plain approve clears the deadline
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.approve(accounts.bob, 10).unwrap();
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 10);
+        }
+
+        #[ink::test]
+        fn test_periodic_allowance_renews() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            token.mint(accounts.alice, 1000).unwrap();
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            token.approve_periodic(accounts.bob, 100, 500).unwrap();
+            token.approve(accounts.bob, 20).unwrap();
+
+            // The regular allowance goes first, the periodic budget covers the rest
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token
+                .transfer_from(accounts.alice, accounts.charlie, 20, Vec::new())
+                .unwrap();
+            token
+                .transfer_from(accounts.alice, accounts.charlie, 60, Vec::new())
+                .unwrap();
+            assert_eq!(
+                token.transfer_from(accounts.alice, accounts.charlie, 50, Vec::new()),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+
+            // The next period starts from scratch
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(2_200);
+            let periodic = token
+                .periodic_allowance(accounts.alice, accounts.bob)
+                .unwrap();
+            assert_eq!((periodic.period_start, periodic.spent), (2_000, 0));
+            token
+                .transfer_from(accounts.alice, accounts.charlie, 100, Vec::new())
+                .unwrap();
+            assert_eq!(token.balance_of(accounts.charlie), 180);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.approve_periodic(accounts.bob, 0, 0).unwrap();
+            assert_eq!(token.periodic_allowance(accounts.alice, accounts.bob), None);
+        }
+
+        #[ink::test]
+        fn test_spender_destinations() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            token.mint(accounts.alice, 1000).unwrap();
+            token.approve(accounts.bob, 500).unwrap();
+            token
+                .set_spender_destinations(accounts.bob, ink::prelude::vec![accounts.django])
+                .unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.transfer_from(accounts.alice, accounts.charlie, 100, Vec::new()),
+                Err(PSP22Error::Custom(String::from("DestinationNotAllowed")))
+            );
+            assert_eq!(
+                token.burn_from(accounts.alice, 100),
+                Err(Error::DestinationNotAllowed)
+            );
+            token
+                .transfer_from(accounts.alice, accounts.django, 100, Vec::new())
+                .unwrap();
+            assert_eq!(token.balance_of(accounts.django), 100);
+
+            // Lifting the restriction lets bob pay anyone again
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token
+                .set_spender_destinations(accounts.bob, Vec::new())
+                .unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token
+                .transfer_from(accounts.alice, accounts.charlie, 100, Vec::new())
+                .unwrap();
+        }
+
+        #[ink::test]
+        fn test_session_key() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            token.mint(accounts.alice, 1000).unwrap();
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            token
+                .register_session_key(
+                    accounts.bob,
+                    150,
+                    ink::prelude::vec![accounts.charlie],
+                    2_000,
+                )
+                .unwrap();
+
+            // No classic allowance needed
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 0);
+            token
+                .delegated_transfer(accounts.alice, accounts.charlie, 100)
+                .unwrap();
+            assert_eq!(token.balance_of(accounts.charlie), 100);
+            assert_eq!(
+                token.delegated_transfer(accounts.alice, accounts.django, 10),
+                Err(Error::DestinationNotAllowed)
+            );
+            assert_eq!(
+                token.delegated_transfer(accounts.alice, accounts.charlie, 60),
+                Err(Error::SessionCapExceeded)
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(2_000);
+            assert_eq!(
+                token.delegated_transfer(accounts.alice, accounts.charlie, 10),
+                Err(Error::SessionKeyExpired)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.revoke_session_key(accounts.bob).unwrap();
+            assert_eq!(token.session_key(accounts.alice, accounts.bob), None);
+        }
+
+        #[ink::test]
+        fn test_batch_approve() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            let approvals = ink::prelude::vec![(accounts.bob, 100), (accounts.charlie, 200)];
+            token.batch_approve(approvals).unwrap();
+
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 100);
+            assert_eq!(token.allowance(accounts.alice, accounts.charlie), 200);
+        }
+
+        #[ink::test]
+        fn test_batch_approve_is_atomic() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            // Self-approval anywhere in the batch rejects the whole batch
+            let approvals = ink::prelude::vec![(accounts.bob, 100), (accounts.alice, 200)];
+            assert_eq!(token.batch_approve(approvals), Err(Error::SelfApproval));
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 0);
+
+            token.blacklist(accounts.charlie).unwrap();
+            let approvals = ink::prelude::vec![(accounts.bob, 100), (accounts.charlie, 200)];
+            assert_eq!(
+                token.batch_approve(approvals),
+                Err(Error::AccountBlacklisted)
+            );
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn test_permit() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            let secret_key = secp256k1::SecretKey::from_slice(&[0x11; 32]).unwrap();
+            let owner = ecdsa_account(&secret_key);
+            let signature = ecdsa_sign(
+                &secret_key,
+                token.permit_hash(owner, accounts.bob, 500, 1000),
+            );
+
+            // Anyone (here charlie) can submit the signed approval
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token
+                .permit(owner, accounts.bob, 500, 1000, signature)
+                .unwrap();
+
+            assert_eq!(token.allowance(owner, accounts.bob), 500);
+        }
+
+        #[ink::test]
+        fn test_permit_rejects_wrong_signer_and_tampering() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            let secret_key = secp256k1::SecretKey::from_slice(&[0x11; 32]).unwrap();
+            let owner = ecdsa_account(&secret_key);
+            let signature = ecdsa_sign(
+                &secret_key,
+                token.permit_hash(owner, accounts.bob, 500, 1000),
+            );
+
+            // Signature doesn't belong to alice
+            assert_eq!(
+                token.permit(accounts.alice, accounts.bob, 500, 1000, signature),
+                Err(Error::InvalidSignature)
+            );
+            // Signed value doesn't match
+            assert_eq!(
+                token.permit(owner, accounts.bob, 900, 1000, signature),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(token.allowance(owner, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn test_permit_cannot_be_replayed() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            let secret_key = secp256k1::SecretKey::from_slice(&[0x11; 32]).unwrap();
+            let owner = ecdsa_account(&secret_key);
+            let signature = ecdsa_sign(
+                &secret_key,
+                token.permit_hash(owner, accounts.bob, 500, 1000),
+            );
+
+            assert_eq!(token.nonce_of(owner), 0);
+            token
+                .permit(owner, accounts.bob, 500, 1000, signature)
+                .unwrap();
+            assert_eq!(token.nonce_of(owner), 1);
+
+            // Allowance gets spent, then the same signature is resubmitted
+            token
+                .allowances
+                .insert((owner, accounts.bob), &Allowance::default());
+            assert_eq!(
+                token.permit(owner, accounts.bob, 500, 1000, signature),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(token.allowance(owner, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn test_permit_expired() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            let secret_key = secp256k1::SecretKey::from_slice(&[0x11; 32]).unwrap();
+            let owner = ecdsa_account(&secret_key);
+            let signature = ecdsa_sign(
+                &secret_key,
+                token.permit_hash(owner, accounts.bob, 500, 1000),
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1001);
+            assert_eq!(
+                token.permit(owner, accounts.bob, 500, 1000, signature),
+                Err(Error::SignatureExpired)
+            );
+        }
+
+        #[ink::test]
+        fn test_permit_sr25519() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            let keypair = sr25519_keypair(0x22);
+            let owner = sr25519_account(&keypair);
+            let message_hash = token.permit_hash(owner, accounts.bob, 500, 1000);
+
+            let signature = sr25519_sign(&keypair, &message_hash);
+            token
+                .permit_sr25519(owner, accounts.bob, 500, 1000, signature)
+                .unwrap();
+            assert_eq!(token.allowance(owner, accounts.bob), 500);
 
-            self.env().emit_event(Transfer {
-                from: Some(from),
-                to: Some(to),
-                value: amount,
-            });
+            // polkadot-js `signRaw` wraps the payload in <Bytes> tags
+            let message_hash = token.permit_hash(owner, accounts.charlie, 700, 1000);
+            let mut wrapped = b"<Bytes>".to_vec();
+            wrapped.extend_from_slice(&message_hash);
+            wrapped.extend_from_slice(b"</Bytes>");
+            let signature = sr25519_sign(&keypair, &wrapped);
+            token
+                .permit_sr25519(owner, accounts.charlie, 700, 1000, signature)
+                .unwrap();
+            assert_eq!(token.allowance(owner, accounts.charlie), 700);
+        }
 
-            Ok(())
+        #[ink::test]
+        fn test_permit_sr25519_rejects_wrong_signer() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            let keypair = sr25519_keypair(0x22);
+            let other = sr25519_account(&sr25519_keypair(0x33));
+            let signature =
+                sr25519_sign(&keypair, &token.permit_hash(other, accounts.bob, 500, 1000));
+
+            assert_eq!(
+                token.permit_sr25519(other, accounts.bob, 500, 1000, signature),
+                Err(Error::InvalidSignature)
+            );
         }
 
-        #[ink(message)]
-        pub fn approve(&mut self, spender: AccountId, amount: u128) -> Result<()> {
-            let owner = self.env().caller();
+        #[ink::test]
+        fn test_transfer_with_authorization() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
 
-            if owner == spender {
-                return Err(Error::SelfApproval);
-            }
+            let keypair = sr25519_keypair(0x22);
+            let holder = sr25519_account(&keypair);
+            token.mint(holder, 1000).unwrap();
 
-            self.not_blacklisted(owner)?;
-            self.not_blacklisted(spender)?;
+            let message_hash =
+                token.transfer_authorization_hash(holder, accounts.bob, 300, 0, 5000);
+            let signature = Signature::Sr25519(sr25519_sign(&keypair, &message_hash));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(100);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token
+                .transfer_with_authorization(
+                    holder,
+                    accounts.bob,
+                    300,
+                    0,
+                    5000,
+                    0,
+                    signature.clone(),
+                )
+                .unwrap();
+
+            assert_eq!(token.balance_of(holder), 700);
+            assert_eq!(token.balance_of(accounts.bob), 300);
+            assert_eq!(token.nonce_of(holder), 1);
+
+            // The nonce has been consumed
+            assert_eq!(
+                token.transfer_with_authorization(holder, accounts.bob, 300, 0, 5000, 0, signature),
+                Err(Error::InvalidNonce)
+            );
+        }
+
+        #[ink::test]
+        fn test_cash_cheque() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            let keypair = sr25519_keypair(0x33);
+            let payer = sr25519_account(&keypair);
+            token.mint(payer, 1000).unwrap();
+
+            let hash = token.cheque_hash(payer, Some(accounts.bob), 300, 5000, 7);
+            let cheque = Signature::Sr25519(sr25519_sign(&keypair, &hash));
+            let hash = token.cheque_hash(payer, None, 200, 5000, 3);
+            let bearer = Signature::Sr25519(sr25519_sign(&keypair, &hash));
+
+            // Made out to bob, so charlie can't cash it
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                token.cash_cheque(payer, Some(accounts.bob), 300, 5000, 7, cheque.clone()),
+                Err(Error::Unauthorized)
+            );
+            // Anyone can cash a bearer cheque, in any nonce order
+            token
+                .cash_cheque(payer, None, 200, 5000, 3, bearer.clone())
+                .unwrap();
+            assert_eq!(token.balance_of(accounts.charlie), 200);
+            assert_eq!(
+                token.cash_cheque(payer, None, 200, 5000, 3, bearer),
+                Err(Error::ChequeSpent)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.cash_cheque(payer, Some(accounts.bob), 301, 5000, 7, cheque.clone()),
+                Err(Error::InvalidSignature)
+            );
+            token
+                .cash_cheque(payer, Some(accounts.bob), 300, 5000, 7, cheque)
+                .unwrap();
+            assert_eq!(token.balance_of(payer), 500);
+            assert!(token.is_cheque_spent(payer, 7));
+        }
+
+        #[ink::test]
+        fn test_cancel_cheque() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            let secret_key = secp256k1::SecretKey::from_slice(&[0x44; 32]).unwrap();
+            let payer = ecdsa_account(&secret_key);
+            token.mint(payer, 1000).unwrap();
+            let cheque = Signature::Ecdsa(ecdsa_sign(
+                &secret_key,
+                token.cheque_hash(payer, Some(accounts.bob), 300, 5000, 1),
+            ));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(payer);
+            token.cancel_cheque(1).unwrap();
+            assert_eq!(token.cancel_cheque(1), Err(Error::ChequeSpent));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.cash_cheque(payer, Some(accounts.bob), 300, 5000, 1, cheque),
+                Err(Error::ChequeSpent)
+            );
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(5000);
+            assert_eq!(
+                token.cash_cheque(
+                    payer,
+                    Some(accounts.bob),
+                    300,
+                    5000,
+                    2,
+                    Signature::Ecdsa([0; 65])
+                ),
+                Err(Error::SignatureExpired)
+            );
+        }
+
+        #[ink::test]
+        fn test_transfer_with_authorization_validity_window() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            let secret_key = secp256k1::SecretKey::from_slice(&[0x11; 32]).unwrap();
+            let holder = ecdsa_account(&secret_key);
+            token.mint(holder, 1000).unwrap();
+
+            let message_hash =
+                token.transfer_authorization_hash(holder, accounts.bob, 300, 1000, 2000);
+            let signature = Signature::Ecdsa(ecdsa_sign(&secret_key, message_hash));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1000);
+            assert_eq!(
+                token.transfer_with_authorization(
+                    holder,
+                    accounts.bob,
+                    300,
+                    1000,
+                    2000,
+                    0,
+                    signature.clone()
+                ),
+                Err(Error::AuthorizationNotYetValid)
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(2000);
+            assert_eq!(
+                token.transfer_with_authorization(
+                    holder,
+                    accounts.bob,
+                    300,
+                    1000,
+                    2000,
+                    0,
+                    signature.clone()
+                ),
+                Err(Error::SignatureExpired)
+            );
+
+            // Tampered amount
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1500);
+            assert_eq!(
+                token.transfer_with_authorization(
+                    holder,
+                    accounts.bob,
+                    900,
+                    1000,
+                    2000,
+                    0,
+                    signature
+                ),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(token.balance_of(holder), 1000);
+        }
+
+        #[ink::test]
+        fn test_execute_meta_tx_transfer_with_fee() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            let keypair = sr25519_keypair(0x22);
+            let signer = sr25519_account(&keypair);
+            token.mint(signer, 1000).unwrap();
+
+            let call = MetaCall::Transfer {
+                to: accounts.bob,
+                amount: 300,
+            };
+            let message_hash = token.meta_tx_hash(signer, &call, 5, 1000);
+            let signature = Signature::Sr25519(sr25519_sign(&keypair, &message_hash));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token
+                .execute_meta_tx(signer, call.clone(), 5, 1000, signature.clone())
+                .unwrap();
+
+            assert_eq!(token.balance_of(signer), 695);
+            assert_eq!(token.balance_of(accounts.bob), 300);
+            assert_eq!(token.balance_of(accounts.charlie), 5);
+            assert_eq!(token.nonce_of(signer), 1);
+
+            // Replay is rejected once the nonce has moved on
+            assert_eq!(
+                token.execute_meta_tx(signer, call, 5, 1000, signature),
+                Err(Error::InvalidSignature)
+            );
+        }
+
+        #[ink::test]
+        fn test_execute_meta_tx_approve_and_burn() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            let secret_key = secp256k1::SecretKey::from_slice(&[0x11; 32]).unwrap();
+            let signer = ecdsa_account(&secret_key);
+            token.mint(signer, 1000).unwrap();
+
+            let approve = MetaCall::Approve {
+                spender: accounts.bob,
+                amount: 250,
+            };
+            let signature = ecdsa_sign(&secret_key, token.meta_tx_hash(signer, &approve, 0, 1000));
+            token
+                .execute_meta_tx(signer, approve, 0, 1000, Signature::Ecdsa(signature))
+                .unwrap();
+            assert_eq!(token.allowance(signer, accounts.bob), 250);
+
+            let burn = MetaCall::Burn { amount: 400 };
+            let signature = ecdsa_sign(&secret_key, token.meta_tx_hash(signer, &burn, 0, 1000));
+            token
+                .execute_meta_tx(signer, burn, 0, 1000, Signature::Ecdsa(signature))
+                .unwrap();
+            assert_eq!(token.balance_of(signer), 600);
+            assert_eq!(token.total_supply(), 600);
+        }
+
+        #[ink::test]
+        fn test_multicall() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1000).unwrap();
+
+            let calls = ink::prelude::vec![
+                MetaCall::Transfer {
+                    to: accounts.bob,
+                    amount: 100,
+                },
+                MetaCall::Approve {
+                    spender: accounts.charlie,
+                    amount: 50,
+                },
+                MetaCall::Burn { amount: 200 },
+            ];
+            assert_eq!(
+                token.multicall(calls),
+                Ok(ink::prelude::vec![Ok(()), Ok(()), Ok(())])
+            );
+
+            assert_eq!(token.balance_of(accounts.alice), 700);
+            assert_eq!(token.balance_of(accounts.bob), 100);
+            assert_eq!(token.allowance(accounts.alice, accounts.charlie), 50);
+            assert_eq!(token.total_supply(), 800);
+        }
+
+        #[ink::test]
+        fn test_multicall_reports_failures() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 100).unwrap();
+
+            let calls = ink::prelude::vec![
+                MetaCall::Transfer {
+                    to: accounts.bob,
+                    amount: 50,
+                },
+                MetaCall::Burn { amount: 500 },
+                MetaCall::Approve {
+                    spender: accounts.alice,
+                    amount: 1,
+                },
+            ];
+
+            // Err reverts the whole batch on-chain, and carries every call's outcome
+            assert_eq!(
+                token.multicall(calls),
+                Err(ink::prelude::vec![
+                    Ok(()),
+                    Err(Error::InsufficientBalance),
+                    Err(Error::SelfApproval),
+                ])
+            );
+        }
+
+        #[ink::test]
+        fn test_approve_and_call_requires_contract() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            assert_eq!(
+                token.approve_and_call(accounts.bob, 100, Vec::new()),
+                Err(Error::NotAContract)
+            );
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "off-chain environment does not support contract invocation")]
+        fn test_approve_and_call_invokes_spender() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            ink::env::test::set_contract::<ink::env::DefaultEnvironment>(accounts.django);
+            let _ = token.approve_and_call(accounts.django, 100, Vec::new());
+        }
+
+        #[ink::test]
+        fn test_increase_decrease_allowance() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            // Initial approval
+            token.approve(accounts.bob, 100).unwrap();
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 100);
+
+            // Increase allowance
+            token.increase_allowance(accounts.bob, 50).unwrap();
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 150);
+
+            // Decrease allowance
+            token.decrease_allowance(accounts.bob, 30).unwrap();
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 120);
+        }
+
+        #[ink::test]
+        fn test_erc20_facade() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1000).unwrap();
+
+            assert!(token.erc20_transfer(accounts.bob, 100));
+            assert!(!token.erc20_transfer(accounts.bob, 5000));
+            assert_eq!(token.balance_of(accounts.bob), 100);
+
+            assert!(token.erc20_approve(accounts.bob, 300));
+            assert!(!token.erc20_approve(accounts.alice, 300));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert!(token.erc20_transfer_from(accounts.alice, accounts.charlie, 200));
+            assert_eq!(token.balance_of(accounts.charlie), 200);
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 100);
+        }
+
+        #[ink::test]
+        fn test_erc20_transfer_from_failure_keeps_allowance() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 100).unwrap();
+            token.approve(accounts.bob, 500).unwrap();
+
+            // A failed transfer must not consume allowance, since `false` doesn't revert
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert!(!token.erc20_transfer_from(accounts.alice, accounts.charlie, 200));
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 500);
+        }
+
+        #[ink::test]
+        fn test_pause_and_unpause() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            // Setup: mint tokens
+            token.mint(accounts.alice, 1000).unwrap();
+
+            // Initially not paused
+            assert!(!token.is_paused());
+            token.transfer(accounts.bob, 100, Vec::new()).unwrap();
+
+            // Pause contract
+            token.pause().unwrap();
+            assert!(token.is_paused());
+
+            // Transfers should fail when paused
+            assert_eq!(
+                token.transfer(accounts.bob, 100, Vec::new()),
+                Err(Error::ContractPaused.into())
+            );
+
+            // Unpause contract
+            token.unpause().unwrap();
+            assert!(!token.is_paused());
+
+            // Transfers should work again
+            token.transfer(accounts.bob, 100, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 200);
+        }
+
+        #[ink::test]
+        fn test_blacklist_and_unblacklist() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            // Setup: mint tokens
+            token.mint(accounts.alice, 1000).unwrap();
+
+            // Blacklist bob
+            token.blacklist(accounts.bob).unwrap();
+            assert!(token.is_blacklisted(accounts.bob));
+
+            // Transfer to blacklisted address should fail
+            assert_eq!(
+                token.transfer(accounts.bob, 100, Vec::new()),
+                Err(Error::AccountBlacklisted.into())
+            );
+
+            // Unblacklist bob
+            token.unblacklist(accounts.bob).unwrap();
+            assert!(!token.is_blacklisted(accounts.bob));
+
+            // Transfer should work now
+            token.transfer(accounts.bob, 100, Vec::new()).unwrap();
+            assert_eq!(token.balance_of(accounts.bob), 100);
+        }
+
+        #[ink::test]
+        fn test_blacklist_appeal() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+            let memo_hash = Hash::from([7u8; 32]);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.submit_appeal(memo_hash), Err(Error::NotBlacklisted));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.blacklist(accounts.bob).unwrap();
 
-            self.allowances.insert((owner, spender), &amount);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.submit_appeal(memo_hash).unwrap();
+            assert_eq!(token.submit_appeal(memo_hash), Err(Error::AppealPending));
+            assert_eq!(
+                token.resolve_appeal(accounts.bob, true),
+                Err(Error::Unauthorized)
+            );
 
-            self.env().emit_event(Approval {
-                owner,
-                spender,
-                value: amount,
-            });
+            // Rejected appeals can be resubmitted
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.resolve_appeal(accounts.bob, false).unwrap();
+            assert_eq!(
+                token.appeal_of(accounts.bob).unwrap().status,
+                AppealStatus::Rejected
+            );
+            assert!(token.is_blacklisted(accounts.bob));
 
-            Ok(())
-        }
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.submit_appeal(memo_hash).unwrap();
 
-        #[ink(message)]
-        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> u128 {
-            self.allowances.get((owner, spender)).unwrap_or(0)
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.resolve_appeal(accounts.bob, true).unwrap();
+            assert_eq!(
+                token.appeal_of(accounts.bob).unwrap().status,
+                AppealStatus::Approved
+            );
+            assert!(!token.is_blacklisted(accounts.bob));
+            assert_eq!(
+                token.resolve_appeal(accounts.bob, true),
+                Err(Error::AppealNotFound)
+            );
         }
 
-        #[ink(message)]
-        pub fn transfer_from(
-            &mut self,
-            from: AccountId,
-            to: AccountId,
-            amount: u128,
-        ) -> Result<()> {
-            self.when_not_paused()?;
+        #[ink::test]
+        fn test_blacklisted_accounts_pagination() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
 
-            let caller = self.env().caller();
-            self.not_blacklisted(from)?;
-            self.not_blacklisted(to)?;
-            self.not_blacklisted(caller)?;
+            token.blacklist(accounts.bob).unwrap();
+            token.blacklist(accounts.charlie).unwrap();
+            token.blacklist_until(accounts.django, 100).unwrap();
+            // Re-blacklisting doesn't duplicate the entry
+            token.blacklist(accounts.bob).unwrap();
+            assert_eq!(token.blacklist_count(), 3);
 
-            let current_allowance = self.allowance(from, caller);
+            let page: Vec<AccountId> = token
+                .blacklisted_accounts(0, 2)
+                .into_iter()
+                .map(|(account, _)| account)
+                .collect();
+            assert_eq!(page, ink::prelude::vec![accounts.bob, accounts.charlie]);
+            assert_eq!(token.blacklisted_accounts(2, 10).len(), 1);
+            assert!(token.blacklisted_accounts(5, 10).is_empty());
 
-            if current_allowance < amount {
-                return Err(Error::InsufficientAllowance);
-            }
+            // Removing swaps the last entry into the gap
+            token.unblacklist(accounts.bob).unwrap();
+            assert_eq!(token.blacklist_count(), 2);
+            assert_eq!(token.blacklisted_accounts(0, 1)[0].0, accounts.django);
 
-            let new_allowance = current_allowance
-                .checked_sub(amount)
-                .ok_or(Error::Overflow)?;
-            self.allowances.insert((from, caller), &new_allowance);
+            // Lapsed entries can be purged by anyone, active ones can't
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(
+                token.purge_expired_blacklist(accounts.django),
+                Err(Error::BlacklistEntryActive)
+            );
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(100);
+            token.purge_expired_blacklist(accounts.django).unwrap();
+            assert_eq!(token.blacklist_count(), 1);
+            assert_eq!(token.blacklisted_accounts(0, 10)[0].0, accounts.charlie);
+        }
 
-            self._transfer(from, to, amount)?;
+        #[ink::test]
+        fn test_blacklist_reason() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
 
-            Ok(())
-        }
+            token
+                .blacklist_with_reason(accounts.bob, BlacklistReason::Sanctions, None)
+                .unwrap();
+            assert_eq!(
+                token.blacklist_info(accounts.bob),
+                Some(BlacklistEntry {
+                    expires_at: None,
+                    reason: BlacklistReason::Sanctions,
+                })
+            );
 
-        #[ink(message)]
-        pub fn increase_allowance(&mut self, spender: AccountId, added_value: u128) -> Result<()> {
-            let owner = self.env().caller();
-            let current_allowance = self.allowance(owner, spender);
-            let new_allowance = current_allowance
-                .checked_add(added_value)
-                .ok_or(Error::Overflow)?;
+            token.blacklist(accounts.charlie).unwrap();
+            assert_eq!(
+                token.blacklist_info(accounts.charlie).unwrap().reason,
+                BlacklistReason::Unspecified
+            );
 
-            self.approve(spender, new_allowance)
+            token.unblacklist(accounts.bob).unwrap();
+            assert_eq!(token.blacklist_info(accounts.bob), None);
         }
 
-        #[ink(message)]
-        pub fn decrease_allowance(
-            &mut self,
-            spender: AccountId,
-            subtracted_value: u128,
-        ) -> Result<()> {
-            let owner = self.env().caller();
-            let current_allowance = self.allowance(owner, spender);
+        #[ink::test]
+        fn test_blacklist_expiry() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
 
-            if current_allowance < subtracted_value {
-                return Err(Error::InsufficientAllowance);
-            }
+            token.mint(accounts.alice, 1000).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(100);
 
-            let new_allowance = current_allowance
-                .checked_sub(subtracted_value)
-                .ok_or(Error::Overflow)?;
-            self.approve(spender, new_allowance)
-        }
+            // Expiry must be in the future
+            assert_eq!(
+                token.blacklist_until(accounts.bob, 100),
+                Err(Error::InvalidAmount)
+            );
 
-        #[ink(message)]
-        pub fn batch_transfer(&mut self, recipients: Vec<(AccountId, u128)>) -> Result<()> {
-            self.when_not_paused()?;
+            token.blacklist_until(accounts.bob, 1000).unwrap();
+            assert!(token.is_blacklisted(accounts.bob));
+            assert_eq!(
+                token.transfer(accounts.bob, 100, Vec::new()),
+                Err(Error::AccountBlacklisted.into())
+            );
 
-            let from = self.env().caller();
-            self.not_blacklisted(from)?;
+            // Lapses without an unblacklist call
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1000);
+            assert!(!token.is_blacklisted(accounts.bob));
+            token.transfer(accounts.bob, 100, Vec::new()).unwrap();
+        }
 
-            let mut total_amount: u128 = 0;
-            for (to, amount) in &recipients {
-                self.not_blacklisted(*to)?;
-                total_amount = total_amount.checked_add(*amount).ok_or(Error::Overflow)?;
-            }
+        #[ink::test]
+        fn test_blacklisted_sender() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
 
-            let from_balance = self.balance_of(from);
-            if from_balance < total_amount {
-                return Err(Error::InsufficientBalance);
-            }
+            // Setup: mint to bob
+            token.mint(accounts.bob, 500).unwrap();
 
-            for (to, amount) in recipients {
-                if amount > 0 {
-                    self._transfer(from, to, amount)?;
-                }
-            }
+            // Blacklist bob
+            token.blacklist(accounts.bob).unwrap();
 
-            Ok(())
+            // Bob cannot send tokens when blacklisted
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                token.transfer(accounts.charlie, 100, Vec::new()),
+                Err(Error::AccountBlacklisted.into())
+            );
         }
 
-        #[ink(message)]
-        pub fn pause(&mut self) -> Result<()> {
-            self.only_owner()?;
+        #[ink::test]
+        fn test_batch_transfer() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
 
-            if self.paused {
-                return Ok(());
-            }
+            // Setup: mint tokens to alice
+            token.mint(accounts.alice, 1000).unwrap();
 
-            self.paused = true;
+            // Batch transfer to multiple recipients
+            let recipients = ink::prelude::vec![
+                (accounts.bob, 100),
+                (accounts.charlie, 200),
+                (accounts.django, 150),
+            ];
 
-            self.env().emit_event(Paused { paused: true });
+            token.batch_transfer(recipients).unwrap();
 
-            Ok(())
+            assert_eq!(token.balance_of(accounts.alice), 550);
+            assert_eq!(token.balance_of(accounts.bob), 100);
+            assert_eq!(token.balance_of(accounts.charlie), 200);
+            assert_eq!(token.balance_of(accounts.django), 150);
         }
 
-        #[ink(message)]
-        pub fn unpause(&mut self) -> Result<()> {
-            self.only_owner()?;
-
-            if !self.paused {
-                return Ok(());
-            }
+        #[ink::test]
+        fn test_batch_transfer_insufficient_balance() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
 
-            self.paused = false;
+            // Setup: mint only 200 tokens
+            token.mint(accounts.alice, 200).unwrap();
 
-            self.env().emit_event(Paused { paused: false });
+            // Try to batch transfer more than balance
+            let recipients = ink::prelude::vec![
+                (accounts.bob, 100),
+                (accounts.charlie, 150), // Total: 250 > 200
+            ];
 
-            Ok(())
-        }
+            assert_eq!(
+                token.batch_transfer(recipients),
+                Err(Error::InsufficientBalance)
+            );
 
-        #[ink(message)]
-        pub fn is_paused(&self) -> bool {
-            self.paused
+            // Balances should remain unchanged (atomic operation)
+            assert_eq!(token.balance_of(accounts.alice), 200);
+            assert_eq!(token.balance_of(accounts.bob), 0);
+            assert_eq!(token.balance_of(accounts.charlie), 0);
         }
 
-        #[ink(message)]
-        pub fn blacklist(&mut self, account: AccountId) -> Result<()> {
-            self.only_owner()?;
-
-            if account == self.owner {
-                return Err(Error::Unauthorized);
-            }
+        #[ink::test]
+        fn test_batch_transfer_with_blacklisted_recipient() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
 
-            self.blacklist.insert(account, &true);
+            // Setup
+            token.mint(accounts.alice, 1000).unwrap();
+            token.blacklist(accounts.charlie).unwrap();
 
-            self.env().emit_event(BlacklistUpdated {
-                account,
-                blacklisted: true,
-            });
+            // Batch transfer with blacklisted recipient should fail
+            let recipients = ink::prelude::vec![
+                (accounts.bob, 100),
+                (accounts.charlie, 200), // Blacklisted!
+            ];
 
-            Ok(())
+            assert_eq!(
+                token.batch_transfer(recipients),
+                Err(Error::AccountBlacklisted)
+            );
         }
 
-        #[ink(message)]
-        pub fn unblacklist(&mut self, account: AccountId) -> Result<()> {
-            self.only_owner()?;
+        #[ink::test]
+        fn test_batch_mint() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
 
-            self.blacklist.insert(account, &false);
+            let recipients = ink::prelude::vec![
+                (accounts.bob, 100),
+                (accounts.charlie, 200),
+                (accounts.django, 0), // Skipped
+            ];
+            token.batch_mint(recipients).unwrap();
 
-            self.env().emit_event(BlacklistUpdated {
-                account,
-                blacklisted: false,
-            });
+            assert_eq!(token.balance_of(accounts.bob), 100);
+            assert_eq!(token.balance_of(accounts.charlie), 200);
+            assert_eq!(token.balance_of(accounts.django), 0);
+            assert_eq!(token.total_supply(), 300);
 
-            Ok(())
+            let events = ink::env::test::recorded_events().collect::<Vec<_>>();
+            let summary =
+                <BatchMinted as ink::scale::Decode>::decode(&mut &events[2].data[..]).unwrap();
+            assert_eq!(summary.recipients, 3);
+            assert_eq!(summary.total_amount, 300);
         }
 
-        #[ink(message)]
-        pub fn is_blacklisted(&self, account: AccountId) -> bool {
-            self.blacklist.get(account).unwrap_or(false)
-        }
+        #[ink::test]
+        fn test_batch_mint_is_atomic() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
 
-        #[ink(message)]
-        pub fn total_supply(&self) -> u128 {
-            self.total_supply
-        }
+            token.blacklist(accounts.charlie).unwrap();
 
-        #[ink(message)]
-        pub fn owner(&self) -> AccountId {
-            self.owner
+            let recipients = ink::prelude::vec![(accounts.bob, 100), (accounts.charlie, 200)];
+            assert_eq!(token.batch_mint(recipients), Err(Error::AccountBlacklisted));
+            assert_eq!(token.balance_of(accounts.bob), 0);
+
+            let recipients = ink::prelude::vec![(accounts.bob, u128::MAX), (accounts.django, 1)];
+            assert_eq!(token.batch_mint(recipients), Err(Error::Overflow));
+            assert_eq!(token.total_supply(), 0);
         }
 
-        #[ink(message)]
-        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<()> {
-            self.only_owner()?;
+        #[ink::test]
+        fn test_unauthorized_batch_mint() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
 
-            let old_owner = self.owner;
-            self.owner = new_owner;
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let recipients = ink::prelude::vec![(accounts.bob, 100)];
+            assert_eq!(token.batch_mint(recipients), Err(Error::Unauthorized));
+        }
 
-            self.env().emit_event(OwnershipTransferred {
-                previous_owner: old_owner,
-                new_owner,
-            });
+        #[ink::test]
+        fn test_ownership_transfer() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
 
-            Ok(())
-        }
-    }
+            // Alice is initial owner
+            assert_eq!(token.owner(), accounts.alice);
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
+            // Transfer ownership to bob
+            token.transfer_ownership(accounts.bob).unwrap();
+            assert_eq!(token.owner(), accounts.bob);
 
-        fn get_accounts() -> ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment> {
-            ink::env::test::default_accounts::<ink::env::DefaultEnvironment>()
+            // Old owner cannot mint
+            assert_eq!(token.mint(accounts.charlie, 100), Err(Error::Unauthorized));
+
+            // New owner can mint
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.mint(accounts.charlie, 100).unwrap();
+            assert_eq!(token.balance_of(accounts.charlie), 100);
         }
 
         #[ink::test]
-        fn test_mint() {
+        fn test_timelocked_large_mint() {
             let mut token = Token::new();
             let accounts = get_accounts();
 
-            assert_eq!(token.balance_of(accounts.bob), 0);
-            assert_eq!(token.total_supply(), 0);
+            token.set_timelock(1000, 500).unwrap();
 
-            // Mint tokens
-            token.mint(accounts.bob, 1000).unwrap();
+            // Small mints stay immediate, large ones must be scheduled
+            token.mint(accounts.bob, 500).unwrap();
+            assert_eq!(token.mint(accounts.bob, 501), Err(Error::TimelockRequired));
 
-            assert_eq!(token.balance_of(accounts.bob), 1000);
-            assert_eq!(token.total_supply(), 1000);
+            let id = token
+                .schedule_admin_action(AdminAction::Mint {
+                    to: accounts.bob,
+                    amount: 1000,
+                })
+                .unwrap();
+            assert_eq!(token.scheduled_operation(id).unwrap().eta, 1000);
+            assert_eq!(token.execute_scheduled(id), Err(Error::OperationNotReady));
 
-            // Mint more to same account
-            token.mint(accounts.bob, 500).unwrap();
+            // Anyone can execute once the delay has passed
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            token.execute_scheduled(id).unwrap();
             assert_eq!(token.balance_of(accounts.bob), 1500);
-            assert_eq!(token.total_supply(), 1500);
+            assert_eq!(token.execute_scheduled(id), Err(Error::OperationNotFound));
         }
 
         #[ink::test]
-        fn test_burn() {
+        fn test_timelocked_ownership_and_cancel() {
             let mut token = Token::new();
             let accounts = get_accounts();
 
-            // Setup: mint some tokens
-            token.mint(accounts.alice, 1000).unwrap();
+            token.set_timelock(1000, 0).unwrap();
+            assert_eq!(
+                token.transfer_ownership(accounts.bob),
+                Err(Error::TimelockRequired)
+            );
+            assert_eq!(token.set_cap(100), Err(Error::TimelockRequired));
+            assert_eq!(token.set_timelock(0, 0), Err(Error::TimelockRequired));
 
-            // Burn tokens
-            token.burn(300).unwrap();
+            let id = token
+                .schedule_admin_action(AdminAction::TransferOwnership {
+                    new_owner: accounts.bob,
+                })
+                .unwrap();
 
-            assert_eq!(token.balance_of(accounts.alice), 700);
-            assert_eq!(token.total_supply(), 700);
+            // Only the owner can cancel
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.cancel_scheduled(id), Err(Error::Unauthorized));
 
-            // Burn more
-            token.burn(200).unwrap();
-            assert_eq!(token.balance_of(accounts.alice), 500);
-            assert_eq!(token.total_supply(), 500);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.cancel_scheduled(id).unwrap();
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1000);
+            assert_eq!(token.execute_scheduled(id), Err(Error::OperationNotFound));
+            assert_eq!(token.owner(), accounts.alice);
         }
 
         #[ink::test]
-        fn test_transfer() {
+        fn test_multisig_proposal_goes_through_timelock() {
             let mut token = Token::new();
             let accounts = get_accounts();
 
-            // Setup: mint tokens to alice
-            token.mint(accounts.alice, 1000).unwrap();
-
-            // Transfer to bob
-            token.transfer(accounts.bob, 300).unwrap();
-
-            assert_eq!(token.balance_of(accounts.alice), 700);
-            assert_eq!(token.balance_of(accounts.bob), 300);
-            assert_eq!(token.total_supply(), 1000); // Total unchanged
+            token.set_timelock(1000, 0).unwrap();
+            token
+                .set_admins(ink::prelude::vec![accounts.alice, accounts.bob], 2, 5000)
+                .unwrap();
 
-            // Transfer to charlie
-            token.transfer(accounts.charlie, 200).unwrap();
+            let id = token
+                .propose_admin_action(AdminAction::SetCap { cap: 100 })
+                .unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.confirm_admin_action(id).unwrap();
 
-            assert_eq!(token.balance_of(accounts.alice), 500);
-            assert_eq!(token.balance_of(accounts.charlie), 200);
+            // Approved, but queued rather than applied
+            assert_eq!(token.cap(), None);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1000);
+            token.execute_scheduled(0).unwrap();
+            assert_eq!(token.cap(), Some(100));
         }
 
         #[ink::test]
-        fn test_approve_and_transfer_from() {
+        fn test_multisig_admin_actions() {
             let mut token = Token::new();
             let accounts = get_accounts();
 
-            // Setup: mint tokens to alice
-            token.mint(accounts.alice, 1000).unwrap();
+            token
+                .set_admins(
+                    ink::prelude::vec![accounts.alice, accounts.bob, accounts.charlie],
+                    2,
+                    1000,
+                )
+                .unwrap();
 
-            // Alice approves bob to spend 300
-            token.approve(accounts.bob, 300).unwrap();
-            assert_eq!(token.allowance(accounts.alice, accounts.bob), 300);
+            // Direct privileged calls are disabled
+            assert_eq!(
+                token.mint(accounts.django, 100),
+                Err(Error::MultisigRequired)
+            );
+            assert_eq!(token.pause(), Err(Error::MultisigRequired));
+            assert_eq!(
+                token.transfer_ownership(accounts.bob),
+                Err(Error::MultisigRequired)
+            );
 
-            // Bob transfers from alice to charlie
-            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
-            token
-                .transfer_from(accounts.alice, accounts.charlie, 200)
+            let id = token
+                .propose_admin_action(AdminAction::Mint {
+                    to: accounts.django,
+                    amount: 100,
+                })
                 .unwrap();
+            assert_eq!(token.balance_of(accounts.django), 0);
+            assert_eq!(token.confirm_admin_action(id), Err(Error::AlreadyConfirmed));
 
-            assert_eq!(token.balance_of(accounts.alice), 800);
-            assert_eq!(token.balance_of(accounts.charlie), 200);
-            assert_eq!(token.allowance(accounts.alice, accounts.bob), 100); // Decreased
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.confirm_admin_action(id).unwrap();
+            assert_eq!(token.balance_of(accounts.django), 100);
+            assert!(token.proposal(id).unwrap().executed);
+
+            // Executed proposals can't be replayed
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(token.confirm_admin_action(id), Err(Error::ProposalNotFound));
+
+            // Non-admins can't take part
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(
+                token.propose_admin_action(AdminAction::Pause),
+                Err(Error::Unauthorized)
+            );
         }
 
         #[ink::test]
-        fn test_increase_decrease_allowance() {
+        fn test_multisig_proposal_expiry() {
             let mut token = Token::new();
             let accounts = get_accounts();
 
-            // Initial approval
-            token.approve(accounts.bob, 100).unwrap();
-            assert_eq!(token.allowance(accounts.alice, accounts.bob), 100);
-
-            // Increase allowance
-            token.increase_allowance(accounts.bob, 50).unwrap();
-            assert_eq!(token.allowance(accounts.alice, accounts.bob), 150);
+            token
+                .set_admins(ink::prelude::vec![accounts.alice, accounts.bob], 2, 1000)
+                .unwrap();
+            let id = token.propose_admin_action(AdminAction::Pause).unwrap();
 
-            // Decrease allowance
-            token.decrease_allowance(accounts.bob, 30).unwrap();
-            assert_eq!(token.allowance(accounts.alice, accounts.bob), 120);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.confirm_admin_action(id), Err(Error::ProposalExpired));
+            assert!(!token.is_paused());
         }
 
         #[ink::test]
-        fn test_pause_and_unpause() {
+        fn test_set_admins_validation() {
             let mut token = Token::new();
             let accounts = get_accounts();
 
-            // Setup: mint tokens
-            token.mint(accounts.alice, 1000).unwrap();
-
-            // Initially not paused
-            assert!(!token.is_paused());
-            token.transfer(accounts.bob, 100).unwrap();
+            assert_eq!(
+                token.set_admins(ink::prelude::vec![accounts.alice], 2, 1000),
+                Err(Error::InvalidThreshold)
+            );
+            assert_eq!(
+                token.set_admins(ink::prelude::vec![accounts.alice, accounts.alice], 2, 1000),
+                Err(Error::InvalidThreshold)
+            );
+            assert_eq!(
+                token.set_admins(ink::prelude::vec![accounts.alice], 0, 1000),
+                Err(Error::InvalidThreshold)
+            );
 
-            // Pause contract
-            token.pause().unwrap();
-            assert!(token.is_paused());
+            token
+                .set_admins(ink::prelude::vec![accounts.alice, accounts.bob], 2, 1000)
+                .unwrap();
 
-            // Transfers should fail when paused
+            // Reconfiguring also needs the multisig
             assert_eq!(
-                token.transfer(accounts.bob, 100),
-                Err(Error::ContractPaused)
+                token.set_admins(ink::prelude::vec![accounts.alice], 1, 1000),
+                Err(Error::MultisigRequired)
             );
+            let id = token
+                .propose_admin_action(AdminAction::SetAdmins {
+                    admins: ink::prelude::vec![accounts.alice],
+                    threshold: 1,
+                })
+                .unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.confirm_admin_action(id).unwrap();
+            assert_eq!(token.admins(), ink::prelude::vec![accounts.alice]);
+            assert_eq!(token.admin_threshold(), 1);
+        }
 
-            // Unpause contract
-            token.unpause().unwrap();
+        #[ink::test]
+        fn test_pause_minting_keeps_transfers_running() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 1000).unwrap();
+            token.pause_minting().unwrap();
+            assert!(token.is_scope_paused(PauseScope::Minting));
             assert!(!token.is_paused());
 
-            // Transfers should work again
-            token.transfer(accounts.bob, 100).unwrap();
-            assert_eq!(token.balance_of(accounts.bob), 200);
+            assert_eq!(token.mint(accounts.bob, 100), Err(Error::ContractPaused));
+            token.transfer(accounts.bob, 100, Vec::new()).unwrap();
+            token.burn(100).unwrap();
+            token.approve(accounts.bob, 100).unwrap();
+
+            token.unpause_minting().unwrap();
+            token.mint(accounts.bob, 100).unwrap();
         }
 
         #[ink::test]
-        fn test_blacklist_and_unblacklist() {
+        fn test_pause_scopes_are_independent() {
             let mut token = Token::new();
             let accounts = get_accounts();
 
-            // Setup: mint tokens
             token.mint(accounts.alice, 1000).unwrap();
+            token.pause_transfers().unwrap();
+            token.pause_burning().unwrap();
+            token.pause_approvals().unwrap();
 
-            // Blacklist bob
-            token.blacklist(accounts.bob).unwrap();
-            assert!(token.is_blacklisted(accounts.bob));
-
-            // Transfer to blacklisted address should fail
             assert_eq!(
-                token.transfer(accounts.bob, 100),
-                Err(Error::AccountBlacklisted)
+                token.transfer(accounts.bob, 100, Vec::new()),
+                Err(Error::ContractPaused.into())
             );
+            assert_eq!(token.burn(100), Err(Error::ContractPaused));
+            assert_eq!(
+                token.approve(accounts.bob, 100),
+                Err(Error::ContractPaused.into())
+            );
+            token.mint(accounts.bob, 100).unwrap();
 
-            // Unblacklist bob
-            token.unblacklist(accounts.bob).unwrap();
-            assert!(!token.is_blacklisted(accounts.bob));
-
-            // Transfer should work now
-            token.transfer(accounts.bob, 100).unwrap();
-            assert_eq!(token.balance_of(accounts.bob), 100);
+            // Only the owner can flip the switches
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.unpause_transfers(), Err(Error::Unauthorized));
         }
 
         #[ink::test]
-        fn test_blacklisted_sender() {
+        fn test_circuit_breaker_trips_on_transfer_volume() {
             let mut token = Token::new();
             let accounts = get_accounts();
 
-            // Setup: mint to bob
-            token.mint(accounts.bob, 500).unwrap();
+            token.mint(accounts.alice, 10_000).unwrap();
+            token.set_circuit_breaker(1000, 0, 500).unwrap();
 
-            // Blacklist bob
-            token.blacklist(accounts.bob).unwrap();
+            token.transfer(accounts.bob, 300, Vec::new()).unwrap();
+            assert!(!token.is_paused());
 
-            // Bob cannot send tokens when blacklisted
-            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            // The transfer that crosses the threshold goes through, then everything stops
+            token.transfer(accounts.bob, 300, Vec::new()).unwrap();
+            assert!(token.is_paused());
+            assert!(token.is_scope_paused(PauseScope::Minting));
+            assert_eq!(token.window_volume(), (0, 600));
             assert_eq!(
-                token.transfer(accounts.charlie, 100),
-                Err(Error::AccountBlacklisted)
+                token.transfer(accounts.bob, 1, Vec::new()),
+                Err(Error::ContractPaused.into())
             );
+            assert_eq!(token.mint(accounts.bob, 1), Err(Error::ContractPaused));
+
+            // Unpausing starts a fresh window
+            token.unpause().unwrap();
+            token.unpause_minting().unwrap();
+            assert_eq!(token.window_volume(), (0, 0));
+            token.transfer(accounts.bob, 400, Vec::new()).unwrap();
+            assert!(!token.is_paused());
         }
 
         #[ink::test]
-        fn test_batch_transfer() {
+        fn test_circuit_breaker_window_resets() {
             let mut token = Token::new();
             let accounts = get_accounts();
 
-            // Setup: mint tokens to alice
-            token.mint(accounts.alice, 1000).unwrap();
-
-            // Batch transfer to multiple recipients
-            let recipients = ink::prelude::vec![
-                (accounts.bob, 100),
-                (accounts.charlie, 200),
-                (accounts.django, 150),
-            ];
+            token.set_circuit_breaker(1000, 500, 0).unwrap();
+            token.mint(accounts.bob, 400).unwrap();
 
-            token.batch_transfer(recipients).unwrap();
+            // Volume from the previous window no longer counts
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1000);
+            token.mint(accounts.bob, 400).unwrap();
+            assert!(!token.is_paused());
+            assert_eq!(token.window_volume(), (400, 0));
 
-            assert_eq!(token.balance_of(accounts.alice), 550);
-            assert_eq!(token.balance_of(accounts.bob), 100);
-            assert_eq!(token.balance_of(accounts.charlie), 200);
-            assert_eq!(token.balance_of(accounts.django), 150);
+            token.mint(accounts.bob, 101).unwrap();
+            assert!(token.is_paused());
         }
 
         #[ink::test]
-        fn test_batch_transfer_insufficient_balance() {
+        fn test_guardian_can_only_pause() {
             let mut token = Token::new();
             let accounts = get_accounts();
 
-            // Setup: mint only 200 tokens
-            token.mint(accounts.alice, 200).unwrap();
+            token.mint(accounts.alice, 1000).unwrap();
+            token.set_guardian(Some(accounts.bob)).unwrap();
+            assert_eq!(token.guardian(), Some(accounts.bob));
 
-            // Try to batch transfer more than balance
-            let recipients = ink::prelude::vec![
-                (accounts.bob, 100),
-                (accounts.charlie, 150), // Total: 250 > 200
-            ];
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            token.pause().unwrap();
+            assert!(token.is_paused());
 
+            // Nothing else is delegated
+            assert_eq!(token.unpause(), Err(Error::Unauthorized));
+            assert_eq!(token.mint(accounts.bob, 1), Err(Error::Unauthorized));
+            assert_eq!(token.blacklist(accounts.charlie), Err(Error::Unauthorized));
             assert_eq!(
-                token.batch_transfer(recipients),
-                Err(Error::InsufficientBalance)
+                token.set_guardian(Some(accounts.charlie)),
+                Err(Error::Unauthorized)
             );
 
-            // Balances should remain unchanged (atomic operation)
-            assert_eq!(token.balance_of(accounts.alice), 200);
-            assert_eq!(token.balance_of(accounts.bob), 0);
-            assert_eq!(token.balance_of(accounts.charlie), 0);
+            // Owner can remove the guardian
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            token.unpause().unwrap();
+            token.set_guardian(None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(token.pause(), Err(Error::Unauthorized));
         }
 
         #[ink::test]
-        fn test_batch_transfer_with_blacklisted_recipient() {
+        fn test_renounce_ownership() {
             let mut token = Token::new();
             let accounts = get_accounts();
 
-            // Setup
             token.mint(accounts.alice, 1000).unwrap();
-            token.blacklist(accounts.charlie).unwrap();
 
-            // Batch transfer with blacklisted recipient should fail
-            let recipients = ink::prelude::vec![
-                (accounts.bob, 100),
-                (accounts.charlie, 200), // Blacklisted!
-            ];
+            // Confirmation must match the owner
+            assert_eq!(
+                token.renounce_ownership(accounts.bob),
+                Err(Error::Unauthorized)
+            );
 
+            token.renounce_ownership(accounts.alice).unwrap();
+            assert!(token.is_renounced());
+            assert_eq!(token.owner(), AccountId::from([0u8; 32]));
+
+            assert_eq!(token.mint(accounts.bob, 1), Err(Error::OwnershipRenounced));
+            assert_eq!(token.pause(), Err(Error::OwnershipRenounced));
             assert_eq!(
-                token.batch_transfer(recipients),
-                Err(Error::AccountBlacklisted)
+                token.blacklist(accounts.bob),
+                Err(Error::OwnershipRenounced)
+            );
+            assert_eq!(
+                token.transfer_ownership(accounts.alice),
+                Err(Error::OwnershipRenounced)
             );
+
+            // Token keeps working for holders
+            token.transfer(accounts.bob, 100, Vec::new()).unwrap();
         }
 
         #[ink::test]
-        fn test_ownership_transfer() {
+        fn test_renounce_ownership_when_paused_fails() {
             let mut token = Token::new();
             let accounts = get_accounts();
 
-            // Alice is initial owner
-            assert_eq!(token.owner(), accounts.alice);
-
-            // Transfer ownership to bob
-            token.transfer_ownership(accounts.bob).unwrap();
-            assert_eq!(token.owner(), accounts.bob);
-
-            // Old owner cannot mint
-            assert_eq!(token.mint(accounts.charlie, 100), Err(Error::Unauthorized));
-
-            // New owner can mint
-            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
-            token.mint(accounts.charlie, 100).unwrap();
-            assert_eq!(token.balance_of(accounts.charlie), 100);
+            token.pause().unwrap();
+            assert_eq!(
+                token.renounce_ownership(accounts.alice),
+                Err(Error::ContractPaused)
+            );
+            assert!(!token.is_renounced());
         }
 
         #[ink::test]
@@ -737,7 +17939,10 @@ mod token {
             let accounts = get_accounts();
 
             token.mint(accounts.alice, 100).unwrap();
-            assert_eq!(token.transfer(accounts.bob, 0), Err(Error::InvalidAmount));
+            assert_eq!(
+                token.transfer(accounts.bob, 0, Vec::new()),
+                Err(Error::InvalidAmount.into())
+            );
         }
 
         #[ink::test]
@@ -756,8 +17961,8 @@ mod token {
 
             token.mint(accounts.alice, 100).unwrap();
             assert_eq!(
-                token.transfer(accounts.bob, 200),
-                Err(Error::InsufficientBalance)
+                token.transfer(accounts.bob, 200, Vec::new()),
+                Err(PSP22Error::InsufficientBalance)
             );
         }
 
@@ -772,8 +17977,8 @@ mod token {
             // Bob tries to transfer more than allowance
             ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
             assert_eq!(
-                token.transfer_from(accounts.alice, accounts.charlie, 200),
-                Err(Error::InsufficientAllowance)
+                token.transfer_from(accounts.alice, accounts.charlie, 200, Vec::new()),
+                Err(PSP22Error::InsufficientAllowance)
             );
         }
 
@@ -782,7 +17987,38 @@ mod token {
             let mut token = Token::new();
             let accounts = get_accounts();
 
-            assert_eq!(token.approve(accounts.alice, 100), Err(Error::SelfApproval));
+            assert_eq!(
+                token.approve(accounts.alice, 100),
+                Err(Error::SelfApproval.into())
+            );
+        }
+
+        #[ink::test]
+        fn test_psp22_custom_error_mapping() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.mint(accounts.alice, 100).unwrap();
+            token.pause().unwrap();
+
+            // Non-PSP22 errors are reported as Custom with the variant name
+            assert_eq!(
+                token.transfer(accounts.bob, 10, Vec::new()),
+                Err(PSP22Error::Custom(String::from("ContractPaused")))
+            );
+        }
+
+        #[ink::test]
+        fn test_decrease_allowance_below_zero_fails() {
+            let mut token = Token::new();
+            let accounts = get_accounts();
+
+            token.approve(accounts.bob, 50).unwrap();
+            assert_eq!(
+                token.decrease_allowance(accounts.bob, 51),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 50);
         }
 
         #[ink::test]